@@ -1,10 +1,12 @@
+import datetime
 import os
 
 import pandas as pd
+import pyarrow as pa
 import pytest
 from pandas.testing import assert_frame_equal
 
-from .. import read_sql
+from .. import read_sql, read_sql_batches
 
 
 @pytest.fixture(scope="module")  # type: ignore
@@ -267,6 +269,22 @@ def test_read_sql_with_partition_and_spja(postgres_url: str) -> None:
     assert_frame_equal(df, expected, check_names=True)
 
 
+def test_read_sql_with_partition_on_ctid(postgres_url: str) -> None:
+    query = "SELECT * FROM test_table WHERE test_int < 1000"
+    df = read_sql(postgres_url, query, partition_on="ctid", partition_num=3)
+    expected = pd.DataFrame(
+        index=range(5),
+        data={
+            "test_int": pd.Series([1, 2, 0, 3, 4], dtype="Int64"),
+            "test_nullint": pd.Series([3, None, 5, 7, 9], dtype="Int64"),
+            "test_str": pd.Series(["str1", "str2", "a", "b", "c"], dtype="object"),
+            "test_float": pd.Series([None, 2.2, 3.1, 3, 7.8], dtype="float64"),
+            "test_bool": pd.Series([True, False, None, False, None], dtype="boolean"),
+        },
+    )
+    assert_frame_equal(df, expected, check_names=True)
+
+
 def test_read_sql_on_utf8(postgres_url: str) -> None:
     query = "SELECT * FROM test_str"
     df = read_sql(postgres_url, query)
@@ -376,6 +394,121 @@ def test_types_csv(postgres_url: str) -> None:
     )
     assert_frame_equal(df, expected, check_names=True)
 
+def test_types_pre_epoch_timestamp(postgres_url: str) -> None:
+    query = "SELECT '1960-06-15 12:30:00+00'::timestamptz AS test_ts"
+    df = read_sql(postgres_url, query)
+    expected = pd.DataFrame(
+        index=range(1),
+        data={
+            "test_ts": pd.Series(["1960-06-15 12:30:00"], dtype="datetime64[ns]"),
+        },
+    )
+    assert_frame_equal(df, expected, check_names=True)
+
+
+def test_types_timestamp_out_of_range(postgres_url: str) -> None:
+    for ts in ["1500-01-01 00:00:00+00", "2300-01-01 00:00:00+00"]:
+        query = f"SELECT '{ts}'::timestamptz AS test_ts"
+        with pytest.raises(RuntimeError, match="out of the datetime64\\[ns\\] range"):
+            read_sql(postgres_url, query)
+
+def test_types_objects(postgres_url: str) -> None:
+    # the types pandas has no dtype for come as the python values they hold
+    query = "SELECT point(1.5, 2) AS p, '18446744073709551615'::xid8 AS x, '10:20:10,14,15'::pg_snapshot AS s, string_to_array('a|*', '|', '*') AS arr"
+    df = read_sql(postgres_url, query)
+    assert list(df.dtypes) == ["object"] * 4
+    assert df["p"][0] == (1.5, 2.0)
+    assert df["x"][0] == 2 ** 64 - 1
+    assert df["s"][0] == {"xmin": 10, "xmax": 20, "xip": [10, 14, 15]}
+    assert df["arr"][0] == ["a", None]
+
+    query = "SELECT row('ann', 1.5, 'happy', row('main st', 12345))::test_person AS p UNION ALL SELECT null::test_person"
+    df = read_sql(postgres_url, query)
+    assert df["p"][0] == {"name": "ann", "score": 1.5, "mood": "happy", "home": {"street": "main st", "zip": 12345}}
+    assert df["p"][1] is None
+
+    # a circle is ((x, y), radius)
+    query = "SELECT circle(point(1.5, 2), 3) AS c UNION ALL SELECT null::circle"
+    df = read_sql(postgres_url, query)
+    assert df["c"][0] == ((1.5, 2.0), 3.0)
+    assert df["c"][1] is None
+
+    # a tid is (block, index)
+    query = "SELECT '(3,7)'::tid AS t UNION ALL SELECT null::tid"
+    df = read_sql(postgres_url, query)
+    assert df["t"][0] == (3, 7)
+    assert df["t"][1] is None
+
+    query = "SELECT '{[1,3), [5,7]}'::int4multirange AS r UNION ALL SELECT '{}'::int4multirange UNION ALL SELECT null::int4multirange"
+    df = read_sql(postgres_url, query)
+    # a multirange is a list of (lower, upper, lower_inc, upper_inc), int ranges canonical
+    assert df["r"][0] == [(1, 3, True, False), (5, 8, True, False)]
+    assert df["r"][1] == []
+    assert df["r"][2] is None
+
+    query = "SELECT '{[2021-01-01 00:00+00, 2021-01-02 00:00+00), (2021-03-01 12:30+00,)}'::tstzmultirange AS r"
+    df = read_sql(postgres_url, query)
+    utc = datetime.timezone.utc
+    assert df["r"][0] == [
+        (datetime.datetime(2021, 1, 1, tzinfo=utc), datetime.datetime(2021, 1, 2, tzinfo=utc), True, False),
+        (datetime.datetime(2021, 3, 1, 12, 30, tzinfo=utc), None, False, False),
+    ]
+
+def test_datetime_block_shapes(postgres_url: str) -> None:
+    # pandas keeps all the datetime columns of a frame in one block, so these are
+    # blocks of 1x1, 1xN (one row, N columns), Nx1 and 0xN
+    ts = "'2021-01-0{}'::timestamp"
+
+    df = read_sql(postgres_url, f"SELECT {ts.format(1)} AS a")
+    assert df.shape == (1, 1)
+    assert list(df["a"]) == [pd.Timestamp("2021-01-01")]
+
+    df = read_sql(postgres_url, f"SELECT {ts.format(1)} AS a, {ts.format(2)} AS b, {ts.format(3)} AS c")
+    assert df.shape == (1, 3)
+    assert list(df.iloc[0]) == [pd.Timestamp(f"2021-01-0{i}") for i in range(1, 4)]
+
+    query = "SELECT '2021-01-01'::timestamp + test_int * interval '1 day' AS a FROM test_table WHERE test_int < 5"
+    df = read_sql(postgres_url, query, partition_on="test_int", partition_num=3)
+    assert df.shape == (5, 1)
+    assert sorted(df["a"]) == [pd.Timestamp(f"2021-01-0{i}") for i in range(1, 6)]
+
+    # the metadata query takes the column types from the one row it reads in place of the
+    # LIMIT 0, so this is a 0x2 datetime block (WHERE false would read strings)
+    df = read_sql(postgres_url, f"SELECT {ts.format(1)} AS a, {ts.format(2)} AS b LIMIT 0")
+    assert df.shape == (0, 2)
+    assert list(df.dtypes) == ["datetime64[ns]", "datetime64[ns]"]
+
+def test_max_cell_bytes(postgres_url: str) -> None:
+    query = "SELECT 'short' AS test_str, repeat('好', 10) AS test_long, decode(repeat('ab', 20), 'hex') AS test_bytea"
+
+    df = read_sql(postgres_url, query, max_cell_bytes=8, oversized_cells="truncate")
+    expected = pd.DataFrame(
+        index=range(1),
+        data={
+            "test_str": pd.Series(["short"], dtype="object"),
+            "test_long": pd.Series(["好好"], dtype="object"),
+            "test_bytea": pd.Series([b"\xab" * 8], dtype="object"),
+        },
+    )
+    assert_frame_equal(df, expected, check_names=True)
+
+    df = read_sql(postgres_url, query, max_cell_bytes=8, oversized_cells="null")
+    expected = pd.DataFrame(
+        index=range(1),
+        data={
+            "test_str": pd.Series(["short"], dtype="object"),
+            "test_long": pd.Series([None], dtype="object"),
+            "test_bytea": pd.Series([None], dtype="object"),
+        },
+    )
+    assert_frame_equal(df, expected, check_names=True)
+
+    with pytest.raises(RuntimeError, match="row 0 of its partition in column 'test_long'"):
+        read_sql(postgres_url, query, max_cell_bytes=8, oversized_cells="error")
+
+    with pytest.raises(RuntimeError, match="oversized cell policy"):
+        read_sql(postgres_url, query, max_cell_bytes=8, oversized_cells="drop")
+
 def test_empty_result(postgres_url: str) -> None:
     query = "SELECT * FROM test_table where test_int < -100"
     df = read_sql(postgres_url, query)
@@ -403,3 +536,56 @@ def test_empty_result_on_some_partition(postgres_url: str) -> None:
         }
     )
     assert_frame_equal(df, expected, check_names=True)
+
+def test_read_sql_batches(postgres_url: str) -> None:
+    query = "SELECT test_int, test_nullint, test_str, test_float, test_bool, to_timestamp(test_int * 86400) AS test_ts FROM test_table"
+    batches = list(
+        read_sql_batches(
+            postgres_url, query, partition_on="test_int", partition_num=3, batch_size=2
+        )
+    )
+    assert len(batches) > 3
+    assert all(batch.num_rows <= 2 for batch in batches)
+
+    table = pa.Table.from_batches(batches)
+    assert table.schema.field("test_ts").type == pa.timestamp("ms", tz="UTC")
+    streamed = table.to_pydict()
+    epoch = datetime.datetime(1970, 1, 1, tzinfo=datetime.timezone.utc)
+    assert streamed.pop("test_ts") == [
+        epoch + datetime.timedelta(days=i) for i in streamed["test_int"]
+    ]
+    streamed = sorted(zip(*streamed.values()))
+    df = read_sql(postgres_url, query, partition_on="test_int", partition_num=3)
+    df = df.drop(columns="test_ts")
+    df = df.astype(object).where(df.notna(), None)
+    assert streamed == sorted(df.itertuples(index=False, name=None))
+
+
+def test_read_sql_batches_partition_order(postgres_url: str) -> None:
+    queries = [
+        "SELECT test_int FROM test_table WHERE test_int >= 3 ORDER BY test_int",
+        "SELECT test_int FROM test_table WHERE test_int < 3 ORDER BY test_int",
+    ]
+    ints = [
+        v
+        for batch in read_sql_batches(postgres_url, queries)
+        for v in batch.column(0).to_pylist()
+    ]
+    df = read_sql(postgres_url, queries)
+    assert ints == df["test_int"].tolist()
+
+
+def test_arrow_dtypes(postgres_url: str) -> None:
+    query = "SELECT test_int, test_nullint, test_bool FROM test_table ORDER BY test_int"
+    df = read_sql(postgres_url, query, arrow_dtypes=True)
+    assert df["test_nullint"].dtype == pd.ArrowDtype(pa.int64())
+    assert df["test_bool"].dtype == pd.ArrowDtype(pa.bool_())
+    assert df["test_nullint"].isna().tolist() == [False, False, True, False, False, False]
+    assert df["test_nullint"].dropna().tolist() == [5, 3, 7, 9, 2]
+    assert df["test_bool"].isna().tolist() == [True, False, False, False, True, False]
+
+    query = "SELECT '2021-03-04 05:06:07.891+02'::timestamptz AS ts UNION ALL SELECT null::timestamptz"
+    df = read_sql(postgres_url, query, arrow_dtypes=True)
+    assert df["ts"].dtype == pd.ArrowDtype(pa.timestamp("ms", tz="UTC"))
+    assert df["ts"].isna().tolist() == [False, True]
+    assert df["ts"][0] == pd.Timestamp("2021-03-04 03:06:07.891", tz="UTC")
@@ -18,6 +18,8 @@ pub fn run(nq: usize, conn: &str) {
             None,
             None,
             Some(PartitionQuery::new(QUERY, "L_ORDERKEY", None, None, nq)),
+            None,
+            None,
         )
         .unwrap();
     });
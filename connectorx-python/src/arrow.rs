@@ -0,0 +1,188 @@
+use crate::errors::ConnectorAgentPythonError;
+use anyhow::anyhow;
+use arrow::array::{ArrayData, ArrayRef, Int64Array};
+use arrow::datatypes::{DataType, TimeUnit};
+use arrow::ffi::ArrowArray;
+use arrow::record_batch::RecordBatch;
+use connectorx::source_router::{SourceConn, SourceType};
+use connectorx::{
+    sources::postgres::{Binary, PostgresSource},
+    transports::PostgresArrowTransport,
+    ConnectorAgentError, Dispatcher,
+};
+use fehler::{throw, throws};
+use pyo3::prelude::*;
+use pyo3::PyIterProtocol;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Batch = connectorx::Result<RecordBatch>;
+
+// how many batches the reading thread gets ahead of the python side
+const BUFFERED_BATCHES: usize = 1;
+
+/// An iterator over pyarrow RecordBatches, read on a background thread.
+///
+/// The queries are read one after another, so the batches come out partition by partition,
+/// in the order of the queries, and in row order within each partition. Only one partition
+/// is read at a time, which bounds the memory to a partition plus `BUFFERED_BATCHES`. If the
+/// batches are not consumed, the reading thread waits with the partition in memory.
+#[pyclass]
+pub struct ArrowBatchStream {
+    batches: Mutex<Receiver<Batch>>,
+}
+
+#[pyproto]
+impl PyIterProtocol for ArrowBatchStream {
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __next__(slf: PyRef<Self>) -> PyResult<Option<PyObject>> {
+        let py = slf.py();
+        let batches = &slf.batches;
+        // the reading thread is gone once it has sent everything, or the error that stopped it
+        let next = py.allow_threads(|| {
+            batches
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .recv()
+                .ok()
+        });
+        match next {
+            Some(batch) => {
+                let batch = batch.map_err(ConnectorAgentPythonError::from)?;
+                Ok(Some(to_pyarrow(py, batch)?))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[throws(ConnectorAgentPythonError)]
+pub fn stream_arrow(
+    source_conn: &SourceConn,
+    queries: &[&str],
+    protocol: &str,
+    batch_size: Option<usize>,
+) -> ArrowBatchStream {
+    match (&source_conn.ty, protocol) {
+        (SourceType::Postgres, "binary") => {}
+        (SourceType::Postgres, _) => throw!(anyhow!(
+            "{} protocol not supported for arrow batches",
+            protocol
+        )),
+        (SourceType::Sqlite, _) => throw!(anyhow!("arrow batches are not supported for sqlite")),
+    }
+
+    let conn = source_conn.conn.clone();
+    let queries: Vec<String> = queries.iter().map(|q| q.to_string()).collect();
+    let (tx, rx) = sync_channel(BUFFERED_BATCHES);
+    thread::spawn(move || {
+        // a panic would drop the sender, which looks like the end of the batches
+        let read = panic::catch_unwind(AssertUnwindSafe(|| {
+            for query in &queries {
+                match read_postgres(&conn, query, batch_size) {
+                    Ok(batches) => {
+                        for batch in batches {
+                            // the stream was dropped, nobody wants the rest
+                            if tx.send(Ok(batch)).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        let _ = tx.send(Err(e));
+                        return;
+                    }
+                }
+            }
+        }));
+        if let Err(payload) = read {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            let _ = tx.send(Err(
+                anyhow!("reading the batches panicked: {}", message).into()
+            ));
+        }
+    });
+
+    ArrowBatchStream {
+        batches: Mutex::new(rx),
+    }
+}
+
+fn read_postgres(
+    conn: &str,
+    query: &str,
+    batch_size: Option<usize>,
+) -> connectorx::Result<Vec<RecordBatch>> {
+    let source = PostgresSource::<Binary>::new(conn, 1)?;
+    let mut run = Dispatcher::<_, _, PostgresArrowTransport>::to_arrow(source, &[query]);
+    if let Some(batch_size) = batch_size {
+        run = run.batch_size(batch_size);
+    }
+    run.run()
+}
+
+/// Hand the columns of `batch` over to pyarrow through the Arrow C data interface.
+#[throws(ConnectorAgentPythonError)]
+pub(crate) fn to_pyarrow(py: Python, batch: RecordBatch) -> PyObject {
+    let pa = py.import("pyarrow")?;
+    let array_cls = pa.getattr("Array")?;
+
+    let mut arrays = Vec::with_capacity(batch.num_columns());
+    for column in batch.columns() {
+        // the C data interface of arrow 3 has no timestamps, they go over as their int64
+        // values and pyarrow views them as timestamps again
+        let (column, timestamp) = match column.data_type() {
+            DataType::Timestamp(unit, tz) => (as_int64(column), Some((time_unit(unit), tz))),
+            _ => (column.clone(), None),
+        };
+        let (array, schema) = column.to_raw().map_err(ConnectorAgentError::from)?;
+        let imported = array_cls.call_method1("_import_from_c", (array as usize, schema as usize));
+        // pyarrow moves the data out of the structs on success, dropping them releases the
+        // data only if the import failed
+        drop(unsafe { ArrowArray::try_from_raw(array, schema) });
+        let mut imported = imported?;
+        if let Some((unit, tz)) = timestamp {
+            let ty = pa.call_method1("timestamp", (unit, tz.clone()))?;
+            imported = imported.call_method1("view", (ty,))?;
+        }
+        arrays.push(imported);
+    }
+
+    let schema = batch.schema();
+    let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    pa.getattr("RecordBatch")?
+        .call_method1("from_arrays", (arrays, names))?
+        .to_object(py)
+}
+
+// The same values and validity, typed as int64.
+fn as_int64(column: &ArrayRef) -> ArrayRef {
+    let data = column.data();
+    Arc::new(Int64Array::from(Arc::new(ArrayData::new(
+        DataType::Int64,
+        data.len(),
+        None,
+        data.null_buffer().cloned(),
+        data.offset(),
+        data.buffers().to_vec(),
+        vec![],
+    ))))
+}
+
+fn time_unit(unit: &TimeUnit) -> &'static str {
+    match unit {
+        TimeUnit::Second => "s",
+        TimeUnit::Millisecond => "ms",
+        TimeUnit::Microsecond => "us",
+        TimeUnit::Nanosecond => "ns",
+    }
+}
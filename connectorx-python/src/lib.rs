@@ -1,6 +1,7 @@
 #![feature(generic_associated_types)]
 #![allow(incomplete_features)]
 
+mod arrow;
 mod errors;
 pub mod pandas;
 pub mod read_sql;
@@ -32,6 +33,7 @@ fn connectorx_python(_: Python, m: &PyModule) -> PyResult<()> {
 
     m.add_wrapped(wrap_pyfunction!(read_pg))?;
     m.add_wrapped(wrap_pyfunction!(read_sql))?;
+    m.add_class::<arrow::ArrowBatchStream>()?;
     Ok(())
 }
 
@@ -69,6 +71,21 @@ pub fn read_sql<'a>(
     protocol: Option<&str>,
     queries: Option<Vec<String>>,
     partition_query: Option<read_sql::PartitionQuery>,
+    max_cell_bytes: Option<usize>,
+    oversized_cells: Option<&str>,
+    batch_size: Option<usize>,
+    arrow_dtypes: Option<bool>,
 ) -> PyResult<&'a PyAny> {
-    read_sql::read_sql(py, conn, return_type, protocol, queries, partition_query)
+    read_sql::read_sql(
+        py,
+        conn,
+        return_type,
+        protocol,
+        queries,
+        partition_query,
+        max_cell_bytes,
+        oversized_cells,
+        batch_size,
+        arrow_dtypes,
+    )
 }
@@ -1,6 +1,6 @@
 use super::pandas_columns::{
-    BooleanBlock, BytesBlock, DateTimeBlock, Float64Block, HasPandasColumn, Int64Block,
-    PandasColumn, PandasColumnObject, StringBlock,
+    BooleanBlock, BytesBlock, CellLimit, DateTimeBlock, Float64Block, HasPandasColumn, Int64Block,
+    ObjectBlock, OversizedCellPolicy, PandasColumn, PandasColumnObject, StringBlock,
 };
 use super::types::{PandasDType, PandasTypeSystem};
 use anyhow::anyhow;
@@ -20,7 +20,9 @@ use std::mem::transmute;
 pub struct PandasDestination<'py> {
     py: Python<'py>,
     nrows: Option<usize>,
+    names: Option<Vec<String>>,
     schema: Option<Vec<PandasTypeSystem>>,
+    max_cell_bytes: Option<(usize, OversizedCellPolicy)>,
     buffers: Option<&'py PyList>,
     buffer_column_index: Option<Vec<Vec<usize>>>,
     dataframe: Option<&'py PyAny>, // Using this field other than the return purpose should be careful: this refers to the same data as buffers
@@ -31,13 +33,30 @@ impl<'a> PandasDestination<'a> {
         PandasDestination {
             py,
             nrows: None,
+            names: None,
             schema: None,
+            max_cell_bytes: None,
             buffers: None,
             buffer_column_index: None,
             dataframe: None,
         }
     }
 
+    /// Limit the size of every cell in the string and bytes columns to `max_bytes`.
+    /// Cells over the limit are handled according to `policy`.
+    pub fn max_cell_bytes(&mut self, max_bytes: usize, policy: OversizedCellPolicy) {
+        self.max_cell_bytes = Some((max_bytes, policy));
+    }
+
+    fn cell_limit(&self, cid: usize) -> Option<CellLimit> {
+        let (max_bytes, policy) = self.max_cell_bytes?;
+        Some(CellLimit {
+            max_bytes,
+            policy,
+            column: self.names.as_ref()?[cid].clone(),
+        })
+    }
+
     pub fn result(self) -> Option<&'a PyAny> {
         self.dataframe
     }
@@ -88,6 +107,7 @@ impl<'a> Destination for PandasDestination<'a> {
         }
 
         self.nrows = Some(nrows);
+        self.names = Some(names.iter().map(|n| n.as_ref().to_string()).collect());
         self.schema = Some(schema.to_vec());
         self.buffers = Some(buffers);
         self.buffer_column_index = Some(buffer_column_index);
@@ -162,7 +182,10 @@ impl<'a> Destination for PandasDestination<'a> {
                     | PandasTypeSystem::Char(_) => {
                         let block = StringBlock::extract(buf).map_err(|e| anyhow!(e))?;
                         let cols = block.split()?;
-                        for (&cid, col) in cids.iter().zip_eq(cols) {
+                        for (&cid, mut col) in cids.iter().zip_eq(cols) {
+                            if let Some(limit) = self.cell_limit(cid) {
+                                col.set_cell_limit(limit);
+                            }
                             partitioned_columns[cid] = col
                                 .partition(&counts)
                                 .into_iter()
@@ -173,7 +196,10 @@ impl<'a> Destination for PandasDestination<'a> {
                     PandasTypeSystem::Bytes(_) => {
                         let block = BytesBlock::extract(buf).map_err(|e| anyhow!(e))?;
                         let cols = block.split()?;
-                        for (&cid, col) in cids.iter().zip_eq(cols) {
+                        for (&cid, mut col) in cids.iter().zip_eq(cols) {
+                            if let Some(limit) = self.cell_limit(cid) {
+                                col.set_cell_limit(limit);
+                            }
                             partitioned_columns[cid] = col
                                 .partition(&counts)
                                 .into_iter()
@@ -192,6 +218,17 @@ impl<'a> Destination for PandasDestination<'a> {
                                 .collect()
                         }
                     }
+                    PandasTypeSystem::Object(_) => {
+                        let block = ObjectBlock::extract(buf).map_err(|e| anyhow!(e))?;
+                        let cols = block.split()?;
+                        for (&cid, col) in cids.iter().zip_eq(cols) {
+                            partitioned_columns[cid] = col
+                                .partition(&counts)
+                                .into_iter()
+                                .map(|c| Box::new(c) as _)
+                                .collect()
+                        }
+                    }
                 }
             }
         }
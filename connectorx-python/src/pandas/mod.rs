@@ -5,6 +5,7 @@ mod transports;
 mod types;
 
 pub use self::destination::{PandasDestination, PandasPartitionDestination};
+pub use self::pandas_columns::OversizedCellPolicy;
 pub use self::transports::{PostgresPandasTransport, SqlitePandasTransport};
 pub use self::types::{PandasDType, PandasTypeSystem};
 use crate::errors::ConnectorAgentPythonError;
@@ -15,11 +16,12 @@ use connectorx::{
         postgres::{Binary, PostgresSource, CSV},
         sqlite::SqliteSource,
     },
+    transports::PostgresArrowTransport,
     Dispatcher,
 };
-use fehler::throws;
+use fehler::{throw, throws};
 use log::debug;
-use pyo3::{PyAny, Python};
+use pyo3::{types::IntoPyDict, PyAny, Python};
 
 #[throws(ConnectorAgentPythonError)]
 pub fn write_pandas<'a>(
@@ -27,8 +29,12 @@ pub fn write_pandas<'a>(
     source_conn: &SourceConn,
     queries: &[&str],
     protocol: &str,
+    max_cell_bytes: Option<(usize, OversizedCellPolicy)>,
 ) -> &'a PyAny {
     let mut destination = PandasDestination::new(py);
+    if let Some((max_bytes, policy)) = max_cell_bytes {
+        destination.max_cell_bytes(max_bytes, policy);
+    }
 
     // TODO: unlock gil if possible
     match source_conn.ty {
@@ -73,3 +79,39 @@ pub fn write_pandas<'a>(
         .result()
         .ok_or_else(|| anyhow!("destination not run"))?
 }
+
+/// Read the queries through the Arrow destination and convert the batches into a DataFrame of
+/// ArrowDtype-backed columns, where a null is NA whatever the type instead of a sentinel.
+#[throws(ConnectorAgentPythonError)]
+pub fn write_pandas_arrow<'a>(
+    py: Python<'a>,
+    source_conn: &SourceConn,
+    queries: &[&str],
+    protocol: &str,
+) -> &'a PyAny {
+    let batches = match (&source_conn.ty, protocol) {
+        (SourceType::Postgres, "binary") => {
+            let source = PostgresSource::<Binary>::new(&source_conn.conn[..], queries.len())?;
+            Dispatcher::<_, _, PostgresArrowTransport>::run_to_arrow(source, queries)?
+        }
+        (SourceType::Postgres, _) => throw!(anyhow!(
+            "{} protocol not supported for arrow dtypes",
+            protocol
+        )),
+        (SourceType::Sqlite, _) => throw!(anyhow!("arrow dtypes are not supported for sqlite")),
+    };
+
+    let batches = batches
+        .into_iter()
+        .map(|batch| crate::arrow::to_pyarrow(py, batch))
+        .collect::<Result<Vec<_>, _>>()?;
+    let types_mapper = py.import("pandas")?.getattr("ArrowDtype")?;
+    py.import("pyarrow")?
+        .getattr("Table")?
+        .call_method1("from_batches", (batches,))?
+        .call_method(
+            "to_pandas",
+            (),
+            Some(vec![("types_mapper", types_mapper)].into_py_dict(py)),
+        )?
+}
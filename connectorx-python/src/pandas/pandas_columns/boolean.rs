@@ -1,8 +1,8 @@
-use super::{check_dtype, HasPandasColumn, PandasColumn, PandasColumnObject};
+use super::{check_dtype, split_block, HasPandasColumn, PandasColumn, PandasColumnObject};
 use anyhow::anyhow;
 use connectorx::ConnectorAgentError;
 use fehler::throws;
-use ndarray::{ArrayViewMut1, ArrayViewMut2, Axis, Ix2};
+use ndarray::{ArrayViewMut1, ArrayViewMut2, Ix2};
 use numpy::{PyArray, PyArray1};
 use pyo3::{FromPyObject, PyAny, PyResult};
 use std::any::TypeId;
@@ -45,16 +45,10 @@ impl<'a> BooleanBlock<'a> {
                 ),
                 i: 0,
             }),
-            BooleanBlock::NumPy(mut view) => {
-                let nrows = view.ncols();
-                while view.nrows() > 0 {
-                    let (col, rest) = view.split_at(Axis(0), 1);
-                    view = rest;
+            BooleanBlock::NumPy(view) => {
+                for data in split_block(view)? {
                     ret.push(BooleanColumn {
-                        data: col
-                            .into_shape(nrows)?
-                            .into_slice()
-                            .ok_or_else(|| anyhow!("get None for splitted Boolean data"))?,
+                        data,
                         mask: None,
                         i: 0,
                     })
@@ -1,8 +1,10 @@
-use super::{check_dtype, HasPandasColumn, PandasColumn, PandasColumnObject};
+use super::{
+    check_dtype, split_block, CellLimit, HasPandasColumn, PandasColumn, PandasColumnObject,
+};
 use anyhow::anyhow;
 use connectorx::ConnectorAgentError;
 use fehler::throws;
-use ndarray::{ArrayViewMut2, Axis, Ix2};
+use ndarray::{ArrayViewMut2, Ix2};
 use numpy::{npyffi::NPY_TYPES, Element, PyArray, PyArrayDescr};
 use pyo3::{FromPyObject, Py, PyAny, PyResult, Python};
 use std::any::TypeId;
@@ -43,22 +45,15 @@ impl<'a> BytesBlock<'a> {
     #[throws(ConnectorAgentError)]
     pub fn split(self) -> Vec<BytesColumn<'a>> {
         let mut ret = vec![];
-        let mut view = self.data;
-
-        let nrows = view.ncols();
-        while view.nrows() > 0 {
-            let (col, rest) = view.split_at(Axis(0), 1);
-            view = rest;
+        for data in split_block(self.data)? {
             ret.push(BytesColumn {
-                data: col
-                    .into_shape(nrows)?
-                    .into_slice()
-                    .ok_or_else(|| anyhow!("get None for splitted String data"))?,
+                data,
                 next_write: 0,
                 bytes_lengths: vec![],
                 bytes_buf: Vec::with_capacity(self.buf_size_mb * 2 << 20 * 11 / 10), // allocate a little bit more memory to avoid Vec growth
                 buf_size: self.buf_size_mb * 2 << 20,
                 mutex: self.mutex.clone(),
+                cell_limit: None,
             })
         }
         ret
@@ -72,6 +67,7 @@ pub struct BytesColumn<'a> {
     bytes_lengths: Vec<usize>,
     buf_size: usize,
     mutex: Arc<Mutex<()>>,
+    cell_limit: Option<CellLimit>,
 }
 
 impl<'a> PandasColumnObject for BytesColumn<'a> {
@@ -93,25 +89,14 @@ impl<'a> PandasColumnObject for BytesColumn<'a> {
 impl<'a> PandasColumn<Vec<u8>> for BytesColumn<'a> {
     #[throws(ConnectorAgentError)]
     fn write(&mut self, val: Vec<u8>) {
-        self.bytes_lengths.push(val.len());
-        self.bytes_buf.extend_from_slice(&val[..]);
-        self.try_flush()?;
+        self.push(Some(&val[..]))?;
     }
 }
 
 impl<'a> PandasColumn<Option<Vec<u8>>> for BytesColumn<'a> {
     #[throws(ConnectorAgentError)]
     fn write(&mut self, val: Option<Vec<u8>>) {
-        match val {
-            Some(b) => {
-                self.bytes_lengths.push(b.len());
-                self.bytes_buf.extend_from_slice(&b[..]);
-                self.try_flush()?;
-            }
-            None => {
-                self.bytes_lengths.push(0);
-            }
-        }
+        self.push(val.as_deref())?;
     }
 }
 
@@ -139,12 +124,40 @@ impl<'a> BytesColumn<'a> {
                 bytes_buf: Vec::with_capacity(self.buf_size),
                 buf_size: self.buf_size,
                 mutex: self.mutex.clone(),
+                cell_limit: self.cell_limit.clone(),
             });
         }
 
         partitions
     }
 
+    /// Check every cell of this column against `limit` before it is buffered.
+    pub fn set_cell_limit(&mut self, limit: CellLimit) {
+        self.cell_limit = Some(limit);
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn push(&mut self, val: Option<&[u8]>) {
+        let val = match (val, &self.cell_limit) {
+            (Some(bytes), Some(limit)) => {
+                let row = self.next_write + self.bytes_lengths.len();
+                limit.apply(bytes, false, row)?
+            }
+            (val, _) => val,
+        };
+
+        match val {
+            Some(bytes) => {
+                self.bytes_lengths.push(bytes.len());
+                self.bytes_buf.extend_from_slice(bytes);
+                self.try_flush()?;
+            }
+            None => {
+                self.bytes_lengths.push(0);
+            }
+        }
+    }
+
     #[throws(ConnectorAgentError)]
     pub fn flush(&mut self) {
         let nstrings = self.bytes_lengths.len();
@@ -1,9 +1,9 @@
-use super::{check_dtype, HasPandasColumn, PandasColumn, PandasColumnObject};
+use super::{check_dtype, split_block, HasPandasColumn, PandasColumn, PandasColumnObject};
 use anyhow::anyhow;
 use chrono::{DateTime, Utc};
 use connectorx::ConnectorAgentError;
 use fehler::throws;
-use ndarray::{ArrayViewMut2, Axis, Ix2};
+use ndarray::{ArrayViewMut2, Ix2};
 use numpy::PyArray;
 use pyo3::{FromPyObject, PyAny, PyResult};
 use std::any::TypeId;
@@ -27,19 +27,8 @@ impl<'a> DateTimeBlock<'a> {
     #[throws(ConnectorAgentError)]
     pub fn split(self) -> Vec<DateTimeColumn<'a>> {
         let mut ret = vec![];
-        let mut view = self.data;
-
-        let nrows = view.ncols();
-        while view.nrows() > 0 {
-            let (col, rest) = view.split_at(Axis(0), 1);
-            view = rest;
-            ret.push(DateTimeColumn {
-                data: col
-                    .into_shape(nrows)?
-                    .into_slice()
-                    .ok_or_else(|| anyhow!("get None for splitted DateTime data"))?,
-                i: 0,
-            })
+        for data in split_block(self.data)? {
+            ret.push(DateTimeColumn { data, i: 0 })
         }
         ret
     }
@@ -62,10 +51,28 @@ impl<'a> PandasColumnObject for DateTimeColumn<'a> {
     }
 }
 
+// datetime64[ns] only spans 1677-09-21 to 2262-04-11 and reserves i64::MIN for NaT, so anything
+// outside has to be rejected instead of letting the multiplication wrap around
+#[throws(ConnectorAgentError)]
+fn to_nanos(val: DateTime<Utc>, row: usize) -> i64 {
+    val.timestamp()
+        .checked_mul(1_000_000_000)
+        .and_then(|ns| ns.checked_add(val.timestamp_subsec_nanos() as i64))
+        .filter(|&ns| ns != i64::MIN)
+        .ok_or_else(|| {
+            anyhow!(
+                "timestamp {} at row {} of its partition is out of the datetime64[ns] range",
+                val,
+                row
+            )
+        })?
+}
+
 impl<'a> PandasColumn<DateTime<Utc>> for DateTimeColumn<'a> {
     #[throws(ConnectorAgentError)]
     fn write(&mut self, val: DateTime<Utc>) {
-        unsafe { *self.data.get_unchecked_mut(self.i) = val.timestamp_nanos() };
+        let val = to_nanos(val, self.i)?;
+        unsafe { *self.data.get_unchecked_mut(self.i) = val };
         self.i += 1;
     }
 }
@@ -74,10 +81,11 @@ impl<'a> PandasColumn<Option<DateTime<Utc>>> for DateTimeColumn<'a> {
     #[throws(ConnectorAgentError)]
     fn write(&mut self, val: Option<DateTime<Utc>>) {
         // numpy use i64::MIN as NaT
-        unsafe {
-            *self.data.get_unchecked_mut(self.i) =
-                val.map(|t| t.timestamp_nanos()).unwrap_or(i64::MIN);
+        let val = match val {
+            Some(t) => to_nanos(t, self.i)?,
+            None => i64::MIN,
         };
+        unsafe { *self.data.get_unchecked_mut(self.i) = val };
         self.i += 1;
     }
 }
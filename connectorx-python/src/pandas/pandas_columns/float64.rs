@@ -1,8 +1,7 @@
-use super::{check_dtype, HasPandasColumn, PandasColumn, PandasColumnObject};
-use anyhow::anyhow;
+use super::{check_dtype, split_block, HasPandasColumn, PandasColumn, PandasColumnObject};
 use connectorx::ConnectorAgentError;
 use fehler::throws;
-use ndarray::{ArrayViewMut2, Axis, Ix2};
+use ndarray::{ArrayViewMut2, Ix2};
 use numpy::PyArray;
 use pyo3::{FromPyObject, PyAny, PyResult};
 use std::any::TypeId;
@@ -25,19 +24,8 @@ impl<'a> Float64Block<'a> {
     #[throws(ConnectorAgentError)]
     pub fn split(self) -> Vec<Float64Column<'a>> {
         let mut ret = vec![];
-        let mut view = self.data;
-
-        let nrows = view.ncols();
-        while view.nrows() > 0 {
-            let (col, rest) = view.split_at(Axis(0), 1);
-            view = rest;
-            ret.push(Float64Column {
-                data: col
-                    .into_shape(nrows)?
-                    .into_slice()
-                    .ok_or_else(|| anyhow!("get None for splitted Float64 data"))?,
-                i: 0,
-            })
+        for data in split_block(self.data)? {
+            ret.push(Float64Column { data, i: 0 })
         }
         ret
     }
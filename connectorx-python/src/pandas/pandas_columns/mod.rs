@@ -3,16 +3,20 @@ mod bytes;
 mod datetime;
 mod float64;
 mod int64;
+mod object;
 mod string;
 // TODO: use macro for integers
 
 pub use crate::pandas::pandas_columns::bytes::{BytesBlock, BytesColumn};
+use anyhow::anyhow;
 pub use boolean::{BooleanBlock, BooleanColumn};
-use connectorx::Result;
+use connectorx::{ConnectorAgentError, Result};
 pub use datetime::{DateTimeBlock, DateTimeColumn};
-use fehler::throw;
+use fehler::{throw, throws};
 pub use float64::{Float64Block, Float64Column};
 pub use int64::{Int64Block, Int64Column};
+use ndarray::{ArrayViewMut2, Axis};
+pub use object::{ObjectBlock, ObjectColumn, PyValue};
 use pyo3::{exceptions::PyRuntimeError, PyAny, PyResult};
 use std::any::TypeId;
 pub use string::{StringBlock, StringColumn};
@@ -35,6 +39,104 @@ pub trait HasPandasColumn: Sized {
     type PandasColumn<'a>: PandasColumn<Self>;
 }
 
+/// What to do with an object cell that is larger than `max_cell_bytes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OversizedCellPolicy {
+    /// Keep the first `max_cell_bytes` bytes (cut on a character boundary for strings).
+    Truncate,
+    /// Fail the whole read.
+    Error,
+    /// Replace the cell with a null.
+    Null,
+}
+
+impl std::str::FromStr for OversizedCellPolicy {
+    type Err = ConnectorAgentError;
+
+    #[throws(ConnectorAgentError)]
+    fn from_str(s: &str) -> Self {
+        match s {
+            "truncate" => OversizedCellPolicy::Truncate,
+            "error" => OversizedCellPolicy::Error,
+            "null" => OversizedCellPolicy::Null,
+            _ => throw!(anyhow!(
+                "oversized cell policy should be 'truncate', 'error' or 'null', got '{}'",
+                s
+            )),
+        }
+    }
+}
+
+/// A size limit on the cells of one string or bytes column.
+#[derive(Debug, Clone)]
+pub struct CellLimit {
+    pub max_bytes: usize,
+    pub policy: OversizedCellPolicy,
+    pub column: String,
+}
+
+impl CellLimit {
+    /// Check a cell against the limit. Returns the bytes to store, or None if the cell
+    /// should be stored as a null. `is_str` keeps truncated strings valid utf-8.
+    #[throws(ConnectorAgentError)]
+    pub fn apply<'b>(&self, bytes: &'b [u8], is_str: bool, row: usize) -> Option<&'b [u8]> {
+        if bytes.len() <= self.max_bytes {
+            return Some(bytes);
+        }
+
+        match self.policy {
+            OversizedCellPolicy::Error => throw!(anyhow!(
+                "cell at row {} of its partition in column '{}' has {} bytes, more than max_cell_bytes {}",
+                row,
+                self.column,
+                bytes.len(),
+                self.max_bytes
+            )),
+            OversizedCellPolicy::Null => None,
+            OversizedCellPolicy::Truncate => {
+                let mut end = self.max_bytes;
+                // step back over utf-8 continuation bytes
+                while is_str && end > 0 && bytes[end] & 0xC0 == 0x80 {
+                    end -= 1;
+                }
+                Some(&bytes[..end])
+            }
+        }
+    }
+}
+
+/// Split a block into one slice per column of the frame. Pandas stores blocks transposed, so
+/// every row of the block is a column, `block.ncols()` values long.
+#[throws(ConnectorAgentError)]
+pub fn split_block<'a, T>(mut view: ArrayViewMut2<'a, T>) -> Vec<&'a mut [T]> {
+    let nrows = view.ncols();
+    let mut cols = Vec::with_capacity(view.nrows());
+    while view.nrows() > 0 {
+        let (col, rest) = view.split_at(Axis(0), 1);
+        view = rest;
+        if nrows == 0 {
+            // numpy puts arbitrary strides on empty arrays, there is nothing to reshape anyway
+            cols.push(&mut [][..]);
+            continue;
+        }
+        let strides = col.strides().to_vec();
+        let col = col
+            .into_shape(nrows)
+            .ok()
+            .and_then(|col| col.into_slice())
+            .ok_or_else(|| {
+                anyhow!(
+                    "column {} of a {} row block is not contiguous, strides {:?}",
+                    cols.len(),
+                    nrows,
+                    strides
+                )
+            })?;
+        cols.push(col);
+    }
+    cols
+}
+
 pub fn check_dtype(ob: &PyAny, expected_dtype: &str) -> PyResult<()> {
     let dtype = ob.getattr("dtype")?.str()?;
     let dtype = dtype.to_str()?;
@@ -0,0 +1,272 @@
+use super::{check_dtype, split_block, HasPandasColumn, PandasColumn, PandasColumnObject};
+use anyhow::anyhow;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Timelike, Utc};
+use connectorx::ConnectorAgentError;
+use fehler::throws;
+use ndarray::{ArrayViewMut2, Ix2};
+use numpy::{npyffi::NPY_TYPES, Element, PyArray, PyArrayDescr};
+use pyo3::{
+    types::{PyDate, PyDateTime, PyDict, PyList, PyTuple},
+    FromPyObject, PyAny, PyObject, PyResult, Python, ToPyObject,
+};
+use rust_decimal::Decimal;
+use std::any::TypeId;
+use std::sync::{Arc, Mutex};
+
+/// A value of an object column, built without the GIL and turned into the Python object it
+/// stands for when the column is flushed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PyValue {
+    None,
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Str(String),
+    /// A `decimal.Decimal`.
+    Decimal(Decimal),
+    /// A `datetime.date`.
+    Date(NaiveDate),
+    /// A naive `datetime.datetime`.
+    DateTime(NaiveDateTime),
+    /// A `datetime.datetime` in UTC.
+    DateTimeTz(DateTime<Utc>),
+    Tuple(Vec<PyValue>),
+    List(Vec<PyValue>),
+    Dict(Vec<(String, PyValue)>),
+}
+
+// The Python classes some of the values are made of, looked up once per flush.
+struct PyClasses<'py> {
+    decimal: &'py PyAny,
+    utc: PyObject,
+}
+
+impl<'py> PyClasses<'py> {
+    fn new(py: Python<'py>) -> PyResult<Self> {
+        Ok(PyClasses {
+            decimal: py.import("decimal")?.getattr("Decimal")?,
+            utc: py
+                .import("datetime")?
+                .getattr("timezone")?
+                .getattr("utc")?
+                .to_object(py),
+        })
+    }
+}
+
+fn datetime<'py>(
+    py: Python<'py>,
+    dt: &NaiveDateTime,
+    tzinfo: Option<&PyObject>,
+) -> PyResult<&'py PyDateTime> {
+    PyDateTime::new(
+        py,
+        dt.year(),
+        dt.month() as u8,
+        dt.day() as u8,
+        dt.hour() as u8,
+        dt.minute() as u8,
+        dt.second() as u8,
+        // a leap second is past a million microseconds
+        (dt.nanosecond() / 1000).min(999_999),
+        tzinfo,
+    )
+}
+
+impl PyValue {
+    fn to_object(&self, py: Python, classes: &PyClasses) -> PyResult<PyObject> {
+        Ok(match self {
+            PyValue::None => py.None(),
+            PyValue::Bool(v) => v.to_object(py),
+            PyValue::I64(v) => v.to_object(py),
+            PyValue::U64(v) => v.to_object(py),
+            PyValue::F64(v) => v.to_object(py),
+            PyValue::Str(v) => v.to_object(py),
+            PyValue::Decimal(v) => classes.decimal.call1((v.to_string(),))?.to_object(py),
+            PyValue::Date(v) => {
+                PyDate::new(py, v.year(), v.month() as u8, v.day() as u8)?.to_object(py)
+            }
+            PyValue::DateTime(v) => datetime(py, v, None)?.to_object(py),
+            PyValue::DateTimeTz(v) => {
+                datetime(py, &v.naive_utc(), Some(&classes.utc))?.to_object(py)
+            }
+            PyValue::Tuple(vs) => PyTuple::new(
+                py,
+                vs.iter()
+                    .map(|v| v.to_object(py, classes))
+                    .collect::<PyResult<Vec<_>>>()?,
+            )
+            .to_object(py),
+            PyValue::List(vs) => PyList::new(
+                py,
+                vs.iter()
+                    .map(|v| v.to_object(py, classes))
+                    .collect::<PyResult<Vec<_>>>()?,
+            )
+            .to_object(py),
+            PyValue::Dict(items) => {
+                let dict = PyDict::new(py);
+                for (k, v) in items {
+                    dict.set_item(k, v.to_object(py, classes)?)?;
+                }
+                dict.to_object(py)
+            }
+        })
+    }
+}
+
+#[derive(Clone)]
+#[repr(transparent)]
+pub struct PyObjectCell(PyObject);
+
+// In order to put it into a numpy array
+impl Element for PyObjectCell {
+    const DATA_TYPE: numpy::DataType = numpy::DataType::Object;
+    fn is_same_type(dtype: &PyArrayDescr) -> bool {
+        unsafe { *dtype.as_dtype_ptr() }.type_num == NPY_TYPES::NPY_OBJECT as i32
+    }
+}
+
+pub struct ObjectBlock<'a> {
+    data: ArrayViewMut2<'a, PyObjectCell>,
+    mutex: Arc<Mutex<()>>,
+    buf_size: usize,
+}
+
+impl<'a> FromPyObject<'a> for ObjectBlock<'a> {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        check_dtype(ob, "object")?;
+        let array = ob.downcast::<PyArray<PyObjectCell, Ix2>>()?;
+        let data = unsafe { array.as_array_mut() };
+        Ok(ObjectBlock {
+            data,
+            mutex: Arc::new(Mutex::new(())),
+            buf_size: 4096, // in values
+        })
+    }
+}
+
+impl<'a> ObjectBlock<'a> {
+    #[throws(ConnectorAgentError)]
+    pub fn split(self) -> Vec<ObjectColumn<'a>> {
+        let mut ret = vec![];
+        for data in split_block(self.data)? {
+            ret.push(ObjectColumn {
+                data,
+                next_write: 0,
+                values: Vec::with_capacity(self.buf_size),
+                buf_size: self.buf_size,
+                mutex: self.mutex.clone(),
+            })
+        }
+        ret
+    }
+}
+
+pub struct ObjectColumn<'a> {
+    data: &'a mut [PyObjectCell],
+    next_write: usize,
+    values: Vec<Option<PyValue>>,
+    buf_size: usize,
+    mutex: Arc<Mutex<()>>,
+}
+
+impl<'a> PandasColumnObject for ObjectColumn<'a> {
+    fn typecheck(&self, id: TypeId) -> bool {
+        id == TypeId::of::<PyValue>() || id == TypeId::of::<Option<PyValue>>()
+    }
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+    fn typename(&self) -> &'static str {
+        std::any::type_name::<PyValue>()
+    }
+    #[throws(ConnectorAgentError)]
+    fn finalize(&mut self) {
+        self.flush()?;
+    }
+}
+
+impl<'a> PandasColumn<PyValue> for ObjectColumn<'a> {
+    #[throws(ConnectorAgentError)]
+    fn write(&mut self, val: PyValue) {
+        self.values.push(Some(val));
+        self.try_flush()?;
+    }
+}
+
+impl<'a> PandasColumn<Option<PyValue>> for ObjectColumn<'a> {
+    #[throws(ConnectorAgentError)]
+    fn write(&mut self, val: Option<PyValue>) {
+        self.values.push(val);
+        self.try_flush()?;
+    }
+}
+
+impl HasPandasColumn for PyValue {
+    type PandasColumn<'a> = ObjectColumn<'a>;
+}
+
+impl HasPandasColumn for Option<PyValue> {
+    type PandasColumn<'a> = ObjectColumn<'a>;
+}
+
+impl<'a> ObjectColumn<'a> {
+    pub fn partition(self, counts: &[usize]) -> Vec<ObjectColumn<'a>> {
+        let mut partitions = vec![];
+        let mut data = self.data;
+
+        for &c in counts {
+            let (splitted_data, rest) = data.split_at_mut(c);
+            data = rest;
+
+            partitions.push(ObjectColumn {
+                data: splitted_data,
+                next_write: 0,
+                values: Vec::with_capacity(self.buf_size),
+                buf_size: self.buf_size,
+                mutex: self.mutex.clone(),
+            });
+        }
+
+        partitions
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub fn flush(&mut self) {
+        let nvalues = self.values.len();
+
+        if nvalues > 0 {
+            let py = unsafe { Python::assume_gil_acquired() };
+
+            {
+                // allocation in python is not thread safe
+                let _guard = self
+                    .mutex
+                    .lock()
+                    .map_err(|e| anyhow!("mutex poisoned {}", e))?;
+                let classes = PyClasses::new(py).map_err(|e| anyhow!(e))?;
+                for (i, val) in self.values.iter().enumerate() {
+                    // a null stays the None numpy fills the block with
+                    if let Some(val) = val {
+                        let obj = val.to_object(py, &classes).map_err(|e| anyhow!(e))?;
+                        unsafe {
+                            *self.data.get_unchecked_mut(self.next_write + i) = PyObjectCell(obj);
+                        };
+                    }
+                }
+            }
+
+            self.values.truncate(0);
+            self.next_write += nvalues;
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub fn try_flush(&mut self) {
+        if self.values.len() >= self.buf_size {
+            self.flush()?;
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use super::super::pystring::{PyString, StringInfo};
-use super::{check_dtype, HasPandasColumn, PandasColumn, PandasColumnObject};
+use super::{
+    check_dtype, split_block, CellLimit, HasPandasColumn, PandasColumn, PandasColumnObject,
+};
 use anyhow::anyhow;
 use connectorx::ConnectorAgentError;
 use fehler::throws;
 use itertools::Itertools;
-use ndarray::{ArrayViewMut2, Axis, Ix2};
+use ndarray::{ArrayViewMut2, Ix2};
 use numpy::PyArray;
 use pyo3::{FromPyObject, PyAny, PyResult, Python};
 use std::any::TypeId;
@@ -33,22 +35,15 @@ impl<'a> StringBlock<'a> {
     #[throws(ConnectorAgentError)]
     pub fn split(self) -> Vec<StringColumn<'a>> {
         let mut ret = vec![];
-        let mut view = self.data;
-
-        let nrows = view.ncols();
-        while view.nrows() > 0 {
-            let (col, rest) = view.split_at(Axis(0), 1);
-            view = rest;
+        for data in split_block(self.data)? {
             ret.push(StringColumn {
-                data: col
-                    .into_shape(nrows)?
-                    .into_slice()
-                    .ok_or_else(|| anyhow!("get None for splitted String data"))?,
+                data,
                 next_write: 0,
                 string_lengths: vec![],
                 string_buf: Vec::with_capacity(self.buf_size_mb * 2 << 20 * 11 / 10), // allocate a little bit more memory to avoid Vec growth
                 buf_size: self.buf_size_mb * 2 << 20,
                 mutex: self.mutex.clone(),
+                cell_limit: None,
             })
         }
         ret
@@ -62,6 +57,7 @@ pub struct StringColumn<'a> {
     string_lengths: Vec<usize>,
     buf_size: usize,
     mutex: Arc<Mutex<()>>,
+    cell_limit: Option<CellLimit>,
 }
 
 impl<'a> PandasColumnObject for StringColumn<'a> {
@@ -83,30 +79,21 @@ impl<'a> PandasColumnObject for StringColumn<'a> {
 impl<'r, 'a> PandasColumn<&'r str> for StringColumn<'a> {
     #[throws(ConnectorAgentError)]
     fn write(&mut self, val: &'r str) {
-        let bytes = val.as_bytes();
-        self.string_lengths.push(bytes.len());
-        self.string_buf.extend_from_slice(bytes);
-        self.try_flush()?;
+        self.push(Some(val.as_bytes()))?;
     }
 }
 
 impl<'a> PandasColumn<Box<str>> for StringColumn<'a> {
     #[throws(ConnectorAgentError)]
     fn write(&mut self, val: Box<str>) {
-        let bytes = val.as_bytes();
-        self.string_lengths.push(bytes.len());
-        self.string_buf.extend_from_slice(bytes);
-        self.try_flush()?;
+        self.push(Some(val.as_bytes()))?;
     }
 }
 
 impl<'a> PandasColumn<String> for StringColumn<'a> {
     #[throws(ConnectorAgentError)]
     fn write(&mut self, val: String) {
-        let bytes = val.as_bytes();
-        self.string_lengths.push(bytes.len());
-        self.string_buf.extend_from_slice(bytes);
-        self.try_flush()?;
+        self.push(Some(val.as_bytes()))?;
     }
 }
 
@@ -114,78 +101,35 @@ impl<'a> PandasColumn<char> for StringColumn<'a> {
     #[throws(ConnectorAgentError)]
     fn write(&mut self, val: char) {
         let mut buffer = [0; 4]; // a char is max to 4 bytes
-        let bytes = val.encode_utf8(&mut buffer).as_bytes();
-        self.string_lengths.push(bytes.len());
-        self.string_buf.extend_from_slice(bytes);
-        self.try_flush()?;
+        self.push(Some(val.encode_utf8(&mut buffer).as_bytes()))?;
     }
 }
 
 impl<'r, 'a> PandasColumn<Option<&'r str>> for StringColumn<'a> {
     #[throws(ConnectorAgentError)]
     fn write(&mut self, val: Option<&'r str>) {
-        match val {
-            Some(b) => {
-                let bytes = b.as_bytes();
-                self.string_lengths.push(bytes.len());
-                self.string_buf.extend_from_slice(bytes);
-                self.try_flush()?;
-            }
-            None => {
-                self.string_lengths.push(0);
-            }
-        }
+        self.push(val.as_ref().map(|b| b.as_bytes()))?;
     }
 }
 
 impl<'a> PandasColumn<Option<Box<str>>> for StringColumn<'a> {
     #[throws(ConnectorAgentError)]
     fn write(&mut self, val: Option<Box<str>>) {
-        match val {
-            Some(b) => {
-                let bytes = b.as_bytes();
-                self.string_lengths.push(bytes.len());
-                self.string_buf.extend_from_slice(bytes);
-                self.try_flush()?;
-            }
-            None => {
-                self.string_lengths.push(0);
-            }
-        }
+        self.push(val.as_ref().map(|b| b.as_bytes()))?;
     }
 }
 impl<'a> PandasColumn<Option<String>> for StringColumn<'a> {
     #[throws(ConnectorAgentError)]
     fn write(&mut self, val: Option<String>) {
-        match val {
-            Some(b) => {
-                let bytes = b.as_bytes();
-                self.string_lengths.push(bytes.len());
-                self.string_buf.extend_from_slice(bytes);
-                self.try_flush()?;
-            }
-            None => {
-                self.string_lengths.push(0);
-            }
-        }
+        self.push(val.as_ref().map(|b| b.as_bytes()))?;
     }
 }
 
 impl<'a> PandasColumn<Option<char>> for StringColumn<'a> {
     #[throws(ConnectorAgentError)]
     fn write(&mut self, val: Option<char>) {
-        match val {
-            Some(b) => {
-                let mut buffer = [0; 4]; // a char is max to 4 bytes
-                let bytes = b.encode_utf8(&mut buffer).as_bytes();
-                self.string_lengths.push(bytes.len());
-                self.string_buf.extend_from_slice(bytes);
-                self.try_flush()?;
-            }
-            None => {
-                self.string_lengths.push(0);
-            }
-        }
+        let mut buffer = [0; 4]; // a char is max to 4 bytes
+        self.push(val.map(|b| b.encode_utf8(&mut buffer).as_bytes()))?;
     }
 }
 
@@ -237,12 +181,40 @@ impl<'a> StringColumn<'a> {
                 string_buf: Vec::with_capacity(self.buf_size),
                 buf_size: self.buf_size,
                 mutex: self.mutex.clone(),
+                cell_limit: self.cell_limit.clone(),
             });
         }
 
         partitions
     }
 
+    /// Check every cell of this column against `limit` before it is buffered.
+    pub fn set_cell_limit(&mut self, limit: CellLimit) {
+        self.cell_limit = Some(limit);
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn push(&mut self, val: Option<&[u8]>) {
+        let val = match (val, &self.cell_limit) {
+            (Some(bytes), Some(limit)) => {
+                let row = self.next_write + self.string_lengths.len();
+                limit.apply(bytes, true, row)?
+            }
+            (val, _) => val,
+        };
+
+        match val {
+            Some(bytes) => {
+                self.string_lengths.push(bytes.len());
+                self.string_buf.extend_from_slice(bytes);
+                self.try_flush()?;
+            }
+            None => {
+                self.string_lengths.push(0);
+            }
+        }
+    }
+
     #[throws(ConnectorAgentError)]
     pub fn flush(&mut self, force_flush: bool) {
         let nstrings = self.string_lengths.len();
@@ -1,9 +1,14 @@
 use crate::pandas::destination::PandasDestination;
+use crate::pandas::pandas_columns::PyValue;
 use crate::pandas::types::PandasTypeSystem;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use connectorx::{
+    dummy_typesystem::{Circle, Point, Record, RecordType, RecordValue, Snapshot},
     impl_transport,
-    sources::postgres::{Binary, PostgresSource, PostgresTypeSystem, CSV},
+    sources::postgres::{
+        Binary, JsonPathStr, Multirange, PostgresSource, PostgresTypeSystem, RangeBound, RegOid,
+        Tid, Xid8, CSV,
+    },
     typesystem::TypeConversion,
 };
 use rust_decimal::prelude::*;
@@ -35,9 +40,19 @@ impl_transport!(
         { UUID[Uuid]                 => String[String]          | conversion half }
         { JSON[Value]                => String[String]          | conversion half }
         { JSONB[Value]               => String[String]          | conversion none }
+        { JsonPath[JsonPathStr]      => String[String]          | conversion half }
+        { RegOid[RegOid]             => I64[i64]                | conversion half }
         { Time[NaiveTime]            => String[String]          | conversion half }
         { ByteA[Vec<u8>]             => Bytes[Vec<u8>]          | conversion all }
         { Enum[&'r str]              => Str[&'r str]            | conversion none }
+        { Point[Point]               => Object[PyValue]         | conversion half }
+        { Circle[Circle]             => Object[PyValue]         | conversion half }
+        { Xid8[Xid8]                 => Object[PyValue]         | conversion half }
+        { Tid[Tid]                   => Object[PyValue]         | conversion half }
+        { Snapshot[Snapshot]         => Object[PyValue]         | conversion half }
+        { TextArray[Vec<Option<String>>] => Object[PyValue]     | conversion half }
+        { Composite[Record]          => Object[PyValue]         | conversion half }
+        { Multirange[Multirange]     => Object[PyValue]         | conversion half }
     }
 );
 
@@ -106,8 +121,134 @@ impl<'py, P> TypeConversion<Uuid, String> for PostgresPandasTransport<'py, P> {
     }
 }
 
+impl<'py, P> TypeConversion<JsonPathStr, String> for PostgresPandasTransport<'py, P> {
+    fn convert(val: JsonPathStr) -> String {
+        val.0
+    }
+}
+
+impl<'py, P> TypeConversion<RegOid, i64> for PostgresPandasTransport<'py, P> {
+    fn convert(val: RegOid) -> i64 {
+        val.0 as i64
+    }
+}
+
 impl<'py, P> TypeConversion<Value, String> for PostgresPandasTransport<'py, P> {
     fn convert(val: Value) -> String {
         to_string(&val).unwrap()
     }
 }
+
+impl<'py, P> TypeConversion<Point, PyValue> for PostgresPandasTransport<'py, P> {
+    fn convert(val: Point) -> PyValue {
+        PyValue::Tuple(vec![PyValue::F64(val.x), PyValue::F64(val.y)])
+    }
+}
+
+// the center as an (x, y) tuple, then the radius
+impl<'py, P> TypeConversion<Circle, PyValue> for PostgresPandasTransport<'py, P> {
+    fn convert(val: Circle) -> PyValue {
+        PyValue::Tuple(vec![
+            <Self as TypeConversion<Point, PyValue>>::convert(val.center),
+            PyValue::F64(val.radius),
+        ])
+    }
+}
+
+// xid8 goes up to u64::MAX, past what an int64 column holds
+impl<'py, P> TypeConversion<Xid8, PyValue> for PostgresPandasTransport<'py, P> {
+    fn convert(val: Xid8) -> PyValue {
+        PyValue::U64(val.0)
+    }
+}
+
+impl<'py, P> TypeConversion<Tid, PyValue> for PostgresPandasTransport<'py, P> {
+    fn convert(val: Tid) -> PyValue {
+        PyValue::Tuple(vec![
+            PyValue::I64(val.block as i64),
+            PyValue::I64(val.index as i64),
+        ])
+    }
+}
+
+impl<'py, P> TypeConversion<Snapshot, PyValue> for PostgresPandasTransport<'py, P> {
+    fn convert(val: Snapshot) -> PyValue {
+        PyValue::Dict(vec![
+            ("xmin".to_string(), PyValue::U64(val.xmin)),
+            ("xmax".to_string(), PyValue::U64(val.xmax)),
+            (
+                "xip".to_string(),
+                PyValue::List(val.xip.into_iter().map(PyValue::U64).collect()),
+            ),
+        ])
+    }
+}
+
+impl<'py, P> TypeConversion<Vec<Option<String>>, PyValue> for PostgresPandasTransport<'py, P> {
+    fn convert(val: Vec<Option<String>>) -> PyValue {
+        PyValue::List(
+            val.into_iter()
+                .map(|s| s.map_or(PyValue::None, PyValue::Str))
+                .collect(),
+        )
+    }
+}
+
+// The fields of a composite as a dict.
+fn record_dict(fields: &[(String, RecordType)], values: Vec<RecordValue>) -> PyValue {
+    PyValue::Dict(
+        fields
+            .iter()
+            .zip(values)
+            .map(|((name, ty), val)| (name.clone(), record_value(ty, val)))
+            .collect(),
+    )
+}
+
+// A field of a composite, a nested one as a dict too.
+fn record_value(ty: &RecordType, val: RecordValue) -> PyValue {
+    match (ty, val) {
+        (RecordType::Record(fields), RecordValue::Record(values)) => record_dict(fields, values),
+        (_, RecordValue::Null) | (_, RecordValue::Record(_)) => PyValue::None,
+        (_, RecordValue::I64(v)) => PyValue::I64(v),
+        (_, RecordValue::F64(v)) => PyValue::F64(v),
+        (_, RecordValue::Bool(v)) => PyValue::Bool(v),
+        (_, RecordValue::String(v)) => PyValue::Str(v),
+    }
+}
+
+impl<'py, P> TypeConversion<Record, PyValue> for PostgresPandasTransport<'py, P> {
+    fn convert(val: Record) -> PyValue {
+        record_dict(&val.fields, val.values)
+    }
+}
+
+fn range_bound(bound: Option<RangeBound>) -> PyValue {
+    match bound {
+        None => PyValue::None,
+        Some(RangeBound::Int(v)) => PyValue::I64(v),
+        Some(RangeBound::Numeric(v)) => PyValue::Decimal(v),
+        Some(RangeBound::Date(v)) => PyValue::Date(v),
+        Some(RangeBound::Timestamp(v)) => PyValue::DateTime(v),
+        Some(RangeBound::TimestampTz(v)) => PyValue::DateTimeTz(v),
+    }
+}
+
+// Each range as a (lower, upper, lower_inc, upper_inc) tuple, None for an unbounded side.
+impl<'py, P> TypeConversion<Multirange, PyValue> for PostgresPandasTransport<'py, P> {
+    fn convert(val: Multirange) -> PyValue {
+        PyValue::List(
+            val.0
+                .into_iter()
+                .map(|range| {
+                    PyValue::Tuple(vec![
+                        range_bound(range.lower),
+                        range_bound(range.upper),
+                        PyValue::Bool(range.lower_inc),
+                        PyValue::Bool(range.upper_inc),
+                    ])
+                })
+                .collect(),
+        )
+    }
+}
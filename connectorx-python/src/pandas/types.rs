@@ -1,4 +1,5 @@
 // Unfortunately, due to the orphan rule, typesystem implementation should be in this crate.
+use crate::pandas::pandas_columns::PyValue;
 use chrono::{DateTime, Utc};
 use connectorx::errors::{ConnectorAgentError, Result};
 use connectorx::impl_typesystem;
@@ -15,6 +16,7 @@ pub enum PandasTypeSystem {
     String(bool),
     Bytes(bool),
     DateTime(bool),
+    Object(bool),
 }
 
 impl_typesystem! {
@@ -29,6 +31,7 @@ impl_typesystem! {
         { String => String }
         { Bytes => Vec<u8> }
         { DateTime => DateTime<Utc> }
+        { Object => PyValue }
     }
 }
 
@@ -55,6 +58,7 @@ impl PandasDType for PandasTypeSystem {
             PandasTypeSystem::String(_) => "object",
             PandasTypeSystem::Bytes(_) => "object",
             PandasTypeSystem::DateTime(_) => "datetime64[ns]",
+            PandasTypeSystem::Object(_) => "object",
         }
     }
 
@@ -69,6 +73,7 @@ impl PandasDType for PandasTypeSystem {
             PandasTypeSystem::String(_) => "O",
             PandasTypeSystem::Bytes(_) => "O",
             PandasTypeSystem::DateTime(_) => "M8[ns]",
+            PandasTypeSystem::Object(_) => "O",
         }
     }
 
@@ -99,6 +104,7 @@ impl PandasDType for PandasTypeSystem {
             PandasTypeSystem::String(_) => false, // we use object instead of string (Extension) for now
             PandasTypeSystem::Bytes(_) => false, // we use object instead of string (Extension) for now
             PandasTypeSystem::DateTime(_) => false,
+            PandasTypeSystem::Object(_) => false,
         }
     }
 
@@ -115,6 +121,7 @@ impl PandasDType for PandasTypeSystem {
             PandasTypeSystem::String(_) => "ObjectBlock", // we use object instead of string (Extension) for now
             PandasTypeSystem::Bytes(_) => "ObjectBlock", // we use object instead of string (Extension) for now
             PandasTypeSystem::DateTime(_) => "DatetimeBlock",
+            PandasTypeSystem::Object(_) => "ObjectBlock",
         }
     }
 }
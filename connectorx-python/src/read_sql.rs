@@ -1,4 +1,5 @@
 use crate::errors::ConnectorAgentPythonError;
+use crate::pandas::OversizedCellPolicy;
 use connectorx::source_router::SourceConn;
 use dict_derive::FromPyObject;
 use fehler::throw;
@@ -37,7 +38,22 @@ pub fn read_sql<'a>(
     protocol: Option<&str>,
     queries: Option<Vec<String>>,
     partition_query: Option<PartitionQuery>,
+    max_cell_bytes: Option<usize>,
+    oversized_cells: Option<&str>,
+    batch_size: Option<usize>,
+    arrow_dtypes: Option<bool>,
 ) -> PyResult<&'a PyAny> {
+    let max_cell_bytes = match max_cell_bytes {
+        Some(max_bytes) => {
+            let policy: OversizedCellPolicy = oversized_cells
+                .unwrap_or("error")
+                .parse()
+                .map_err(ConnectorAgentPythonError::ConnectorAgentError)?;
+            Some((max_bytes, policy))
+        }
+        None => None,
+    };
+
     let source_conn =
         SourceConn::try_from(conn).map_err(ConnectorAgentPythonError::ConnectorAgentError)?;
     let queries = match (queries, partition_query) {
@@ -76,7 +92,7 @@ pub fn read_sql<'a>(
                 };
                 let partition_query = source_conn
                     .ty
-                    .get_part_query(&query, &col, lower, upper)
+                    .get_part_query(&query, &col, lower, upper, i == num - 1)
                     .map_err(ConnectorAgentPythonError::ConnectorAgentError)?;
                 queries.push(partition_query);
             }
@@ -92,17 +108,40 @@ pub fn read_sql<'a>(
 
     let queries: Vec<_> = queries.iter().map(|s| s.as_str()).collect();
     match return_type {
+        "pandas" if arrow_dtypes.unwrap_or(false) => {
+            if max_cell_bytes.is_some() {
+                throw!(PyValueError::new_err(
+                    "max_cell_bytes is not supported together with arrow_dtypes",
+                ));
+            }
+            Ok(crate::pandas::write_pandas_arrow(
+                py,
+                &source_conn,
+                &queries,
+                protocol.unwrap_or("binary"),
+            )?)
+        }
         "pandas" => Ok(crate::pandas::write_pandas(
             py,
             &source_conn,
             &queries,
             protocol.unwrap_or("binary"),
+            max_cell_bytes,
         )?),
         "arrow" => Err(PyNotImplementedError::new_err(
             "arrow return type is not implemented",
         )),
+        "arrow_stream" => {
+            let stream = crate::arrow::stream_arrow(
+                &source_conn,
+                &queries,
+                protocol.unwrap_or("binary"),
+                batch_size,
+            )?;
+            Ok(Py::new(py, stream)?.into_py(py).into_ref(py))
+        }
         _ => Err(PyValueError::new_err(format!(
-            "return type should be 'pandas' or 'arrow', got '{}'",
+            "return type should be 'pandas', 'arrow' or 'arrow_stream', got '{}'",
             return_type
         ))),
     }
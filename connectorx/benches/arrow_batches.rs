@@ -0,0 +1,75 @@
+use arrow::array::{ArrayRef, Float64Array, Int64Array, LargeStringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use connectorx::{
+    destinations::arrow::ArrowDestination, sources::arrow::ArrowSource,
+    transports::ArrowArrowTransport, Dispatcher,
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::env;
+use std::fs::File;
+use std::sync::Arc;
+
+const NFILES: usize = 4;
+const NBATCHES: usize = 16;
+const BATCH_ROWS: usize = 8192;
+
+fn write_files() -> Vec<String> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("x", DataType::Float64, true),
+        Field::new("s", DataType::LargeUtf8, true),
+    ]));
+
+    (0..NFILES)
+        .map(|i| {
+            let path = env::temp_dir().join(format!("arrow_batches_{}.arrow", i));
+            let mut writer = FileWriter::try_new(File::create(&path).unwrap(), &schema).unwrap();
+            for _ in 0..NBATCHES {
+                let ids: ArrayRef =
+                    Arc::new(Int64Array::from((0..BATCH_ROWS as i64).collect::<Vec<_>>()));
+                let xs: ArrayRef = Arc::new(Float64Array::from(
+                    (0..BATCH_ROWS)
+                        .map(|r| if r % 10 == 0 { None } else { Some(r as f64) })
+                        .collect::<Vec<_>>(),
+                ));
+                let ss: ArrayRef = Arc::new(LargeStringArray::from(
+                    (0..BATCH_ROWS)
+                        .map(|r| format!("value {}", r))
+                        .collect::<Vec<_>>()
+                        .iter()
+                        .map(|s| Some(s.as_str()))
+                        .collect::<Vec<_>>(),
+                ));
+                let batch = RecordBatch::try_new(schema.clone(), vec![ids, xs, ss]).unwrap();
+                writer.write(&batch).unwrap();
+            }
+            writer.finish().unwrap();
+            path.to_str().unwrap().to_string()
+        })
+        .collect()
+}
+
+fn run(files: &[String], cell_by_cell: bool) {
+    let mut destination = ArrowDestination::new();
+    let mut dispatcher =
+        Dispatcher::<_, _, ArrowArrowTransport>::new(ArrowSource::new(), &mut destination, files);
+    if cell_by_cell {
+        dispatcher = dispatcher.cell_by_cell();
+    }
+    dispatcher.run().unwrap();
+}
+
+fn bench_arrow_batches(c: &mut Criterion) {
+    let files = write_files();
+
+    let mut group = c.benchmark_group("arrow_to_arrow");
+    group.sample_size(10);
+    group.bench_function("batches", |b| b.iter(|| run(&files, false)));
+    group.bench_function("cell_by_cell", |b| b.iter(|| run(&files, true)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_arrow_batches);
+criterion_main!(benches);
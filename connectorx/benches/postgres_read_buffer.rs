@@ -0,0 +1,87 @@
+use connectorx::{
+    destinations::memory::MemoryDestination,
+    sources::{
+        postgres::{Binary, PostgresSource},
+        Source,
+    },
+    transports::PostgresMemoryTransport,
+    Dispatcher,
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::env;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+use url::Url;
+
+const LATENCY: Duration = Duration::from_millis(5);
+
+// Forward connections on a local port to the server, holding back every chunk the client
+// sends by LATENCY, so that each round-trip takes at least that long.
+fn latency_proxy(server: String) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for client in listener.incoming() {
+            let client = client.unwrap();
+            let upstream = TcpStream::connect(&server).unwrap();
+            client.set_nodelay(true).unwrap();
+            upstream.set_nodelay(true).unwrap();
+            let (mut client_rx, mut upstream_tx) = (client.try_clone().unwrap(), upstream);
+            let (mut upstream_rx, mut client_tx) = (upstream_tx.try_clone().unwrap(), client);
+            thread::spawn(move || {
+                let mut buf = [0; 8192];
+                while let Ok(n) = client_rx.read(&mut buf) {
+                    if n == 0 || upstream_tx.write_all(&buf[..n]).is_err() {
+                        break;
+                    }
+                    thread::sleep(LATENCY);
+                }
+            });
+            thread::spawn(move || std::io::copy(&mut upstream_rx, &mut client_tx));
+        }
+    });
+    port
+}
+
+fn proxied_url() -> String {
+    let mut url = Url::parse(&env::var("POSTGRES_URL").unwrap()).unwrap();
+    let server = format!("{}:{}", url.host_str().unwrap(), url.port().unwrap_or(5432));
+    let port = latency_proxy(server);
+    url.set_host(Some("127.0.0.1")).unwrap();
+    url.set_port(Some(port)).unwrap();
+    url.to_string()
+}
+
+fn run(url: &str, flow_control: bool, rows: usize) {
+    let queries = ["select i, i::text as s from generate_series(1, 20000) i"];
+    let mut source = PostgresSource::<Binary>::new(url, 1).unwrap();
+    if flow_control {
+        source.flow_control(rows).unwrap();
+    }
+    source.set_read_buffer(rows).unwrap();
+    let mut destination = MemoryDestination::new();
+    Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(source, &mut destination, &queries)
+        .run()
+        .unwrap();
+}
+
+fn bench_read_buffer(c: &mut Criterion) {
+    let url = proxied_url();
+
+    let mut group = c.benchmark_group("postgres_read_buffer_5ms");
+    group.sample_size(10);
+    for &rows in &[256, 4096] {
+        group.bench_function(format!("copy_{}", rows), |b| {
+            b.iter(|| run(&url, false, rows))
+        });
+        group.bench_function(format!("cursor_{}", rows), |b| {
+            b.iter(|| run(&url, true, rows))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_read_buffer);
+criterion_main!(benches);
@@ -0,0 +1,106 @@
+use connectorx::{
+    destinations::memory::MemoryDestination,
+    sources::{
+        dummy::DummySource,
+        postgres::{Binary, PostgresSource},
+    },
+    transports::{DummyMemoryTransport, PostgresMemoryTransport},
+    Dispatcher, DummyTypeSystem,
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+use postgres::{Client, NoTls};
+use std::env;
+
+// one partition holds most of the rows
+fn skewed_queries() -> Vec<String> {
+    let mut queries = vec!["200000,4".to_string()];
+    queries.extend((0..15).map(|_| "5000,4".to_string()));
+    queries
+}
+
+fn run(queries: &[String], budget: Option<(usize, usize)>) {
+    let schema = [
+        DummyTypeSystem::I64(false),
+        DummyTypeSystem::F64(true),
+        DummyTypeSystem::String(true),
+        DummyTypeSystem::Bool(false),
+    ];
+    let mut destination = MemoryDestination::new();
+    let mut dispatcher = Dispatcher::<_, _, DummyMemoryTransport>::new(
+        DummySource::new(&["a", "b", "c", "d"], &schema),
+        &mut destination,
+        queries,
+    );
+    if let Some((concurrency, batch_rows)) = budget {
+        dispatcher = dispatcher.with_read_budget(concurrency, batch_rows);
+    }
+    dispatcher.run().unwrap();
+}
+
+fn bench_read_budget(c: &mut Criterion) {
+    let queries = skewed_queries();
+
+    let mut group = c.benchmark_group("skewed_partitions");
+    group.sample_size(10);
+    group.bench_function("par_iter", |b| b.iter(|| run(&queries, None)));
+    group.bench_function("budget_4x1024", |b| {
+        b.iter(|| run(&queries, Some((4, 1024))))
+    });
+    group.bench_function("budget_4x16384", |b| {
+        b.iter(|| run(&queries, Some((4, 16384))))
+    });
+    group.finish();
+}
+
+// A table whose key 0 holds 200k rows and the keys 1 to 15 5k rows each, for one partition
+// per key.
+fn skewed_table(url: &str) -> Vec<String> {
+    let mut client = Client::connect(url, NoTls).unwrap();
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS bench_skewed;
+             CREATE TABLE bench_skewed AS
+                 SELECT i, i::text AS s, CASE WHEN i <= 200000 THEN 0 ELSE 1 + i % 15 END AS k
+                 FROM generate_series(1, 275000) i;",
+        )
+        .unwrap();
+    (0..16)
+        .map(|k| format!("select i, s from bench_skewed where k = {}", k))
+        .collect()
+}
+
+fn run_postgres(url: &str, queries: &[String], budget: Option<(usize, usize)>) {
+    // every partition holds a connection of its own
+    let source = PostgresSource::<Binary>::new(url, queries.len()).unwrap();
+    let mut destination = MemoryDestination::new();
+    let mut dispatcher =
+        Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(source, &mut destination, queries);
+    if let Some((concurrency, batch_rows)) = budget {
+        dispatcher = dispatcher.with_read_budget(concurrency, batch_rows);
+    }
+    dispatcher.run().unwrap();
+}
+
+fn bench_read_budget_postgres(c: &mut Criterion) {
+    let url = match env::var("POSTGRES_URL") {
+        Ok(url) => url,
+        Err(_) => return,
+    };
+    let queries = skewed_table(&url);
+
+    let mut group = c.benchmark_group("postgres_skewed_table");
+    group.sample_size(10);
+    group.bench_function("par_iter", |b| {
+        b.iter(|| run_postgres(&url, &queries, None))
+    });
+    group.bench_function("budget_4x4096", |b| {
+        b.iter(|| run_postgres(&url, &queries, Some((4, 4096))))
+    });
+    group.finish();
+
+    let mut client = Client::connect(&url, NoTls).unwrap();
+    client.batch_execute("DROP TABLE bench_skewed").unwrap();
+}
+
+criterion_group!(benches, bench_read_budget, bench_read_budget_postgres);
+criterion_main!(benches);
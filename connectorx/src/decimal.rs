@@ -0,0 +1,96 @@
+use crate::errors::ConnectorAgentError;
+use anyhow::anyhow;
+use fehler::{throw, throws};
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::str::FromStr;
+
+/// How a decimal is rounded when its scale has to be reduced.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DecimalRounding {
+    /// Ties go away from zero: 1.235 -> 1.24, -1.235 -> -1.24.
+    HalfUp,
+    /// Ties go to the even neighbour (banker's rounding): 1.225 -> 1.22, 1.235 -> 1.24.
+    HalfEven,
+    /// Drop the extra digits, i.e. round toward zero.
+    Down,
+    /// Round away from zero whenever a non-zero digit is dropped.
+    Up,
+}
+
+impl Default for DecimalRounding {
+    fn default() -> Self {
+        DecimalRounding::HalfEven
+    }
+}
+
+impl DecimalRounding {
+    /// Reduce `val` to at most `scale` fractional digits. Values that already fit are
+    /// returned unchanged.
+    pub fn rescale(&self, val: Decimal, scale: u32) -> Decimal {
+        let strategy = match self {
+            DecimalRounding::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            DecimalRounding::HalfEven => RoundingStrategy::MidpointNearestEven,
+            DecimalRounding::Down => RoundingStrategy::ToZero,
+            DecimalRounding::Up => RoundingStrategy::AwayFromZero,
+        };
+        val.round_dp_with_strategy(scale, strategy)
+    }
+}
+
+/// How a decimal written as text separates its fraction and groups its digits, e.g.
+/// `DecimalFormat::new(',', Some('.'))` for `1.234.567,89` in a German locale. The default
+/// is a `.` before the fraction and no grouping, which is how Postgres writes a `numeric`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct DecimalFormat {
+    decimal_separator: char,
+    grouping_separator: Option<char>,
+}
+
+impl Default for DecimalFormat {
+    fn default() -> Self {
+        DecimalFormat {
+            decimal_separator: '.',
+            grouping_separator: None,
+        }
+    }
+}
+
+impl DecimalFormat {
+    #[throws(ConnectorAgentError)]
+    pub fn new(decimal_separator: char, grouping_separator: Option<char>) -> Self {
+        if grouping_separator == Some(decimal_separator) {
+            throw!(anyhow!(
+                "the decimal and the grouping separator must differ, both are {:?}",
+                decimal_separator
+            ));
+        }
+        DecimalFormat {
+            decimal_separator,
+            grouping_separator,
+        }
+    }
+
+    /// Parse `text` in this format. The grouping separators are dropped wherever they are,
+    /// but a `.` is only taken for the fraction if it is the decimal separator. None if
+    /// `text` is not a decimal.
+    pub fn parse(&self, text: &str) -> Option<Decimal> {
+        let text = text.trim();
+        if *self == DecimalFormat::default() {
+            return Decimal::from_str(text).ok();
+        }
+        let mut plain = String::with_capacity(text.len());
+        for c in text.chars() {
+            if Some(c) == self.grouping_separator {
+                continue;
+            }
+            if c == self.decimal_separator {
+                plain.push('.');
+            } else if c == '.' {
+                return None;
+            } else {
+                plain.push(c);
+            }
+        }
+        Decimal::from_str(&plain).ok()
+    }
+}
@@ -0,0 +1,441 @@
+use super::callback::IntoValue;
+use super::memory::Value;
+use super::{Consume, Destination, DestinationPartition};
+use crate::data_order::DataOrder;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::errors::{ConnectorAgentError, Result};
+use crate::typesystem::{TypeAssoc, TypeSystem};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use fehler::{throw, throws};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+/// An aggregate function of an `AggregateDestination`. As in SQL, they skip nulls, and all but
+/// `Count` are null for a group without a single non-null value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Aggregate {
+    /// Sum of an integer or float column, as the column's type.
+    Sum,
+    /// Number of non-null values of any column.
+    Count,
+    Min,
+    Max,
+    /// Mean of an integer or float column, as a float.
+    Avg,
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Aggregate::Sum => "sum",
+            Aggregate::Count => "count",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+            Aggregate::Avg => "avg",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Computes a group-by while loading instead of storing the rows. Each partition aggregates
+/// its rows into its own hash table, and `finish` merges the tables. Rows with a null in a
+/// group-by column form their own group, as with SQL `GROUP BY`.
+pub struct AggregateDestination {
+    group_by: Vec<String>,
+    aggregates: Vec<(String, Aggregate)>,
+    nrows: usize,
+    schema: Vec<DummyTypeSystem>,
+    key_cols: Vec<usize>,
+    agg_cols: Vec<(usize, DummyTypeSystem, Aggregate)>,
+    groups: Vec<Groups>,
+}
+
+impl AggregateDestination {
+    /// Group by the columns `group_by` and compute each of `aggregates`, given as a column
+    /// name and the function to apply to it.
+    pub fn new<S: AsRef<str>>(group_by: &[S], aggregates: &[(S, Aggregate)]) -> Self {
+        AggregateDestination {
+            group_by: group_by.iter().map(|c| c.as_ref().to_string()).collect(),
+            aggregates: aggregates
+                .iter()
+                .map(|(c, agg)| (c.as_ref().to_string(), *agg))
+                .collect(),
+            nrows: 0,
+            schema: vec![],
+            key_cols: vec![],
+            agg_cols: vec![],
+            groups: vec![],
+        }
+    }
+
+    /// The names of the result columns: the group-by columns, then an aggregate like `sum(x)`
+    /// for each of the aggregates.
+    pub fn names(&self) -> Vec<String> {
+        self.group_by
+            .iter()
+            .cloned()
+            .chain(
+                self.aggregates
+                    .iter()
+                    .map(|(col, agg)| format!("{}({})", agg, col)),
+            )
+            .collect()
+    }
+
+    /// Merge the partitions and return one row per group, in the order the groups first
+    /// appear in the queries. A row holds the values of the group-by columns followed by
+    /// those of the aggregates.
+    #[throws(ConnectorAgentError)]
+    pub fn finish(self) -> Vec<Vec<Value>> {
+        let mut partitions = self.groups.into_iter();
+        let mut merged = partitions.next().unwrap_or_default();
+        for groups in partitions {
+            merged.merge(groups)?;
+        }
+
+        merged
+            .groups
+            .into_iter()
+            .map(|(key, states)| {
+                key.into_iter()
+                    .map(Key::into_value)
+                    .chain(states.into_iter().map(State::into_value))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl Destination for AggregateDestination {
+    const DATA_ORDERS: &'static [DataOrder] = &[DataOrder::RowMajor];
+    type TypeSystem = DummyTypeSystem;
+    type Partition<'a> = AggregatePartitionDestination<'a>;
+
+    #[throws(ConnectorAgentError)]
+    fn allocate<S: AsRef<str>>(
+        &mut self,
+        nrows: usize,
+        names: &[S],
+        schema: &[DummyTypeSystem],
+        data_order: DataOrder,
+    ) {
+        if !matches!(data_order, DataOrder::RowMajor) {
+            throw!(ConnectorAgentError::UnsupportedDataOrder(data_order))
+        }
+
+        let position = |col: &str| {
+            names
+                .iter()
+                .position(|n| n.as_ref() == col)
+                .ok_or_else(|| anyhow!("column {} is not in the result", col))
+        };
+
+        let mut key_cols = vec![];
+        for col in &self.group_by {
+            let i = position(col)?;
+            if !Key::supports(schema[i]) {
+                throw!(anyhow!("cannot group by {}, a {:?} column", col, schema[i]));
+            }
+            key_cols.push(i);
+        }
+
+        let mut agg_cols = vec![];
+        for (col, agg) in &self.aggregates {
+            let i = position(col)?;
+            if State::new(*agg, schema[i]).is_none() {
+                throw!(anyhow!(
+                    "cannot take the {} of {}, a {:?} column",
+                    agg,
+                    col,
+                    schema[i]
+                ));
+            }
+            agg_cols.push((i, schema[i], *agg));
+        }
+
+        self.nrows = nrows;
+        self.schema = schema.to_vec();
+        self.key_cols = key_cols;
+        self.agg_cols = agg_cols;
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn partition(&mut self, counts: &[usize]) -> Vec<Self::Partition<'_>> {
+        assert_eq!(counts.iter().sum::<usize>(), self.nrows);
+        assert_eq!(self.groups.len(), 0);
+
+        self.groups = counts.iter().map(|_| Groups::default()).collect();
+        let (schema, key_cols, agg_cols) = (&self.schema, &self.key_cols, &self.agg_cols);
+        self.groups
+            .iter_mut()
+            .zip(counts)
+            .map(|(groups, &nrows)| AggregatePartitionDestination {
+                schema,
+                key_cols,
+                agg_cols,
+                groups,
+                row: Vec::with_capacity(schema.len()),
+                nrows,
+            })
+            .collect()
+    }
+
+    fn schema(&self) -> &[DummyTypeSystem] {
+        self.schema.as_slice()
+    }
+}
+
+pub struct AggregatePartitionDestination<'a> {
+    schema: &'a [DummyTypeSystem],
+    key_cols: &'a [usize],
+    agg_cols: &'a [(usize, DummyTypeSystem, Aggregate)],
+    groups: &'a mut Groups,
+    row: Vec<Value>,
+    nrows: usize,
+}
+
+impl<'a> AggregatePartitionDestination<'a> {
+    fn row_done(&mut self) -> Result<()> {
+        let key: Vec<Key> = self
+            .key_cols
+            .iter()
+            .map(|&col| Key::from_value(&self.row[col]))
+            .collect();
+        let agg_cols = self.agg_cols;
+        let states = self.groups.entry(key, || {
+            agg_cols
+                .iter()
+                .filter_map(|&(_, ty, agg)| State::new(agg, ty))
+                .collect()
+        });
+        for (state, &(col, _, _)) in states.iter_mut().zip(agg_cols) {
+            state.update(&self.row[col])?;
+        }
+        self.row.clear();
+        Ok(())
+    }
+}
+
+impl<'a> DestinationPartition<'a> for AggregatePartitionDestination<'a> {
+    type TypeSystem = DummyTypeSystem;
+
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    fn ncols(&self) -> usize {
+        self.schema.len()
+    }
+}
+
+impl<'a, T> Consume<T> for AggregatePartitionDestination<'a>
+where
+    T: TypeAssoc<<Self as DestinationPartition<'a>>::TypeSystem> + IntoValue,
+{
+    fn consume(&mut self, value: T) -> Result<()> {
+        self.schema[self.row.len()].check::<T>()?;
+        self.row.push(value.into_value());
+
+        if self.row.len() == self.schema.len() {
+            self.row_done()?;
+        }
+        Ok(())
+    }
+}
+
+/// The groups of a partition, in the order they first appear.
+#[derive(Default)]
+struct Groups {
+    index: HashMap<Vec<Key>, usize>,
+    groups: Vec<(Vec<Key>, Vec<State>)>,
+}
+
+impl Groups {
+    fn entry(&mut self, key: Vec<Key>, new: impl FnOnce() -> Vec<State>) -> &mut Vec<State> {
+        let i = match self.index.get(&key) {
+            Some(&i) => i,
+            None => {
+                let i = self.groups.len();
+                self.index.insert(key.clone(), i);
+                self.groups.push((key, new()));
+                i
+            }
+        };
+        &mut self.groups[i].1
+    }
+
+    fn merge(&mut self, other: Groups) -> Result<()> {
+        for (key, states) in other.groups {
+            match self.index.get(&key) {
+                Some(&i) => {
+                    for (state, other) in self.groups[i].1.iter_mut().zip(states) {
+                        state.merge(other)?;
+                    }
+                }
+                None => {
+                    self.index.insert(key.clone(), self.groups.len());
+                    self.groups.push((key, states));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A group-by value. Floats are kept as bits, with all zeros and all NaNs made the same, so
+/// that they group like in SQL.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Key {
+    Null,
+    F64(u64),
+    I64(i64),
+    Bool(bool),
+    String(String),
+    DateTime(DateTime<Utc>),
+}
+
+impl Key {
+    fn supports(ty: DummyTypeSystem) -> bool {
+        use DummyTypeSystem::*;
+        matches!(ty, F64(_) | I64(_) | Bool(_) | String(_) | DateTime(_))
+    }
+
+    fn from_value(value: &Value) -> Key {
+        match value {
+            Value::F64(v) if *v == 0. => Key::F64(0f64.to_bits()),
+            Value::F64(v) if v.is_nan() => Key::F64(f64::NAN.to_bits()),
+            Value::F64(v) => Key::F64(v.to_bits()),
+            Value::I64(v) => Key::I64(*v),
+            Value::Bool(v) => Key::Bool(*v),
+            Value::String(v) => Key::String(v.clone()),
+            Value::DateTime(v) => Key::DateTime(*v),
+            // allocate only lets the types above through
+            _ => Key::Null,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        match self {
+            Key::Null => Value::Null,
+            Key::F64(bits) => Value::F64(f64::from_bits(bits)),
+            Key::I64(v) => Value::I64(v),
+            Key::Bool(v) => Value::Bool(v),
+            Key::String(v) => Value::String(v),
+            Key::DateTime(v) => Value::DateTime(v),
+        }
+    }
+}
+
+/// The running state of an aggregate of one group.
+enum State {
+    Count(i64),
+    SumI64(Option<i64>),
+    SumF64(Option<f64>),
+    Avg(f64, i64),
+    Min(Option<Value>),
+    Max(Option<Value>),
+}
+
+impl State {
+    /// None if `agg` cannot be taken of a `ty` column.
+    fn new(agg: Aggregate, ty: DummyTypeSystem) -> Option<State> {
+        use DummyTypeSystem::*;
+        match (agg, ty) {
+            (Aggregate::Count, _) => Some(State::Count(0)),
+            (Aggregate::Sum, I64(_)) => Some(State::SumI64(None)),
+            (Aggregate::Sum, F64(_)) => Some(State::SumF64(None)),
+            (Aggregate::Avg, I64(_)) | (Aggregate::Avg, F64(_)) => Some(State::Avg(0., 0)),
+            (Aggregate::Min, ty) if Key::supports(ty) => Some(State::Min(None)),
+            (Aggregate::Max, ty) if Key::supports(ty) => Some(State::Max(None)),
+            _ => None,
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn update(&mut self, value: &Value) {
+        match (self, value) {
+            (_, Value::Null) => {}
+            (State::Count(n), _) => *n += 1,
+            (State::SumI64(sum), Value::I64(v)) => *sum = Some(add_i64(*sum, *v)?),
+            (State::SumF64(sum), Value::F64(v)) => *sum = Some(sum.unwrap_or(0.) + v),
+            (State::Avg(sum, n), Value::I64(v)) => {
+                *sum += *v as f64;
+                *n += 1;
+            }
+            (State::Avg(sum, n), Value::F64(v)) => {
+                *sum += v;
+                *n += 1;
+            }
+            (State::Min(min), v) => keep(min, v, Ordering::Less)?,
+            (State::Max(max), v) => keep(max, v, Ordering::Greater)?,
+            (_, v) => throw!(anyhow!("cannot aggregate {:?}", v)),
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn merge(&mut self, other: State) {
+        match (self, other) {
+            (State::Count(n), State::Count(m)) => *n += m,
+            (State::SumI64(sum), State::SumI64(Some(v))) => *sum = Some(add_i64(*sum, v)?),
+            (State::SumF64(sum), State::SumF64(Some(v))) => *sum = Some(sum.unwrap_or(0.) + v),
+            (State::Avg(sum, n), State::Avg(other_sum, m)) => {
+                *sum += other_sum;
+                *n += m;
+            }
+            (State::Min(min), State::Min(Some(v))) => keep(min, &v, Ordering::Less)?,
+            (State::Max(max), State::Max(Some(v))) => keep(max, &v, Ordering::Greater)?,
+            // the other partition saw no non-null value
+            (State::SumI64(_), State::SumI64(None))
+            | (State::SumF64(_), State::SumF64(None))
+            | (State::Min(_), State::Min(None))
+            | (State::Max(_), State::Max(None)) => {}
+            _ => throw!(anyhow!("cannot merge different aggregates")),
+        }
+    }
+
+    fn into_value(self) -> Value {
+        match self {
+            State::Count(n) => Value::I64(n),
+            State::SumI64(sum) => sum.map_or(Value::Null, Value::I64),
+            State::SumF64(sum) => sum.map_or(Value::Null, Value::F64),
+            State::Avg(_, 0) => Value::Null,
+            State::Avg(sum, n) => Value::F64(sum / n as f64),
+            State::Min(v) | State::Max(v) => v.unwrap_or(Value::Null),
+        }
+    }
+}
+
+fn add_i64(sum: Option<i64>, v: i64) -> Result<i64> {
+    sum.unwrap_or(0)
+        .checked_add(v)
+        .ok_or_else(|| anyhow!("integer overflow in a sum").into())
+}
+
+/// Replace `current` by `value` if it compares as `wanted` to it, or there is none yet.
+fn keep(current: &mut Option<Value>, value: &Value, wanted: Ordering) -> Result<()> {
+    let replace = match current {
+        None => true,
+        Some(cur) => compare(value, cur)? == wanted,
+    };
+    if replace {
+        *current = Some(value.clone());
+    }
+    Ok(())
+}
+
+fn compare(a: &Value, b: &Value) -> Result<Ordering> {
+    let ord = match (a, b) {
+        // NaN is larger than any other float, as in Postgres
+        (Value::F64(a), Value::F64(b)) => a
+            .partial_cmp(b)
+            .unwrap_or_else(|| a.is_nan().cmp(&b.is_nan())),
+        (Value::I64(a), Value::I64(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::DateTime(a), Value::DateTime(b)) => a.cmp(b),
+        _ => return Err(anyhow!("cannot compare {:?} and {:?}", a, b).into()),
+    };
+    Ok(ord)
+}
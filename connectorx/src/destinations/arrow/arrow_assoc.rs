@@ -1,13 +1,20 @@
 use crate::constants::SECONDS_IN_DAY;
+use crate::dummy_typesystem::{Circle, Point, Record, RecordType, RecordValue, Snapshot};
 use crate::errors::{ConnectorAgentError, Result};
+use anyhow::anyhow;
 use arrow::array::{
-    ArrayBuilder, BooleanBuilder, Date32Builder, Date64Builder, Float64Builder, Int32Builder,
-    Int64Builder, LargeStringBuilder,
+    Array, ArrayBuilder, ArrayData, ArrayRef, BooleanBufferBuilder, BooleanBuilder, Date32Builder,
+    Date64Builder, FixedSizeListBuilder, Float64Builder, Int32Builder, Int64Builder,
+    LargeStringBuilder, ListBuilder, NullArray, StructArray, TimestampMillisecondArray,
+    TimestampMillisecondBuilder, UInt64Builder,
 };
 use arrow::datatypes::Field;
-use arrow::datatypes::{DataType as ArrowDataType, DateUnit};
+use arrow::datatypes::{DataType as ArrowDataType, DateUnit, TimeUnit};
 use chrono::{Date, DateTime, NaiveDate, NaiveDateTime, Utc};
-use fehler::throws;
+use fehler::{throw, throws};
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
 /// Associate arrow builder with native type
 pub trait ArrowAssoc {
@@ -86,6 +93,40 @@ impl ArrowAssoc for Option<i64> {
     }
 }
 
+impl ArrowAssoc for u64 {
+    type Builder = UInt64Builder;
+
+    fn builder(nrows: usize) -> UInt64Builder {
+        UInt64Builder::new(nrows)
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut UInt64Builder, value: u64) {
+        builder.append_value(value)?;
+    }
+
+    fn field(header: &str) -> Field {
+        Field::new(header, ArrowDataType::UInt64, false)
+    }
+}
+
+impl ArrowAssoc for Option<u64> {
+    type Builder = UInt64Builder;
+
+    fn builder(nrows: usize) -> UInt64Builder {
+        UInt64Builder::new(nrows)
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut UInt64Builder, value: Option<u64>) {
+        builder.append_option(value)?;
+    }
+
+    fn field(header: &str) -> Field {
+        Field::new(header, ArrowDataType::UInt64, true)
+    }
+}
+
 impl ArrowAssoc for f64 {
     type Builder = Float64Builder;
 
@@ -191,67 +232,112 @@ impl ArrowAssoc for Option<String> {
     }
 }
 
+/// Builds timestamps into a `Timestamp(Millisecond, "UTC")` array. The arrow builder for
+/// timestamps only makes arrays without a time zone, the data is retagged when finished.
+pub struct DateTimeBuilder(TimestampMillisecondBuilder);
+
+fn datetime_type() -> ArrowDataType {
+    ArrowDataType::Timestamp(TimeUnit::Millisecond, Some("UTC".to_string()))
+}
+
+impl ArrayBuilder for DateTimeBuilder {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        let data = self.0.finish().data();
+        let mut builder = ArrayData::builder(datetime_type())
+            .len(data.len())
+            .buffers(data.buffers().to_vec());
+        if let Some(nulls) = data.null_buffer() {
+            builder = builder.null_bit_buffer(nulls.clone());
+        }
+        Arc::new(TimestampMillisecondArray::from(builder.build()))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
 impl ArrowAssoc for DateTime<Utc> {
-    type Builder = Float64Builder;
+    type Builder = DateTimeBuilder;
 
-    fn builder(_nrows: usize) -> Float64Builder {
-        unimplemented!()
+    fn builder(nrows: usize) -> DateTimeBuilder {
+        DateTimeBuilder(TimestampMillisecondBuilder::new(nrows))
     }
 
-    fn append(_builder: &mut Self::Builder, _value: DateTime<Utc>) -> Result<()> {
-        unimplemented!()
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: DateTime<Utc>) {
+        builder.0.append_value(value.timestamp_millis())?;
     }
 
-    fn field(_header: &str) -> Field {
-        unimplemented!()
+    fn field(header: &str) -> Field {
+        Field::new(header, datetime_type(), false)
     }
 }
 
 impl ArrowAssoc for Option<DateTime<Utc>> {
-    type Builder = Float64Builder;
+    type Builder = DateTimeBuilder;
 
-    fn builder(_nrows: usize) -> Float64Builder {
-        unimplemented!()
+    fn builder(nrows: usize) -> DateTimeBuilder {
+        DateTimeBuilder(TimestampMillisecondBuilder::new(nrows))
     }
 
-    fn append(_builder: &mut Self::Builder, _value: Option<DateTime<Utc>>) -> Result<()> {
-        unimplemented!()
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: Option<DateTime<Utc>>) {
+        builder
+            .0
+            .append_option(value.map(|dt| dt.timestamp_millis()))?;
     }
 
-    fn field(_header: &str) -> Field {
-        unimplemented!()
+    fn field(header: &str) -> Field {
+        Field::new(header, datetime_type(), true)
     }
 }
 
 impl ArrowAssoc for Date<Utc> {
-    type Builder = Float64Builder;
+    type Builder = Date32Builder;
 
-    fn builder(_nrows: usize) -> Float64Builder {
-        unimplemented!()
+    fn builder(nrows: usize) -> Date32Builder {
+        Date32Builder::new(nrows)
     }
 
-    fn append(_builder: &mut Self::Builder, _value: Date<Utc>) -> Result<()> {
-        unimplemented!()
+    fn append(builder: &mut Self::Builder, value: Date<Utc>) -> Result<()> {
+        <NaiveDate as ArrowAssoc>::append(builder, value.naive_utc())
     }
 
-    fn field(_header: &str) -> Field {
-        unimplemented!()
+    fn field(header: &str) -> Field {
+        <NaiveDate as ArrowAssoc>::field(header)
     }
 }
 
 impl ArrowAssoc for Option<Date<Utc>> {
-    type Builder = Float64Builder;
+    type Builder = Date32Builder;
 
-    fn builder(_nrows: usize) -> Float64Builder {
-        unimplemented!()
+    fn builder(nrows: usize) -> Date32Builder {
+        Date32Builder::new(nrows)
     }
 
-    fn append(_builder: &mut Self::Builder, _value: Option<Date<Utc>>) -> Result<()> {
-        unimplemented!()
+    fn append(builder: &mut Self::Builder, value: Option<Date<Utc>>) -> Result<()> {
+        <Option<NaiveDate> as ArrowAssoc>::append(builder, value.map(|d| d.naive_utc()))
     }
 
-    fn field(_header: &str) -> Field {
-        unimplemented!()
+    fn field(header: &str) -> Field {
+        <Option<NaiveDate> as ArrowAssoc>::field(header)
     }
 }
 
@@ -330,3 +416,547 @@ impl ArrowAssoc for NaiveDateTime {
         Field::new(header, ArrowDataType::Date64(DateUnit::Millisecond), false)
     }
 }
+
+/// Extension name tagged on point columns, which are stored as `FixedSizeList<Float64, 2>`.
+pub const POINT_EXTENSION_NAME: &str = "geoarrow.point";
+
+/// Extension name tagged on circle columns, which are stored as `FixedSizeList<Float64, 3>`
+/// of the x and y of the center, then the radius.
+pub const CIRCLE_EXTENSION_NAME: &str = "connectorx.circle";
+
+// A `FixedSizeList<Float64, size>` field tagged with the extension name `extension`.
+fn extension_field(header: &str, extension: &str, size: i32, nullable: bool) -> Field {
+    let mut field = Field::new(
+        header,
+        ArrowDataType::FixedSizeList(
+            Box::new(Field::new("item", ArrowDataType::Float64, true)),
+            size,
+        ),
+        nullable,
+    );
+    let mut metadata = BTreeMap::new();
+    metadata.insert("ARROW:extension:name".to_string(), extension.to_string());
+    field.set_metadata(Some(metadata));
+    field
+}
+
+impl ArrowAssoc for Point {
+    type Builder = FixedSizeListBuilder<Float64Builder>;
+
+    fn builder(nrows: usize) -> Self::Builder {
+        FixedSizeListBuilder::new(Float64Builder::new(nrows * 2), 2)
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: Point) {
+        builder.values().append_value(value.x)?;
+        builder.values().append_value(value.y)?;
+        builder.append(true)?;
+    }
+
+    fn field(header: &str) -> Field {
+        extension_field(header, POINT_EXTENSION_NAME, 2, false)
+    }
+}
+
+impl ArrowAssoc for Option<Point> {
+    type Builder = FixedSizeListBuilder<Float64Builder>;
+
+    fn builder(nrows: usize) -> Self::Builder {
+        FixedSizeListBuilder::new(Float64Builder::new(nrows * 2), 2)
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: Option<Point>) {
+        match value {
+            Some(p) => <Point as ArrowAssoc>::append(builder, p)?,
+            None => {
+                // a null list slot still takes up its two child values
+                builder.values().append_null()?;
+                builder.values().append_null()?;
+                builder.append(false)?;
+            }
+        }
+    }
+
+    fn field(header: &str) -> Field {
+        extension_field(header, POINT_EXTENSION_NAME, 2, true)
+    }
+}
+
+impl ArrowAssoc for Circle {
+    type Builder = FixedSizeListBuilder<Float64Builder>;
+
+    fn builder(nrows: usize) -> Self::Builder {
+        FixedSizeListBuilder::new(Float64Builder::new(nrows * 3), 3)
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: Circle) {
+        builder.values().append_value(value.center.x)?;
+        builder.values().append_value(value.center.y)?;
+        builder.values().append_value(value.radius)?;
+        builder.append(true)?;
+    }
+
+    fn field(header: &str) -> Field {
+        extension_field(header, CIRCLE_EXTENSION_NAME, 3, false)
+    }
+}
+
+impl ArrowAssoc for Option<Circle> {
+    type Builder = FixedSizeListBuilder<Float64Builder>;
+
+    fn builder(nrows: usize) -> Self::Builder {
+        FixedSizeListBuilder::new(Float64Builder::new(nrows * 3), 3)
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: Option<Circle>) {
+        match value {
+            Some(c) => <Circle as ArrowAssoc>::append(builder, c)?,
+            None => {
+                for _ in 0..3 {
+                    builder.values().append_null()?;
+                }
+                builder.append(false)?;
+            }
+        }
+    }
+
+    fn field(header: &str) -> Field {
+        extension_field(header, CIRCLE_EXTENSION_NAME, 3, true)
+    }
+}
+
+/// Builds snapshots into a `Struct<xmin: UInt64, xmax: UInt64, xip: List<UInt64>>`, the ids
+/// being unsigned like `xid8` columns.
+pub struct SnapshotBuilder {
+    xmin: UInt64Builder,
+    xmax: UInt64Builder,
+    xip: ListBuilder<UInt64Builder>,
+    validity: BooleanBufferBuilder,
+    len: usize,
+}
+
+fn snapshot_fields() -> Vec<Field> {
+    vec![
+        Field::new("xmin", ArrowDataType::UInt64, false),
+        Field::new("xmax", ArrowDataType::UInt64, false),
+        Field::new(
+            "xip",
+            ArrowDataType::List(Box::new(Field::new("item", ArrowDataType::UInt64, true))),
+            false,
+        ),
+    ]
+}
+
+impl SnapshotBuilder {
+    fn new(nrows: usize) -> Self {
+        SnapshotBuilder {
+            xmin: UInt64Builder::new(nrows),
+            xmax: UInt64Builder::new(nrows),
+            xip: ListBuilder::new(UInt64Builder::new(nrows)),
+            validity: BooleanBufferBuilder::new(nrows),
+            len: 0,
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(&mut self, value: Option<Snapshot>) {
+        match value {
+            Some(snapshot) => {
+                self.xmin.append_value(snapshot.xmin)?;
+                self.xmax.append_value(snapshot.xmax)?;
+                for xid in snapshot.xip {
+                    self.xip.values().append_value(xid)?;
+                }
+                self.xip.append(true)?;
+                self.validity.append(true);
+            }
+            None => {
+                // a null struct slot still takes up a slot in each child, which has no nulls
+                self.xmin.append_value(0)?;
+                self.xmax.append_value(0)?;
+                self.xip.append(true)?;
+                self.validity.append(false);
+            }
+        }
+        self.len += 1;
+    }
+}
+
+impl ArrayBuilder for SnapshotBuilder {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        let children: Vec<ArrayRef> = vec![
+            Arc::new(self.xmin.finish()),
+            Arc::new(self.xmax.finish()),
+            Arc::new(self.xip.finish()),
+        ];
+        self.len = 0;
+        Arc::new(StructArray::from((
+            snapshot_fields()
+                .into_iter()
+                .zip(children)
+                .collect::<Vec<_>>(),
+            self.validity.finish(),
+        )))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl ArrowAssoc for Snapshot {
+    type Builder = SnapshotBuilder;
+
+    fn builder(nrows: usize) -> Self::Builder {
+        SnapshotBuilder::new(nrows)
+    }
+
+    fn append(builder: &mut Self::Builder, value: Snapshot) -> Result<()> {
+        builder.append(Some(value))
+    }
+
+    fn field(header: &str) -> Field {
+        Field::new(header, ArrowDataType::Struct(snapshot_fields()), false)
+    }
+}
+
+impl ArrowAssoc for Option<Snapshot> {
+    type Builder = SnapshotBuilder;
+
+    fn builder(nrows: usize) -> Self::Builder {
+        SnapshotBuilder::new(nrows)
+    }
+
+    fn append(builder: &mut Self::Builder, value: Option<Snapshot>) -> Result<()> {
+        builder.append(value)
+    }
+
+    fn field(header: &str) -> Field {
+        Field::new(header, ArrowDataType::Struct(snapshot_fields()), true)
+    }
+}
+
+fn string_list_type() -> ArrowDataType {
+    ArrowDataType::List(Box::new(Field::new("item", ArrowDataType::LargeUtf8, true)))
+}
+
+impl ArrowAssoc for Vec<Option<String>> {
+    type Builder = ListBuilder<LargeStringBuilder>;
+
+    fn builder(nrows: usize) -> Self::Builder {
+        ListBuilder::new(LargeStringBuilder::new(nrows))
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: Vec<Option<String>>) {
+        for item in value {
+            match item {
+                Some(s) => builder.values().append_value(s.as_str())?,
+                None => builder.values().append_null()?,
+            }
+        }
+        builder.append(true)?;
+    }
+
+    fn field(header: &str) -> Field {
+        Field::new(header, string_list_type(), false)
+    }
+}
+
+impl ArrowAssoc for Option<Vec<Option<String>>> {
+    type Builder = ListBuilder<LargeStringBuilder>;
+
+    fn builder(nrows: usize) -> Self::Builder {
+        ListBuilder::new(LargeStringBuilder::new(nrows))
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(builder: &mut Self::Builder, value: Option<Vec<Option<String>>>) {
+        match value {
+            Some(list) => <Vec<Option<String>> as ArrowAssoc>::append(builder, list)?,
+            None => builder.append(false)?,
+        }
+    }
+
+    fn field(header: &str) -> Field {
+        Field::new(header, string_list_type(), true)
+    }
+}
+
+/// The `Struct` type of records with `fields`. Every field is nullable, as the fields of a
+/// composite type are.
+pub fn record_type(fields: &[(String, RecordType)]) -> ArrowDataType {
+    ArrowDataType::Struct(
+        fields
+            .iter()
+            .map(|(name, ty)| {
+                let dt = match ty {
+                    RecordType::I64 => ArrowDataType::Int64,
+                    RecordType::F64 => ArrowDataType::Float64,
+                    RecordType::Bool => ArrowDataType::Boolean,
+                    RecordType::String => ArrowDataType::LargeUtf8,
+                    RecordType::Record(fields) => record_type(fields),
+                };
+                Field::new(name, dt, true)
+            })
+            .collect(),
+    )
+}
+
+enum FieldBuilder {
+    I64(Int64Builder),
+    F64(Float64Builder),
+    Bool(BooleanBuilder),
+    String(LargeStringBuilder),
+    Record(StructColumn),
+}
+
+impl FieldBuilder {
+    fn new(ty: &RecordType, nrows: usize) -> Self {
+        match ty {
+            RecordType::I64 => FieldBuilder::I64(Int64Builder::new(nrows)),
+            RecordType::F64 => FieldBuilder::F64(Float64Builder::new(nrows)),
+            RecordType::Bool => FieldBuilder::Bool(BooleanBuilder::new(nrows)),
+            RecordType::String => FieldBuilder::String(LargeStringBuilder::new(nrows)),
+            RecordType::Record(fields) => FieldBuilder::Record(StructColumn::new(fields, nrows)),
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(&mut self, value: RecordValue) {
+        match (self, value) {
+            (FieldBuilder::I64(b), RecordValue::I64(v)) => b.append_value(v)?,
+            (FieldBuilder::I64(b), RecordValue::Null) => b.append_null()?,
+            (FieldBuilder::F64(b), RecordValue::F64(v)) => b.append_value(v)?,
+            (FieldBuilder::F64(b), RecordValue::Null) => b.append_null()?,
+            (FieldBuilder::Bool(b), RecordValue::Bool(v)) => b.append_value(v)?,
+            (FieldBuilder::Bool(b), RecordValue::Null) => b.append_null()?,
+            (FieldBuilder::String(b), RecordValue::String(v)) => b.append_value(v.as_str())?,
+            (FieldBuilder::String(b), RecordValue::Null) => b.append_null()?,
+            (FieldBuilder::Record(b), RecordValue::Record(values)) => b.append(Some(values))?,
+            (FieldBuilder::Record(b), RecordValue::Null) => b.append(None)?,
+            (_, value) => throw!(anyhow!("record value {:?} does not match its field", value)),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            FieldBuilder::I64(b) => Arc::new(b.finish()),
+            FieldBuilder::F64(b) => Arc::new(b.finish()),
+            FieldBuilder::Bool(b) => Arc::new(b.finish()),
+            FieldBuilder::String(b) => Arc::new(b.finish()),
+            FieldBuilder::Record(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+// The struct of a record type, one child per field. A null record leaves a null in each child.
+struct StructColumn {
+    fields: Vec<(String, RecordType)>,
+    children: Vec<FieldBuilder>,
+    validity: BooleanBufferBuilder,
+    len: usize,
+}
+
+impl StructColumn {
+    fn new(fields: &[(String, RecordType)], nrows: usize) -> Self {
+        StructColumn {
+            fields: fields.to_vec(),
+            children: fields
+                .iter()
+                .map(|(_, ty)| FieldBuilder::new(ty, nrows))
+                .collect(),
+            validity: BooleanBufferBuilder::new(nrows),
+            len: 0,
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(&mut self, values: Option<Vec<RecordValue>>) {
+        match values {
+            Some(values) => {
+                if values.len() != self.children.len() {
+                    throw!(anyhow!(
+                        "record of {} values for {} fields",
+                        values.len(),
+                        self.children.len()
+                    ));
+                }
+                for (child, value) in self.children.iter_mut().zip(values) {
+                    child.append(value)?;
+                }
+                self.validity.append(true);
+            }
+            None => {
+                for child in &mut self.children {
+                    child.append(RecordValue::Null)?;
+                }
+                self.validity.append(false);
+            }
+        }
+        self.len += 1;
+    }
+
+    fn finish(&mut self) -> StructArray {
+        let children = self
+            .children
+            .iter_mut()
+            .map(|c| c.finish().data())
+            .collect();
+        let data = ArrayData::builder(record_type(&self.fields))
+            .len(self.len)
+            .null_bit_buffer(self.validity.finish())
+            .child_data(children)
+            .build();
+        self.len = 0;
+        StructArray::from(data)
+    }
+}
+
+/// Builds records into a `Struct` of their fields, nested records into nested structs. The
+/// builder learns the fields from the first record written to it, until then it only counts
+/// the null ones, and a batch of nothing but nulls comes out as a `NullArray`.
+pub struct RecordBuilder {
+    column: Option<StructColumn>,
+    nulls: usize,
+    nrows: usize,
+}
+
+impl RecordBuilder {
+    fn new(nrows: usize) -> Self {
+        RecordBuilder {
+            column: None,
+            nulls: 0,
+            nrows,
+        }
+    }
+
+    /// The fields of the records written so far, if any was not null.
+    pub fn fields(&self) -> Option<&[(String, RecordType)]> {
+        self.column.as_ref().map(|c| c.fields.as_slice())
+    }
+
+    /// Take `fields` as the fields of the records to come, if none was written yet.
+    #[throws(ConnectorAgentError)]
+    pub fn set_fields(&mut self, fields: &[(String, RecordType)]) {
+        if self.column.is_none() {
+            let mut column = StructColumn::new(fields, self.nrows);
+            for _ in 0..self.nulls {
+                column.append(None)?;
+            }
+            self.column = Some(column);
+            self.nulls = 0;
+        }
+    }
+
+    /// A struct array of `len` null records with `fields`.
+    #[throws(ConnectorAgentError)]
+    pub fn nulls(fields: &[(String, RecordType)], len: usize) -> ArrayRef {
+        let mut builder = RecordBuilder::new(len);
+        builder.nulls = len;
+        builder.set_fields(fields)?;
+        builder.finish()
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn append(&mut self, value: Option<Record>) {
+        match value {
+            Some(record) => {
+                self.set_fields(&record.fields)?;
+                if let Some(column) = &mut self.column {
+                    column.append(Some(record.values))?;
+                }
+            }
+            None => match &mut self.column {
+                Some(column) => column.append(None)?,
+                None => self.nulls += 1,
+            },
+        }
+    }
+}
+
+impl ArrayBuilder for RecordBuilder {
+    fn len(&self) -> usize {
+        self.column.as_ref().map_or(self.nulls, |c| c.len)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match &mut self.column {
+            Some(column) => Arc::new(column.finish()),
+            None => {
+                let nulls = NullArray::new(self.nulls);
+                self.nulls = 0;
+                Arc::new(nulls)
+            }
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+// Until the batches are put together the fields are unknown, see `ArrowDestination::batches`.
+impl ArrowAssoc for Record {
+    type Builder = RecordBuilder;
+
+    fn builder(nrows: usize) -> Self::Builder {
+        RecordBuilder::new(nrows)
+    }
+
+    fn append(builder: &mut Self::Builder, value: Record) -> Result<()> {
+        builder.append(Some(value))
+    }
+
+    fn field(header: &str) -> Field {
+        Field::new(header, ArrowDataType::Null, false)
+    }
+}
+
+impl ArrowAssoc for Option<Record> {
+    type Builder = RecordBuilder;
+
+    fn builder(nrows: usize) -> Self::Builder {
+        RecordBuilder::new(nrows)
+    }
+
+    fn append(builder: &mut Self::Builder, value: Option<Record>) -> Result<()> {
+        builder.append(value)
+    }
+
+    fn field(header: &str) -> Field {
+        Field::new(header, ArrowDataType::Null, true)
+    }
+}
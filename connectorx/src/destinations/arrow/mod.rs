@@ -2,12 +2,14 @@ use super::{Consume, Destination, DestinationPartition};
 use crate::data_order::DataOrder;
 use crate::dummy_typesystem::DummyTypeSystem;
 use crate::errors::{ConnectorAgentError, Result};
+use crate::pseudonym::Pseudonymizer;
 use crate::typesystem::{Realize, TypeAssoc, TypeSystem};
 use anyhow::anyhow;
-use arrow::datatypes::Schema;
+use arrow::array::{ArrayRef, BooleanArray, Float64Array, Int64Array, LargeStringArray};
+use arrow::datatypes::{DataType as ArrowDataType, Field, Schema};
 use arrow::record_batch::RecordBatch;
-use arrow_assoc::ArrowAssoc;
-use fehler::throws;
+use arrow_assoc::{record_type, ArrowAssoc, RecordBuilder};
+use fehler::{throw, throws};
 use funcs::{FFinishBuilder, FNewBuilder, FNewField};
 use itertools::Itertools;
 use std::any::Any;
@@ -16,13 +18,21 @@ use std::sync::Arc;
 mod arrow_assoc;
 mod funcs;
 
+pub use arrow_assoc::{CIRCLE_EXTENSION_NAME, POINT_EXTENSION_NAME};
+
 type Builder = Box<dyn Any + Send>;
 type Builders = Vec<Builder>;
+// columns of the batches a partition has already cut off
+type Chunks = Vec<Vec<ArrayRef>>;
 
 pub struct ArrowDestination {
     nrows: usize,
     schema: Vec<DummyTypeSystem>,
     builders: Vec<Builders>,
+    chunks: Vec<Chunks>,
+    batch_size: Option<usize>,
+    keep_source_batches: bool,
+    pseudonyms: Vec<(usize, Pseudonymizer)>,
 }
 
 impl Default for ArrowDestination {
@@ -31,6 +41,10 @@ impl Default for ArrowDestination {
             nrows: 0,
             schema: vec![],
             builders: vec![],
+            chunks: vec![],
+            batch_size: None,
+            keep_source_batches: false,
+            pseudonyms: vec![],
         }
     }
 }
@@ -39,6 +53,24 @@ impl ArrowDestination {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Cut each partition's output into record batches of at most `batch_size` rows
+    /// instead of producing a single batch per partition.
+    #[throws(ConnectorAgentError)]
+    pub fn batch_size(&mut self, batch_size: usize) {
+        if batch_size == 0 {
+            throw!(anyhow!("batch_size must be positive"));
+        }
+        self.batch_size = Some(batch_size);
+    }
+
+    /// Keep the record batches a partition is handed over as, one output batch per source
+    /// batch, even with a `batch_size`. That spares cutting up batches that already have the
+    /// size the source chose, e.g. those of an Arrow file. Partitions read cell by cell are
+    /// still cut by `batch_size`.
+    pub fn keep_source_batches(&mut self) {
+        self.keep_source_batches = true;
+    }
 }
 
 impl Destination for ArrowDestination {
@@ -65,70 +97,231 @@ impl Destination for ArrowDestination {
         assert_eq!(self.builders.len(), 0);
 
         for &c in counts {
+            let capacity = self.batch_size.map_or(c, |bs| bs.min(c));
             let builders = self
                 .schema
                 .iter()
-                .map(|&dt| Ok(Realize::<FNewBuilder>::realize(dt)?(c)))
+                .map(|&dt| Ok(Realize::<FNewBuilder>::realize(dt)?(capacity)))
                 .collect::<Result<Vec<_>>>()?;
 
             self.builders.push(builders);
+            self.chunks.push(vec![]);
         }
 
         let schema = self.schema.clone();
+        let batch_size = self.batch_size;
+        let keep_source_batches = self.keep_source_batches;
         self.builders
             .iter_mut()
+            .zip(self.chunks.iter_mut())
             .zip(counts)
-            .map(|(builders, &c)| ArrowPartitionWriter::new(schema.clone(), builders, c))
+            .map(|((builders, chunks), &c)| {
+                ArrowPartitionWriter::new(
+                    schema.clone(),
+                    builders,
+                    chunks,
+                    c,
+                    batch_size,
+                    keep_source_batches,
+                )
+            })
             .collect()
     }
 
     fn schema(&self) -> &[DummyTypeSystem] {
         self.schema.as_slice()
     }
+
+    fn partition_index_type() -> Option<DummyTypeSystem> {
+        Some(DummyTypeSystem::I64(false))
+    }
+
+    /// The column becomes a `LargeUtf8` column of the pseudonyms once the batches are built.
+    /// Integer, float, boolean and string columns can be pseudonymized.
+    #[throws(ConnectorAgentError)]
+    fn pseudonymize(&mut self, col: usize, pseudonymizer: Pseudonymizer) {
+        use DummyTypeSystem::*;
+        match self.schema.get(col) {
+            Some(I64(_)) | Some(F64(_)) | Some(Bool(_)) | Some(String(_)) => {
+                self.pseudonyms.push((col, pseudonymizer))
+            }
+            Some(dt) => throw!(anyhow!("cannot pseudonymize a {:?} column", dt)),
+            None => throw!(anyhow!("there is no column {}", col)),
+        }
+    }
 }
 
 impl ArrowDestination {
     #[throws(ConnectorAgentError)]
     pub fn finish(self, headers: Vec<String>) -> Vec<RecordBatch> {
-        let fields = self
+        self.batches(headers)?.collect::<Result<Vec<_>>>()?
+    }
+
+    /// Drain the written data as record batches. Batches come out partition by partition,
+    /// in the order of the queries, and in row order within each partition. Without a
+    /// `batch_size` every partition yields exactly one batch, as `finish` always did, unless
+    /// the partition was handed over as record batches, which are kept as they came.
+    ///
+    /// A record column becomes a `Struct` of the fields of its records, or a `Null` column if
+    /// all of them are null.
+    #[throws(ConnectorAgentError)]
+    pub fn batches(mut self, headers: Vec<String>) -> impl Iterator<Item = Result<RecordBatch>> {
+        let mut fields = self
             .schema
             .iter()
             .zip_eq(headers)
             .map(|(&dt, h)| Ok(Realize::<FNewField>::realize(dt)?(h.as_str())))
             .collect::<Result<Vec<_>>>()?;
+        for (col, &dt) in self.schema.iter().enumerate() {
+            if let DummyTypeSystem::Record(_) = dt {
+                fields[col] =
+                    align_records(col, &fields[col], &mut self.builders, &mut self.chunks)?;
+            }
+        }
+
+        for (col, _) in &self.pseudonyms {
+            let field = &fields[*col];
+            fields[*col] = Field::new(field.name(), ArrowDataType::LargeUtf8, field.is_nullable());
+        }
 
         let arrow_schema = Arc::new(Schema::new(fields));
-        let schema = self.schema.clone();
+        let schema = self.schema;
+        let pseudonyms = self.pseudonyms;
         self.builders
             .into_iter()
-            .map(|pbuilder| {
-                let columns = pbuilder
+            .zip_eq(self.chunks)
+            .flat_map(move |(mut pbuilder, chunks)| {
+                let last = finish_builders(&schema, &mut pbuilder);
+                // skip the trailing batch if the rows divided evenly into the earlier ones
+                let empty_tail = !chunks.is_empty()
+                    && matches!(&last, Ok(cols) if cols.first().map_or(true, |c| c.is_empty()));
+                chunks
                     .into_iter()
-                    .zip(schema.iter())
-                    .map(|(builder, &dt)| Realize::<FFinishBuilder>::realize(dt)?(builder))
-                    .collect::<Result<Vec<_>>>()?;
+                    .map(Ok)
+                    .chain(if empty_tail { None } else { Some(last) })
+            })
+            .map(move |columns| {
+                let mut columns = columns?;
+                for (col, pseudonymizer) in &pseudonyms {
+                    columns[*col] = pseudonymize_array(&columns[*col], pseudonymizer)?;
+                }
                 Ok(RecordBatch::try_new(Arc::clone(&arrow_schema), columns)?)
             })
-            .collect::<Result<Vec<_>>>()?
     }
 }
 
+#[throws(ConnectorAgentError)]
+fn pseudonymize_array(array: &ArrayRef, pseudonymizer: &Pseudonymizer) -> ArrayRef {
+    let any = array.as_any();
+    let pseudonyms: Vec<Option<String>> = if let Some(a) = any.downcast_ref::<Int64Array>() {
+        a.iter()
+            .map(|v| v.map(|v| pseudonymizer.pseudonym(&v.to_string())))
+            .collect()
+    } else if let Some(a) = any.downcast_ref::<Float64Array>() {
+        a.iter()
+            .map(|v| v.map(|v| pseudonymizer.pseudonym(&v.to_string())))
+            .collect()
+    } else if let Some(a) = any.downcast_ref::<BooleanArray>() {
+        a.iter()
+            .map(|v| v.map(|v| pseudonymizer.pseudonym(&v.to_string())))
+            .collect()
+    } else if let Some(a) = any.downcast_ref::<LargeStringArray>() {
+        a.iter()
+            .map(|v| v.map(|v| pseudonymizer.pseudonym(v)))
+            .collect()
+    } else {
+        throw!(anyhow!(
+            "cannot pseudonymize a {:?} column",
+            array.data_type()
+        ))
+    };
+    let pseudonyms: Vec<Option<&str>> = pseudonyms.iter().map(Option::as_deref).collect();
+    Arc::new(LargeStringArray::from(pseudonyms)) as ArrayRef
+}
+
+// A record builder only learns the fields from the records written to it, the partitions and
+// batches that got nothing but null records take them from the others.
+#[throws(ConnectorAgentError)]
+fn align_records(
+    col: usize,
+    field: &Field,
+    builders: &mut [Builders],
+    chunks: &mut [Chunks],
+) -> Field {
+    let mut record_builders = builders
+        .iter_mut()
+        .map(|b| {
+            b[col]
+                .downcast_mut::<RecordBuilder>()
+                .ok_or_else(|| anyhow!("cannot cast arrow builder for records"))
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let fields = match record_builders.iter().find_map(|b| b.fields()) {
+        Some(fields) => fields.to_vec(),
+        None => return Field::new(field.name(), ArrowDataType::Null, field.is_nullable()),
+    };
+
+    for builder in &mut record_builders {
+        builder.set_fields(&fields)?;
+    }
+    for columns in chunks.iter_mut().flatten() {
+        if columns[col].data_type() == &ArrowDataType::Null {
+            columns[col] = RecordBuilder::nulls(&fields, columns[col].len())?;
+        }
+    }
+    Field::new(field.name(), record_type(&fields), field.is_nullable())
+}
+
+fn finish_builders(schema: &[DummyTypeSystem], builders: &mut Builders) -> Result<Vec<ArrayRef>> {
+    builders
+        .iter_mut()
+        .zip(schema.iter())
+        .map(|(builder, &dt)| Realize::<FFinishBuilder>::realize(dt)?(builder))
+        .collect()
+}
+
 pub struct ArrowPartitionWriter<'a> {
     nrows: usize,
     schema: Vec<DummyTypeSystem>,
     builders: &'a mut Builders,
+    chunks: &'a mut Chunks,
+    batch_size: Option<usize>,
+    keep_source_batches: bool,
     current_col: usize,
+    buffered_rows: usize,
 }
 
 impl<'a> ArrowPartitionWriter<'a> {
-    fn new(schema: Vec<DummyTypeSystem>, builders: &'a mut Builders, nrows: usize) -> Self {
+    fn new(
+        schema: Vec<DummyTypeSystem>,
+        builders: &'a mut Builders,
+        chunks: &'a mut Chunks,
+        nrows: usize,
+        batch_size: Option<usize>,
+        keep_source_batches: bool,
+    ) -> Self {
         ArrowPartitionWriter {
             nrows,
             schema,
             builders,
+            chunks,
+            batch_size,
+            keep_source_batches,
             current_col: 0,
+            buffered_rows: 0,
         }
     }
+
+    fn row_done(&mut self) -> Result<()> {
+        self.buffered_rows += 1;
+        if Some(self.buffered_rows) == self.batch_size {
+            // finishing an arrow builder resets it, so the builders are reused for the next batch
+            let columns = finish_builders(&self.schema, self.builders)?;
+            self.chunks.push(columns);
+            self.buffered_rows = 0;
+        }
+        Ok(())
+    }
 }
 
 impl<'a> DestinationPartition<'a> for ArrowPartitionWriter<'a> {
@@ -141,6 +334,47 @@ impl<'a> DestinationPartition<'a> for ArrowPartitionWriter<'a> {
     fn ncols(&self) -> usize {
         self.schema.len()
     }
+
+    fn write_partition_index(&mut self, index: usize) -> Result<()> {
+        self.write(index as i64)
+    }
+
+    fn accepts_batches(&self, schema: &Schema) -> bool {
+        schema.fields().len() == self.schema.len()
+            && schema.fields().iter().zip(&self.schema).all(|(f, &dt)| {
+                matches!(Realize::<FNewField>::realize(dt),
+                    Ok(new_field) if new_field("").data_type() == f.data_type())
+            })
+    }
+
+    /// Take over the columns of the batch without copying them, cut into `batch_size`
+    /// slices if set and not `keep_source_batches`. The rows written cell by cell so far go
+    /// into a batch before them.
+    fn push_batch(&mut self, batch: RecordBatch) -> Result<()> {
+        if self.buffered_rows > 0 {
+            let columns = finish_builders(&self.schema, self.builders)?;
+            self.chunks.push(columns);
+            self.buffered_rows = 0;
+        }
+
+        let nrows = batch.num_rows();
+        match self.batch_size {
+            Some(batch_size) if !self.keep_source_batches => {
+                for offset in (0..nrows).step_by(batch_size) {
+                    let len = batch_size.min(nrows - offset);
+                    self.chunks.push(
+                        batch
+                            .columns()
+                            .iter()
+                            .map(|c| c.slice(offset, len))
+                            .collect(),
+                    );
+                }
+            }
+            _ => self.chunks.push(batch.columns().to_vec()),
+        }
+        Ok(())
+    }
 }
 
 impl<'a, T> Consume<T> for ArrowPartitionWriter<'a>
@@ -160,6 +394,10 @@ where
             value,
         )?;
 
+        if self.current_col == 0 {
+            self.row_done()?;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,316 @@
+use super::memory::Value;
+use super::{Consume, Destination, DestinationPartition};
+use crate::data_order::DataOrder;
+use crate::dummy_typesystem::{Circle, DummyTypeSystem, Point, Record, Snapshot};
+use crate::errors::{ConnectorAgentError, Result};
+use crate::typesystem::{TypeAssoc, TypeSystem};
+use chrono::{DateTime, Utc};
+use fehler::{throw, throws};
+use std::any::type_name;
+use std::sync::Mutex;
+
+type Callback = Box<dyn Fn(RowRef<'_>) -> Result<()> + Send + Sync>;
+
+/// Hands every row to a closure as soon as it is parsed instead of storing it. The partitions
+/// run concurrently, so the closure is called from several threads at once, and rows arrive
+/// in no particular order across partitions.
+pub struct CallbackDestination {
+    f: Callback,
+    nrows: usize,
+    names: Vec<String>,
+    schema: Vec<DummyTypeSystem>,
+}
+
+impl CallbackDestination {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(RowRef<'_>) -> Result<()> + Send + Sync + 'static,
+    {
+        CallbackDestination {
+            f: Box::new(f),
+            nrows: 0,
+            names: vec![],
+            schema: vec![],
+        }
+    }
+
+    /// Like `new`, but the calls to `f` never overlap, so it can keep mutable state. The
+    /// partitions contend for `f`; use `Dispatcher::sequential` to avoid that and to get the
+    /// rows in query order.
+    pub fn serialized<F>(f: F) -> Self
+    where
+        F: FnMut(RowRef<'_>) -> Result<()> + Send + 'static,
+    {
+        let f = Mutex::new(f);
+        Self::new(move |row| {
+            // a panic in an earlier call already fails the run, so a poisoned lock is fine
+            let mut f = f.lock().unwrap_or_else(|e| e.into_inner());
+            (*f)(row)
+        })
+    }
+}
+
+impl Destination for CallbackDestination {
+    const DATA_ORDERS: &'static [DataOrder] = &[DataOrder::RowMajor];
+    type TypeSystem = DummyTypeSystem;
+    type Partition<'a> = CallbackPartitionDestination<'a>;
+
+    #[throws(ConnectorAgentError)]
+    fn allocate<S: AsRef<str>>(
+        &mut self,
+        nrows: usize,
+        names: &[S],
+        schema: &[DummyTypeSystem],
+        data_order: DataOrder,
+    ) {
+        if !matches!(data_order, DataOrder::RowMajor) {
+            throw!(ConnectorAgentError::UnsupportedDataOrder(data_order))
+        }
+
+        self.nrows = nrows;
+        self.names = names.iter().map(|n| n.as_ref().to_string()).collect();
+        self.schema = schema.to_vec();
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn partition(&mut self, counts: &[usize]) -> Vec<Self::Partition<'_>> {
+        assert_eq!(counts.iter().sum::<usize>(), self.nrows);
+
+        let (f, names, schema) = (&self.f, &self.names, &self.schema);
+        counts
+            .iter()
+            .map(|&nrows| CallbackPartitionDestination {
+                f,
+                names,
+                schema,
+                row: Vec::with_capacity(schema.len()),
+                nrows,
+            })
+            .collect()
+    }
+
+    fn schema(&self) -> &[DummyTypeSystem] {
+        self.schema.as_slice()
+    }
+
+    fn partition_index_type() -> Option<DummyTypeSystem> {
+        Some(DummyTypeSystem::I64(false))
+    }
+}
+
+pub struct CallbackPartitionDestination<'a> {
+    f: &'a Callback,
+    names: &'a [String],
+    schema: &'a [DummyTypeSystem],
+    row: Vec<Value>,
+    nrows: usize,
+}
+
+impl<'a> DestinationPartition<'a> for CallbackPartitionDestination<'a> {
+    type TypeSystem = DummyTypeSystem;
+
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    fn ncols(&self) -> usize {
+        self.schema.len()
+    }
+
+    fn write_partition_index(&mut self, index: usize) -> Result<()> {
+        self.write(index as i64)
+    }
+}
+
+impl<'a, T> Consume<T> for CallbackPartitionDestination<'a>
+where
+    T: TypeAssoc<<Self as DestinationPartition<'a>>::TypeSystem> + IntoValue,
+{
+    fn consume(&mut self, value: T) -> Result<()> {
+        self.schema[self.row.len()].check::<T>()?;
+        self.row.push(value.into_value());
+
+        if self.row.len() == self.schema.len() {
+            let res = (self.f)(RowRef {
+                names: self.names,
+                values: &self.row,
+            });
+            self.row.clear();
+            res?;
+        }
+        Ok(())
+    }
+}
+
+/// A row being handed to the closure of a `CallbackDestination`. The typed accessors return
+/// None for a null cell and fail if the column holds another type.
+#[derive(Clone, Copy, Debug)]
+pub struct RowRef<'a> {
+    names: &'a [String],
+    values: &'a [Value],
+}
+
+impl<'a> RowRef<'a> {
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn names(&self) -> &'a [String] {
+        self.names
+    }
+
+    /// The position of the column called `name`.
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub fn value(&self, col: usize) -> &'a Value {
+        self.values
+            .get(col)
+            .ok_or(ConnectorAgentError::OutOfBound)?
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub fn f64(&self, col: usize) -> Option<f64> {
+        match self.value(col)? {
+            Value::Null => None,
+            Value::F64(v) => Some(*v),
+            v => throw!(mismatch::<f64>(v)),
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub fn i64(&self, col: usize) -> Option<i64> {
+        match self.value(col)? {
+            Value::Null => None,
+            Value::I64(v) => Some(*v),
+            v => throw!(mismatch::<i64>(v)),
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub fn u64(&self, col: usize) -> Option<u64> {
+        match self.value(col)? {
+            Value::Null => None,
+            Value::U64(v) => Some(*v),
+            v => throw!(mismatch::<u64>(v)),
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub fn bool(&self, col: usize) -> Option<bool> {
+        match self.value(col)? {
+            Value::Null => None,
+            Value::Bool(v) => Some(*v),
+            v => throw!(mismatch::<bool>(v)),
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub fn str(&self, col: usize) -> Option<&'a str> {
+        match self.value(col)? {
+            Value::Null => None,
+            Value::String(v) => Some(v.as_str()),
+            v => throw!(mismatch::<&str>(v)),
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub fn datetime(&self, col: usize) -> Option<DateTime<Utc>> {
+        match self.value(col)? {
+            Value::Null => None,
+            Value::DateTime(v) => Some(*v),
+            v => throw!(mismatch::<DateTime<Utc>>(v)),
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub fn point(&self, col: usize) -> Option<Point> {
+        match self.value(col)? {
+            Value::Null => None,
+            Value::Point(v) => Some(*v),
+            v => throw!(mismatch::<Point>(v)),
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub fn circle(&self, col: usize) -> Option<Circle> {
+        match self.value(col)? {
+            Value::Null => None,
+            Value::Circle(v) => Some(*v),
+            v => throw!(mismatch::<Circle>(v)),
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub fn snapshot(&self, col: usize) -> Option<&'a Snapshot> {
+        match self.value(col)? {
+            Value::Null => None,
+            Value::Snapshot(v) => Some(v),
+            v => throw!(mismatch::<Snapshot>(v)),
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub fn string_list(&self, col: usize) -> Option<&'a [Option<String>]> {
+        match self.value(col)? {
+            Value::Null => None,
+            Value::StringList(v) => Some(v.as_slice()),
+            v => throw!(mismatch::<Vec<Option<String>>>(v)),
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    pub fn record(&self, col: usize) -> Option<&'a Record> {
+        match self.value(col)? {
+            Value::Null => None,
+            Value::Record(v) => Some(v),
+            v => throw!(mismatch::<Record>(v)),
+        }
+    }
+}
+
+fn mismatch<T>(v: &Value) -> ConnectorAgentError {
+    ConnectorAgentError::TypeCheckFailed(format!("{:?}", v), type_name::<T>())
+}
+
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+macro_rules! impl_into_value {
+    ($($T:ty => $V:ident),*) => {
+        $(
+            impl IntoValue for $T {
+                fn into_value(self) -> Value {
+                    Value::$V(self)
+                }
+            }
+
+            impl IntoValue for Option<$T> {
+                fn into_value(self) -> Value {
+                    self.map_or(Value::Null, Value::$V)
+                }
+            }
+        )*
+    };
+}
+
+impl_into_value!(
+    f64 => F64,
+    i64 => I64,
+    u64 => U64,
+    bool => Bool,
+    String => String,
+    DateTime<Utc> => DateTime,
+    Point => Point,
+    Circle => Circle,
+    Snapshot => Snapshot,
+    Vec<Option<String>> => StringList,
+    Record => Record
+);
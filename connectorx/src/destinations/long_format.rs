@@ -0,0 +1,315 @@
+use super::callback::IntoValue;
+use super::memory::Value;
+use super::{Consume, Destination, DestinationPartition};
+use crate::data_order::DataOrder;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::errors::{ConnectorAgentError, Result};
+use crate::typesystem::{TypeAssoc, TypeSystem};
+use anyhow::anyhow;
+use arrow::array::{
+    Array, ArrayData, ArrayRef, DictionaryArray, Float64Array, Int64Array, StringArray,
+    TimestampMillisecondArray,
+};
+use arrow::buffer::Buffer;
+use arrow::datatypes::{
+    DataType as ArrowDataType, Field, Int32Type, Schema, TimeUnit, ToByteSlice,
+};
+use arrow::record_batch::RecordBatch;
+use fehler::{throw, throws};
+use std::sync::Arc;
+
+/// The name of the series column of a `LongFormatDestination`.
+pub const SERIES_COLUMN: &str = "series_id";
+/// The name of the value column of a `LongFormatDestination`.
+pub const VALUE_COLUMN: &str = "value";
+
+/// Lands a wide table of metrics in the long format of time-series stores: every row of the
+/// result becomes one row per series column, holding the timestamp, the name of the column
+/// as `series_id` and its value. The series names are a dictionary column, so each row only
+/// stores a key into the list of names. The timestamp column is a `DateTime`, landing as a
+/// UTC millisecond timestamp, or an integer like an epoch, landing as it is. The values are
+/// integers if all series are, and floats otherwise. Null values are kept as rows.
+pub struct LongFormatDestination {
+    timestamp: String,
+    series: Option<Vec<String>>,
+    nrows: usize,
+    schema: Vec<DummyTypeSystem>,
+    timestamp_col: usize,
+    series_cols: Vec<usize>,
+    partitions: Vec<LongRows>,
+}
+
+impl LongFormatDestination {
+    /// Use the column `timestamp` as the time of the rows and every other column as a series.
+    pub fn new(timestamp: &str) -> Self {
+        LongFormatDestination {
+            timestamp: timestamp.to_string(),
+            series: None,
+            nrows: 0,
+            schema: vec![],
+            timestamp_col: 0,
+            series_cols: vec![],
+            partitions: vec![],
+        }
+    }
+
+    /// Only the columns `series` are series, in this order, and the other columns are dropped.
+    pub fn with_series<S: AsRef<str>>(mut self, series: &[S]) -> Self {
+        self.series = Some(series.iter().map(|s| s.as_ref().to_string()).collect());
+        self
+    }
+
+    /// One record batch per partition, with the timestamp, `series_id` and `value` columns.
+    /// `names` are the column names of the result, as given to `allocate`.
+    #[throws(ConnectorAgentError)]
+    pub fn finish<S: AsRef<str>>(self, names: &[S]) -> Vec<RecordBatch> {
+        let timestamp_type = match self.schema.get(self.timestamp_col) {
+            Some(DummyTypeSystem::DateTime(_)) => {
+                ArrowDataType::Timestamp(TimeUnit::Millisecond, Some("UTC".to_string()))
+            }
+            _ => ArrowDataType::Int64,
+        };
+        let floats = self.floats();
+        let series_type = ArrowDataType::Dictionary(
+            Box::new(ArrowDataType::Int32),
+            Box::new(ArrowDataType::Utf8),
+        );
+        let value_type = if floats {
+            ArrowDataType::Float64
+        } else {
+            ArrowDataType::Int64
+        };
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                names[self.timestamp_col].as_ref(),
+                timestamp_type.clone(),
+                true,
+            ),
+            Field::new(SERIES_COLUMN, series_type.clone(), false),
+            Field::new(VALUE_COLUMN, value_type, true),
+        ]));
+        let series_names = StringArray::from(
+            self.series_cols
+                .iter()
+                .map(|&col| names[col].as_ref())
+                .collect::<Vec<_>>(),
+        );
+
+        let mut batches = vec![];
+        for rows in self.partitions {
+            let timestamps: ArrayRef = match &timestamp_type {
+                ArrowDataType::Timestamp(_, tz) => Arc::new(
+                    TimestampMillisecondArray::from_opt_vec(rows.timestamps, tz.clone()),
+                ),
+                _ => Arc::new(Int64Array::from(rows.timestamps)),
+            };
+            let series = ArrayData::builder(series_type.clone())
+                .len(rows.series.len())
+                .add_buffer(Buffer::from(rows.series.to_byte_slice()))
+                .add_child_data(series_names.data())
+                .build();
+            let series: ArrayRef = Arc::new(DictionaryArray::<Int32Type>::from(series));
+            let values: ArrayRef = if floats {
+                Arc::new(Float64Array::from(
+                    rows.values.into_iter().map(as_f64).collect::<Vec<_>>(),
+                ))
+            } else {
+                Arc::new(Int64Array::from(
+                    rows.values.into_iter().map(as_i64).collect::<Vec<_>>(),
+                ))
+            };
+            batches.push(RecordBatch::try_new(
+                schema.clone(),
+                vec![timestamps, series, values],
+            )?);
+        }
+        batches
+    }
+
+    fn floats(&self) -> bool {
+        self.series_cols
+            .iter()
+            .any(|&col| matches!(self.schema[col], DummyTypeSystem::F64(_)))
+    }
+}
+
+impl Destination for LongFormatDestination {
+    const DATA_ORDERS: &'static [DataOrder] = &[DataOrder::RowMajor];
+    type TypeSystem = DummyTypeSystem;
+    type Partition<'a> = LongFormatPartitionDestination<'a>;
+
+    #[throws(ConnectorAgentError)]
+    fn allocate<S: AsRef<str>>(
+        &mut self,
+        nrows: usize,
+        names: &[S],
+        schema: &[DummyTypeSystem],
+        data_order: DataOrder,
+    ) {
+        if !matches!(data_order, DataOrder::RowMajor) {
+            throw!(ConnectorAgentError::UnsupportedDataOrder(data_order))
+        }
+
+        let position = |col: &str| {
+            names
+                .iter()
+                .position(|n| n.as_ref() == col)
+                .ok_or_else(|| anyhow!("column {} is not in the result", col))
+        };
+
+        let timestamp_col = position(&self.timestamp)?;
+        if !matches!(
+            schema[timestamp_col],
+            DummyTypeSystem::DateTime(_) | DummyTypeSystem::I64(_)
+        ) {
+            throw!(anyhow!(
+                "cannot use {}, a {:?} column, as the timestamp",
+                self.timestamp,
+                schema[timestamp_col]
+            ));
+        }
+
+        let series_cols = match &self.series {
+            Some(series) => series
+                .iter()
+                .map(|col| position(col))
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+            None => (0..names.len()).filter(|&i| i != timestamp_col).collect(),
+        };
+        for &col in &series_cols {
+            if !matches!(
+                schema[col],
+                DummyTypeSystem::I64(_) | DummyTypeSystem::F64(_)
+            ) {
+                throw!(anyhow!(
+                    "cannot use {}, a {:?} column, as a series",
+                    names[col].as_ref(),
+                    schema[col]
+                ));
+            }
+        }
+
+        self.nrows = nrows;
+        self.schema = schema.to_vec();
+        self.timestamp_col = timestamp_col;
+        self.series_cols = series_cols;
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn partition(&mut self, counts: &[usize]) -> Vec<Self::Partition<'_>> {
+        assert_eq!(counts.iter().sum::<usize>(), self.nrows);
+        assert_eq!(self.partitions.len(), 0);
+
+        // every row turns into one row per series
+        let nseries = self.series_cols.len();
+        self.partitions = counts
+            .iter()
+            .map(|&nrows| LongRows::with_capacity(nrows * nseries))
+            .collect();
+        let (schema, timestamp_col, series_cols) =
+            (&self.schema, self.timestamp_col, &self.series_cols);
+        self.partitions
+            .iter_mut()
+            .zip(counts)
+            .map(|(rows, &nrows)| LongFormatPartitionDestination {
+                schema,
+                timestamp_col,
+                series_cols,
+                rows,
+                row: Vec::with_capacity(schema.len()),
+                nrows,
+            })
+            .collect()
+    }
+
+    fn schema(&self) -> &[DummyTypeSystem] {
+        self.schema.as_slice()
+    }
+}
+
+pub struct LongFormatPartitionDestination<'a> {
+    schema: &'a [DummyTypeSystem],
+    timestamp_col: usize,
+    series_cols: &'a [usize],
+    rows: &'a mut LongRows,
+    row: Vec<Value>,
+    nrows: usize,
+}
+
+impl<'a> LongFormatPartitionDestination<'a> {
+    fn row_done(&mut self) {
+        let timestamp = match &self.row[self.timestamp_col] {
+            Value::DateTime(t) => Some(t.timestamp_millis()),
+            Value::I64(t) => Some(*t),
+            _ => None,
+        };
+        for (series, &col) in self.series_cols.iter().enumerate() {
+            self.rows.timestamps.push(timestamp);
+            self.rows.series.push(series as i32);
+            self.rows
+                .values
+                .push(std::mem::replace(&mut self.row[col], Value::Null));
+        }
+        self.row.clear();
+    }
+}
+
+impl<'a> DestinationPartition<'a> for LongFormatPartitionDestination<'a> {
+    type TypeSystem = DummyTypeSystem;
+
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    fn ncols(&self) -> usize {
+        self.schema.len()
+    }
+}
+
+impl<'a, T> Consume<T> for LongFormatPartitionDestination<'a>
+where
+    T: TypeAssoc<<Self as DestinationPartition<'a>>::TypeSystem> + IntoValue,
+{
+    fn consume(&mut self, value: T) -> Result<()> {
+        self.schema[self.row.len()].check::<T>()?;
+        self.row.push(value.into_value());
+
+        if self.row.len() == self.schema.len() {
+            self.row_done();
+        }
+        Ok(())
+    }
+}
+
+/// The long rows of a partition, column by column.
+#[derive(Default)]
+struct LongRows {
+    timestamps: Vec<Option<i64>>,
+    series: Vec<i32>,
+    values: Vec<Value>,
+}
+
+impl LongRows {
+    fn with_capacity(nrows: usize) -> Self {
+        LongRows {
+            timestamps: Vec::with_capacity(nrows),
+            series: Vec::with_capacity(nrows),
+            values: Vec::with_capacity(nrows),
+        }
+    }
+}
+
+fn as_f64(value: Value) -> Option<f64> {
+    match value {
+        Value::F64(v) => Some(v),
+        Value::I64(v) => Some(v as f64),
+        _ => None,
+    }
+}
+
+fn as_i64(value: Value) -> Option<i64> {
+    match value {
+        Value::I64(v) => Some(v),
+        _ => None,
+    }
+}
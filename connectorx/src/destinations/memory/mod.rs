@@ -2,7 +2,7 @@ mod any_array;
 
 use super::{Consume, Destination, DestinationPartition};
 use crate::data_order::DataOrder;
-use crate::dummy_typesystem::DummyTypeSystem;
+use crate::dummy_typesystem::{Circle, DummyTypeSystem, Point, Record, Snapshot};
 use crate::errors::{ConnectorAgentError, Result};
 use crate::typesystem::{ParameterizedFunc, ParameterizedOn, Realize, TypeAssoc, TypeSystem};
 use any_array::{AnyArray, AnyArrayViewMut};
@@ -19,6 +19,7 @@ pub struct MemoryDestination {
     schema: Vec<DummyTypeSystem>,
     buffers: Vec<AnyArray<Ix2>>,
     column_buffer_index: Vec<(usize, usize)>,
+    allocated_bytes: usize,
 }
 
 impl Default for MemoryDestination {
@@ -28,6 +29,7 @@ impl Default for MemoryDestination {
             schema: vec![],
             buffers: vec![],
             column_buffer_index: vec![],
+            allocated_bytes: 0,
         }
     }
 }
@@ -73,6 +75,7 @@ impl Destination for MemoryDestination {
             let count = grp.count();
             let buffer = Realize::<FArray2>::realize(dt)?(nrows, count);
             self.buffers.push(buffer);
+            self.allocated_bytes += nrows * count * Realize::<ByteWidth>::realize(dt)?();
         }
 
         let mut per_buffer_counter = HashMap::new();
@@ -119,6 +122,31 @@ impl Destination for MemoryDestination {
     fn schema(&self) -> &[DummyTypeSystem] {
         self.schema.as_slice()
     }
+
+    fn allocated_bytes(&self) -> usize {
+        self.allocated_bytes
+    }
+
+    fn partition_index_type() -> Option<DummyTypeSystem> {
+        Some(DummyTypeSystem::I64(false))
+    }
+}
+
+/// A single cell of a `MemoryDestination`, see `MemoryDestination::row`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    F64(f64),
+    I64(i64),
+    U64(u64),
+    Bool(bool),
+    String(String),
+    DateTime(DateTime<Utc>),
+    Point(Point),
+    Circle(Circle),
+    Snapshot(Snapshot),
+    StringList(Vec<Option<String>>),
+    Record(Record),
 }
 
 impl MemoryDestination {
@@ -143,6 +171,65 @@ impl MemoryDestination {
     pub fn column_buffer_index(&self, col: usize) -> (usize, usize) {
         self.column_buffer_index[col]
     }
+
+    /// Get the cell at (`row`, `col`) as a `T`, whether or not the column is nullable.
+    #[throws(ConnectorAgentError)]
+    pub fn cell<T>(&self, row: usize, col: usize) -> Option<T>
+    where
+        T: 'static + Send + Clone,
+    {
+        let dt = *self
+            .schema
+            .get(col)
+            .ok_or(ConnectorAgentError::OutOfBound)?;
+        if dt.is_nullable() {
+            self.get::<Option<T>>(row, col)?
+        } else {
+            Some(self.get::<T>(row, col)?)
+        }
+    }
+
+    /// Collect the cells of `row` into dynamically typed values.
+    #[throws(ConnectorAgentError)]
+    pub fn row(&self, row: usize) -> Vec<Value> {
+        self.schema
+            .iter()
+            .enumerate()
+            .map(|(col, dt)| {
+                let val = match dt {
+                    DummyTypeSystem::F64(_) => self.cell(row, col)?.map(Value::F64),
+                    DummyTypeSystem::I64(_) => self.cell(row, col)?.map(Value::I64),
+                    DummyTypeSystem::U64(_) => self.cell(row, col)?.map(Value::U64),
+                    DummyTypeSystem::Bool(_) => self.cell(row, col)?.map(Value::Bool),
+                    DummyTypeSystem::String(_) => self.cell(row, col)?.map(Value::String),
+                    DummyTypeSystem::DateTime(_) => self.cell(row, col)?.map(Value::DateTime),
+                    DummyTypeSystem::Point(_) => self.cell(row, col)?.map(Value::Point),
+                    DummyTypeSystem::Circle(_) => self.cell(row, col)?.map(Value::Circle),
+                    DummyTypeSystem::Snapshot(_) => self.cell(row, col)?.map(Value::Snapshot),
+                    DummyTypeSystem::StringList(_) => self.cell(row, col)?.map(Value::StringList),
+                    DummyTypeSystem::Record(_) => self.cell(row, col)?.map(Value::Record),
+                };
+                Ok(val.unwrap_or(Value::Null))
+            })
+            .collect::<Result<Vec<_>>>()?
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn get<T>(&self, row: usize, col: usize) -> T
+    where
+        T: 'static + Send + Clone,
+    {
+        self.column_view::<T>(col)
+            .ok_or_else(|| {
+                ConnectorAgentError::TypeCheckFailed(
+                    format!("{:?}", self.schema[col]),
+                    type_name::<T>(),
+                )
+            })?
+            .get(row)
+            .cloned()
+            .ok_or(ConnectorAgentError::OutOfBound)?
+    }
 }
 /// The `PartitionedDestination` of `MemoryDestination`.
 pub struct MemoryPartitionDestination<'a> {
@@ -186,6 +273,10 @@ impl<'a> DestinationPartition<'a> for MemoryPartitionDestination<'a> {
     fn ncols(&self) -> usize {
         self.schema.len()
     }
+
+    fn write_partition_index(&mut self, index: usize) -> Result<()> {
+        self.write(index as i64)
+    }
 }
 
 impl<'a, T> Consume<T> for MemoryPartitionDestination<'a>
@@ -230,16 +321,40 @@ macro_rules! FArray2Parameterize {
 FArray2Parameterize!(
     i32,
     i64,
+    u64,
     f64,
     String,
     bool,
+    Point,
+    Circle,
+    Snapshot,
+    Vec<Option<String>>,
+    Record,
     Option<i32>,
     Option<i64>,
+    Option<u64>,
     Option<f64>,
     Option<String>,
-    Option<bool>
+    Option<bool>,
+    Option<Point>,
+    Option<Circle>,
+    Option<Snapshot>,
+    Option<Vec<Option<String>>>,
+    Option<Record>
 );
 
+struct ByteWidth;
+
+impl ParameterizedFunc for ByteWidth {
+    type Function = fn() -> usize;
+}
+
+impl<T> ParameterizedOn<T> for ByteWidth {
+    fn parameterize() -> Self::Function {
+        std::mem::size_of::<T>
+    }
+}
+
 fn create_default_array<T>(nrows: usize, ncols: usize) -> AnyArray<Ix2>
 where
     T: Default + Send + 'static,
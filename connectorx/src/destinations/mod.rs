@@ -1,9 +1,17 @@
+pub mod aggregate;
 pub mod arrow;
+pub mod callback;
+pub mod long_format;
 pub mod memory;
+pub mod tee;
 
 use crate::data_order::DataOrder;
 use crate::errors::Result;
+use crate::pseudonym::Pseudonymizer;
 use crate::typesystem::{TypeAssoc, TypeSystem};
+use ::arrow::datatypes::Schema;
+use ::arrow::record_batch::RecordBatch;
+use anyhow::anyhow;
 
 /// A `Destination` is associated with a `TypeSystem` and a `PartitionDestination`.
 /// `PartitionDestination` allows multiple threads write data into the buffer owned by `Destination`.
@@ -27,6 +35,24 @@ pub trait Destination: Sized {
     fn partition(&mut self, counts: &[usize]) -> Result<Vec<Self::Partition<'_>>>;
     /// Return the schema of the destination.
     fn schema(&self) -> &[Self::TypeSystem];
+
+    /// Bytes of the buffers `allocate` set up, without the values they point to on the heap.
+    /// 0 for a destination that only grows its buffers while it is written.
+    fn allocated_bytes(&self) -> usize {
+        0
+    }
+
+    /// The type of a column holding the index of the partition each row comes from, see
+    /// `Dispatcher::with_partition_column`. None if the destination cannot hold one.
+    fn partition_index_type() -> Option<Self::TypeSystem> {
+        None
+    }
+
+    /// Replace the values of the column `col` by their pseudonyms, as strings, see
+    /// `Dispatcher::with_pseudonymize`. Called after `allocate`.
+    fn pseudonymize(&mut self, _col: usize, _pseudonymizer: Pseudonymizer) -> Result<()> {
+        Err(anyhow!("this destination cannot pseudonymize a column").into())
+    }
 }
 
 /// `PartitionDestination` writes values to its own region. `PartitionDestination` is parameterized
@@ -55,6 +81,21 @@ pub trait DestinationPartition<'a>: Send {
     fn finalize(&mut self) -> Result<()> {
         Ok(())
     }
+
+    /// Whether `push_batch` can take record batches of `schema` as they are.
+    fn accepts_batches(&self, _schema: &Schema) -> bool {
+        false
+    }
+
+    /// Write a whole record batch of rows. Only called if `accepts_batches` the batch's schema.
+    fn push_batch(&mut self, _batch: RecordBatch) -> Result<()> {
+        Err(anyhow!("this destination does not take record batches").into())
+    }
+
+    /// Write `index` to a column of the type `Destination::partition_index_type`.
+    fn write_partition_index(&mut self, _index: usize) -> Result<()> {
+        Err(anyhow!("this destination cannot hold a partition index").into())
+    }
 }
 
 /// A type implemented `Consume<T>` means that it can consume a value `T` by adding it to it's own buffer.
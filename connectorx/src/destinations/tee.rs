@@ -0,0 +1,168 @@
+use super::{Consume, Destination, DestinationPartition};
+use crate::data_order::DataOrder;
+use crate::errors::Result;
+use crate::pseudonym::Pseudonymizer;
+use crate::typesystem::TypeSystem;
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use itertools::Itertools;
+
+/// Writes every value to two destinations of the same type system at once, e.g. to keep the
+/// rows in memory and hand them to a callback without reading the source twice. It takes
+/// the data orders both destinations support, in the order of the first one.
+pub struct TeeDestination<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> TeeDestination<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        TeeDestination { first, second }
+    }
+
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+
+    /// The two destinations, e.g. to call their own `finish`.
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+const fn supports(orders: &[DataOrder], order: DataOrder) -> bool {
+    let mut i = 0;
+    while i < orders.len() {
+        if orders[i] as u8 == order as u8 {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+// The data orders in both `first` and `second`, in the order of `first`.
+const fn common_orders(first: &[DataOrder], second: &[DataOrder]) -> &'static [DataOrder] {
+    use DataOrder::*;
+    let row = supports(first, RowMajor) && supports(second, RowMajor);
+    let col = supports(first, ColumnMajor) && supports(second, ColumnMajor);
+    match (row, col) {
+        (true, true) if first[0] as u8 == ColumnMajor as u8 => &[ColumnMajor, RowMajor],
+        (true, true) => &[RowMajor, ColumnMajor],
+        (true, false) => &[RowMajor],
+        (false, true) => &[ColumnMajor],
+        (false, false) => &[],
+    }
+}
+
+impl<TS, A, B> Destination for TeeDestination<A, B>
+where
+    TS: TypeSystem + PartialEq,
+    A: Destination<TypeSystem = TS>,
+    B: Destination<TypeSystem = TS>,
+{
+    const DATA_ORDERS: &'static [DataOrder] = common_orders(A::DATA_ORDERS, B::DATA_ORDERS);
+    type TypeSystem = TS;
+    type Partition<'a> = TeePartition<A::Partition<'a>, B::Partition<'a>>;
+
+    fn allocate<S: AsRef<str>>(
+        &mut self,
+        nrows: usize,
+        names: &[S],
+        schema: &[TS],
+        data_order: DataOrder,
+    ) -> Result<()> {
+        self.first.allocate(nrows, names, schema, data_order)?;
+        self.second.allocate(nrows, names, schema, data_order)
+    }
+
+    fn partition(&mut self, counts: &[usize]) -> Result<Vec<Self::Partition<'_>>> {
+        let firsts = self.first.partition(counts)?;
+        let seconds = self.second.partition(counts)?;
+        Ok(firsts
+            .into_iter()
+            .zip_eq(seconds)
+            .map(|(first, second)| TeePartition { first, second })
+            .collect())
+    }
+
+    fn schema(&self) -> &[TS] {
+        self.first.schema()
+    }
+
+    fn allocated_bytes(&self) -> usize {
+        self.first.allocated_bytes() + self.second.allocated_bytes()
+    }
+
+    /// Only if both destinations hold the partition index as the same type.
+    fn partition_index_type() -> Option<TS> {
+        match (A::partition_index_type(), B::partition_index_type()) {
+            (Some(first), Some(second)) if first == second => Some(first),
+            _ => None,
+        }
+    }
+
+    fn pseudonymize(&mut self, col: usize, pseudonymizer: Pseudonymizer) -> Result<()> {
+        self.first.pseudonymize(col, pseudonymizer.clone())?;
+        self.second.pseudonymize(col, pseudonymizer)
+    }
+}
+
+pub struct TeePartition<PA, PB> {
+    first: PA,
+    second: PB,
+}
+
+impl<'a, TS, PA, PB> DestinationPartition<'a> for TeePartition<PA, PB>
+where
+    TS: TypeSystem,
+    PA: DestinationPartition<'a, TypeSystem = TS>,
+    PB: DestinationPartition<'a, TypeSystem = TS>,
+{
+    type TypeSystem = TS;
+
+    fn nrows(&self) -> usize {
+        self.first.nrows()
+    }
+
+    fn ncols(&self) -> usize {
+        self.first.ncols()
+    }
+
+    fn finalize(&mut self) -> Result<()> {
+        self.first.finalize()?;
+        self.second.finalize()
+    }
+
+    fn accepts_batches(&self, schema: &Schema) -> bool {
+        self.first.accepts_batches(schema) && self.second.accepts_batches(schema)
+    }
+
+    /// The columns of a record batch are reference counted, so both get the batch without
+    /// copying it.
+    fn push_batch(&mut self, batch: RecordBatch) -> Result<()> {
+        self.first.push_batch(batch.clone())?;
+        self.second.push_batch(batch)
+    }
+
+    fn write_partition_index(&mut self, index: usize) -> Result<()> {
+        self.first.write_partition_index(index)?;
+        self.second.write_partition_index(index)
+    }
+}
+
+impl<T, PA, PB> Consume<T> for TeePartition<PA, PB>
+where
+    T: Clone,
+    PA: Consume<T>,
+    PB: Consume<T>,
+{
+    fn consume(&mut self, value: T) -> Result<()> {
+        self.first.consume(value.clone())?;
+        self.second.consume(value)
+    }
+}
@@ -1,14 +1,27 @@
 use crate::{
     data_order::{coordinate, DataOrder},
-    destinations::{Destination, DestinationPartition},
-    errors::Result,
+    destinations::{
+        arrow::ArrowDestination,
+        memory::{MemoryDestination, Value},
+        Destination, DestinationPartition,
+    },
+    dummy_typesystem::DummyTypeSystem,
+    errors::{ConnectorAgentError, Result},
+    metrics::{MemoryEstimate, PartitionStats, RunMetrics},
+    name_case::{normalize_names, NameCase},
+    pseudonym::Pseudonymizer,
+    read_budget::{ReadBudget, ReadSlot},
     sources::{Source, SourcePartition},
     typesystem::{Transport, TypeSystem},
 };
+use anyhow::anyhow;
+use arrow::record_batch::RecordBatch;
 use itertools::Itertools;
-use log::debug;
+use log::{debug, warn};
 use rayon::prelude::*;
 use std::marker::PhantomData;
+use std::ops::Range;
+use std::time::{Duration, Instant};
 
 /// A dispatcher owns a `SourceBuilder` `SB` and a vector of `queries`
 /// `schema` is a temporary input before we implement infer schema or get schema from DB.
@@ -16,6 +29,17 @@ pub struct Dispatcher<'a, S, W, TP> {
     src: S,
     dst: &'a mut W,
     queries: Vec<String>,
+    name_case: Option<NameCase>,
+    sequential: bool,
+    skew_threshold: Option<f64>,
+    required_columns: Vec<String>,
+    numeric_coercion: bool,
+    cell_by_cell: bool,
+    partition_column: Option<String>,
+    partition_subset: Option<Range<usize>>,
+    memory_accounting: bool,
+    read_budget: Option<(usize, usize)>,
+    pseudonyms: Vec<(String, Pseudonymizer)>,
     _phantom: PhantomData<TP>,
 }
 
@@ -37,42 +61,258 @@ where
             src,
             dst,
             queries: queries.iter().map(ToString::to_string).collect(),
+            name_case: None,
+            sequential: false,
+            skew_threshold: None,
+            required_columns: vec![],
+            numeric_coercion: false,
+            cell_by_cell: false,
+            partition_column: None,
+            partition_subset: None,
+            memory_accounting: false,
+            read_budget: None,
+            pseudonyms: vec![],
             _phantom: PhantomData,
         }
     }
 
+    /// Convert all the column names to `case` before handing them to the destination.
+    pub fn with_name_case(mut self, case: NameCase) -> Self {
+        self.name_case = Some(case);
+        self
+    }
+
+    /// Process the partitions one after another on the calling thread instead of in parallel.
+    /// Slower, but only one partition is being read and written at a time, which bounds the
+    /// peak memory when a partition holds a lot of data in flight. The destination is sized
+    /// from the row counts of all the partitions before any of them is written, so the
+    /// partitions whose `prepare` loads their data, like those of CSV and Arrow files or of
+    /// Google Sheets, are released after counting (see `SourcePartition::release`) and load
+    /// it again when their turn comes, reading it twice. What a source holds from
+    /// `fetch_metadata` on, like the pages of `TrinoSource` and the `HttpJsonSource` pages
+    /// found by following links, stays in memory for the whole run all the same.
+    pub fn sequential(mut self) -> Self {
+        self.sequential = true;
+        self
+    }
+
+    /// Log a warning, and record it in the `RunMetrics`, when the largest partition has more
+    /// than `threshold` times the mean number of rows.
+    pub fn skew_warning(mut self, threshold: f64) -> Self {
+        self.skew_threshold = Some(threshold);
+        self
+    }
+
+    /// Fail the run with `ConnectorAgentError::UnexpectedNull` as soon as a null is written to
+    /// one of `columns`. The columns are named as they are handed to the destination, that is
+    /// after `with_name_case`.
+    pub fn with_required_columns<C>(mut self, columns: &[C]) -> Self
+    where
+        C: ToString,
+    {
+        self.required_columns = columns.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// When the queries disagree on the type of a numeric column, read the whole column as the
+    /// widest of their types, converting integers to float, instead of the type the first
+    /// query has. Only sources with dynamically typed columns, like SQLite, can disagree.
+    pub fn with_numeric_coercion(mut self, coerce: bool) -> Self {
+        self.numeric_coercion = coerce;
+        self
+    }
+
+    /// Write every value on its own even where whole record batches could be moved from the
+    /// source to the destination, see `SourcePartition::batch_schema`.
+    pub fn cell_by_cell(mut self) -> Self {
+        self.cell_by_cell = true;
+        self
+    }
+
+    /// Add a column `name` after the others holding the index of the partition, that is of the
+    /// query, each row comes from. Its type is `Destination::partition_index_type`.
+    pub fn with_partition_column(mut self, name: &str) -> Self {
+        self.partition_column = Some(name.to_string());
+        self
+    }
+
+    /// Only prepare and read the partitions, that is the queries, in `subset`, e.g. to resume
+    /// a load that stopped after some of them. The destination is sized for the subset only,
+    /// and the metadata comes from its queries. A partition column still holds the index of
+    /// each partition among all the queries.
+    pub fn with_partition_subset(mut self, subset: Range<usize>) -> Self {
+        self.partition_subset = Some(subset);
+        self
+    }
+
+    /// Estimate the peak memory of the allocate and the write phases into
+    /// `RunMetrics::memory`, from the buffers the destination allocates and the read buffers
+    /// of the partitions. When running in parallel all the partitions are taken to be read at
+    /// once, when `sequential` only the one with the largest read buffer.
+    pub fn with_memory_accounting(mut self) -> Self {
+        self.memory_accounting = true;
+        self
+    }
+
+    /// Let at most `concurrency` of the partitions read at the same time, each taking its
+    /// turn for `batch_rows` rows (or a record batch) before queueing up for the next, so that
+    /// a few large partitions do not keep the small ones waiting for the connections, see
+    /// `ReadBudget`. The queries run under the same budget. The partitions are still run by
+    /// `par_iter` as without the budget, and every one keeps its thread and its open result
+    /// while it waits for its turn, so `concurrency` is also bounded by the size of the rayon
+    /// pool. Has no effect when `sequential`. A zero `concurrency` or `batch_rows` fails the
+    /// run.
+    pub fn with_read_budget(mut self, concurrency: usize, batch_rows: usize) -> Self {
+        self.read_budget = Some((concurrency, batch_rows));
+        self
+    }
+
+    /// Replace the values of the column `col` by their pseudonyms under `key`, see
+    /// `Pseudonymizer`, e.g. for a join key that must not leave the database as it is.
+    /// Tables read with the same key still join on the pseudonyms. Only destinations that
+    /// implement `Destination::pseudonymize` support it.
+    pub fn with_pseudonymize(mut self, col: &str, key: &[u8]) -> Self {
+        self.pseudonyms
+            .push((col.to_string(), Pseudonymizer::new(key)));
+        self
+    }
+
     /// Run the dispatcher by specifying the src, the dispatcher will fetch, parse the data,
     /// and write the data to dst.
-    pub fn run(mut self) -> Result<()> {
+    pub fn run(self) -> Result<()> {
+        self.dispatch()?;
+        Ok(())
+    }
+
+    /// Same as `run`, but also returns the row counts and timings of the partitions.
+    pub fn run_with_metrics(self) -> Result<RunMetrics> {
+        Ok(self.dispatch()?.1)
+    }
+
+    /// Same as `run`, but returns the column names handed to the destination along with
+    /// the metrics.
+    fn dispatch(mut self) -> Result<(Vec<String>, RunMetrics)> {
         let dorder = coordinate(S::DATA_ORDERS, W::DATA_ORDERS)?;
+        let read_budget = match self.read_budget {
+            Some((concurrency, batch_rows)) => Some(ReadBudget::new(concurrency, batch_rows)?),
+            None => None,
+        };
         self.src.set_data_order(dorder)?;
-        self.src.set_queries(self.queries.as_slice());
+        // the partitions are created from the queries, so leave the others out from the start
+        let nqueries = self.queries.len();
+        let subset = match self.partition_subset.clone() {
+            Some(subset) if subset.start >= subset.end || subset.end > nqueries => {
+                return Err(anyhow!(
+                    "the partition subset {:?} is not within the {} partitions",
+                    subset,
+                    nqueries
+                )
+                .into());
+            }
+            Some(subset) => subset,
+            None => 0..nqueries,
+        };
+        self.src.set_queries(&self.queries[subset.clone()]);
+        self.src.set_query_offset(subset.start);
+        self.src.set_numeric_coercion(self.numeric_coercion);
         debug!("Fetching metadata");
         self.src.fetch_metadata()?;
+        let notices = self.src.notice_log();
         let src_schema = self.src.schema();
         let dst_schema = src_schema
             .iter()
             .map(|&s| TP::convert_typesystem(s))
             .collect::<Result<Vec<_>>>()?;
-        let names = self.src.names();
+        let mut names = match self.name_case {
+            Some(case) => normalize_names(&self.src.names(), case)?,
+            None => self.src.names(),
+        };
+        let mut dst_columns = dst_schema.clone();
+        if let Some(name) = &self.partition_column {
+            if names.contains(name) {
+                return Err(
+                    anyhow!("the partition column {} is already in the result", name).into(),
+                );
+            }
+            let ty = W::partition_index_type()
+                .ok_or_else(|| anyhow!("the destination cannot hold a partition column"))?;
+            names.push(name.clone());
+            dst_columns.push(ty);
+        }
+        let mut required = vec![false; names.len()];
+        for col in &self.required_columns {
+            match names.iter().position(|n| n == col) {
+                Some(i) => required[i] = true,
+                None => return Err(ConnectorAgentError::RequiredColumnNotFound(col.clone())),
+            }
+        }
 
         // generate partitions
         let mut src_partitions: Vec<S::Partition> = self.src.partition()?;
         debug!("Prepare partitions");
+        let budget = if self.sequential {
+            None
+        } else {
+            read_budget.as_ref()
+        };
         // run queries
-        src_partitions
-            .par_iter_mut()
-            .try_for_each(|partition| -> Result<()> { partition.prepare() })?;
+        let prepare = |partition: &mut S::Partition| -> Result<Duration> {
+            let start = Instant::now();
+            let _slot = budget.map(ReadBudget::acquire);
+            partition.prepare()?;
+            Ok(start.elapsed())
+        };
+        let mut released = vec![false; src_partitions.len()];
+        let prepare_times: Vec<Duration> = if self.sequential {
+            src_partitions
+                .iter_mut()
+                .zip(released.iter_mut())
+                .map(|(partition, released)| {
+                    let elapsed = prepare(partition)?;
+                    // keep only the counts until the partition is written
+                    *released = partition.release();
+                    Ok(elapsed)
+                })
+                .collect::<Result<_>>()?
+        } else {
+            src_partitions
+                .par_iter_mut()
+                .map(prepare)
+                .collect::<Result<_>>()?
+        };
 
         // allocate memory and create one partition for each source
         let num_rows: Vec<usize> = src_partitions
             .iter()
             .map(|partition| partition.nrows())
             .collect();
+        // the row of the whole result each partition starts at
+        let offsets: Vec<usize> = num_rows
+            .iter()
+            .scan(0, |offset, &n| {
+                *offset += n;
+                Some(*offset - n)
+            })
+            .collect();
+
+        let read_buffers = src_partitions.iter().map(|p| p.read_buffer_bytes());
+        let read_buffer_bytes = if self.sequential {
+            read_buffers.max().unwrap_or(0)
+        } else {
+            read_buffers.sum()
+        };
 
         debug!("Allocate destination memory");
         self.dst
-            .allocate(num_rows.iter().sum(), &names, &dst_schema, dorder)?;
+            .allocate(num_rows.iter().sum(), &names, &dst_columns, dorder)?;
+        for (col, pseudonymizer) in &self.pseudonyms {
+            let i = names
+                .iter()
+                .position(|n| n == col)
+                .ok_or_else(|| anyhow!("cannot pseudonymize {}, it is not in the result", col))?;
+            self.dst.pseudonymize(i, pseudonymizer.clone())?;
+        }
+        let allocated_bytes = self.dst.allocated_bytes();
 
         debug!("Create destination partition");
         let dst_partitions = self.dst.partition(&num_rows)?;
@@ -92,12 +332,38 @@ where
             .collect();
 
         debug!("Start writing");
+        let cell_by_cell = self.cell_by_cell;
+        let partition_column = self.partition_column.is_some();
+        let first_partition = subset.start;
         // parse and write
-        dst_partitions
-            .into_par_iter()
-            .zip_eq(src_partitions)
-            .enumerate()
-            .try_for_each(|(i, (mut src, mut dst))| -> Result<()> {
+        let run_partition =
+            |(i, (mut src, mut dst)): (usize, (W::Partition<'_>, S::Partition))| -> Result<Duration> {
+                let start = Instant::now();
+                if released[i] {
+                    dst.prepare()?;
+                    if dst.nrows() != num_rows[i] {
+                        return Err(anyhow!(
+                            "partition {} has {} rows, {} when it was counted",
+                            i,
+                            dst.nrows(),
+                            num_rows[i]
+                        )
+                        .into());
+                    }
+                }
+                let batch_schema = if cell_by_cell || partition_column {
+                    None
+                } else {
+                    dst.batch_schema()
+                };
+                if matches!(batch_schema, Some(schema) if src.accepts_batches(&schema)) {
+                    debug!("Moving partition {} by record batches", i);
+                    move_batches(&mut dst, &mut src, budget, &required, &names, offsets[i])?;
+                    src.finalize()?;
+                    debug!("Partition {} finished", i);
+                    return Ok(start.elapsed());
+                }
+
                 #[cfg(feature = "fptr")]
                 let f: Vec<_> = src_schema
                     .iter()
@@ -106,12 +372,25 @@ where
                     .collect::<Result<Vec<_>>>()?;
 
                 let mut parser = dst.parser()?;
+                let mut slot = None;
 
                 match dorder {
                     DataOrder::RowMajor => {
-                        for _ in 0..src.nrows() {
+                        for row in 0..src.nrows() {
+                            next_turn(budget, &mut slot, row);
                             #[allow(clippy::needless_range_loop)]
-                            for col in 0..src.ncols() {
+                            for col in 0..src_schema.len() {
+                                if required[col] {
+                                    write_required::<TP>(
+                                        (src_schema[col], dst_schema[col]),
+                                        &mut parser,
+                                        &mut src,
+                                        &names[col],
+                                        offsets[i] + row,
+                                    )?;
+                                    continue;
+                                }
+
                                 #[cfg(feature = "fptr")]
                                 f[col](&mut parser, &mut src)?;
 
@@ -121,13 +400,28 @@ where
                                     TP::process(s1, s2, &mut parser, &mut src)?;
                                 }
                             }
+                            if partition_column {
+                                src.write_partition_index(first_partition + i)?;
+                            }
                         }
                     }
                     DataOrder::ColumnMajor =>
                     {
                         #[allow(clippy::needless_range_loop)]
-                        for col in 0..src.ncols() {
-                            for _ in 0..src.nrows() {
+                        for col in 0..src_schema.len() {
+                            for row in 0..src.nrows() {
+                                next_turn(budget, &mut slot, row);
+                                if required[col] {
+                                    write_required::<TP>(
+                                        (src_schema[col], dst_schema[col]),
+                                        &mut parser,
+                                        &mut src,
+                                        &names[col],
+                                        offsets[i] + row,
+                                    )?;
+                                    continue;
+                                }
+
                                 #[cfg(feature = "fptr")]
                                 f[col](&mut parser, &mut src)?;
                                 #[cfg(feature = "branch")]
@@ -137,17 +431,337 @@ where
                                 }
                             }
                         }
+                        if partition_column {
+                            for _ in 0..src.nrows() {
+                                src.write_partition_index(first_partition + i)?;
+                            }
+                        }
                     }
                 }
 
+                drop(slot);
                 debug!("Finalize partition {}", i);
                 src.finalize()?;
                 debug!("Partition {} finished", i);
-                Ok(())
-            })?;
+                Ok(start.elapsed())
+            };
+
+        let write_times: Vec<Duration> = if self.sequential {
+            dst_partitions
+                .into_iter()
+                .zip_eq(src_partitions)
+                .enumerate()
+                .map(|(i, partitions)| {
+                    // the sources count the rows of their partition
+                    run_partition((i, partitions)).map_err(|e| e.at_row_offset(offsets[i]))
+                })
+                .collect::<Result<_>>()?
+        } else {
+            dst_partitions
+                .into_par_iter()
+                .zip_eq(src_partitions)
+                .enumerate()
+                .map(|(i, partitions)| {
+                    // the sources count the rows of their partition
+                    run_partition((i, partitions)).map_err(|e| e.at_row_offset(offsets[i]))
+                })
+                .collect::<Result<_>>()?
+        };
 
         debug!("Writing finished");
 
-        Ok(())
+        let mut metrics = RunMetrics::new(
+            num_rows
+                .iter()
+                .zip_eq(prepare_times.iter().zip_eq(&write_times))
+                .map(|(&rows, (&prepare, &write))| PartitionStats {
+                    rows,
+                    elapsed: prepare + write,
+                })
+                .collect(),
+        );
+        if self.memory_accounting {
+            metrics.memory = Some(MemoryEstimate {
+                allocate: allocated_bytes,
+                write: allocated_bytes + read_buffer_bytes,
+            });
+        }
+        if let Some(notices) = notices {
+            metrics.notices = notices.take();
+        }
+        if let Some(threshold) = self.skew_threshold {
+            metrics.skew_warning = metrics.check_skew(threshold);
+            if let Some(msg) = &metrics.skew_warning {
+                warn!("{}", msg);
+            }
+        }
+
+        Ok((names, metrics))
+    }
+}
+
+/// Take the next turn of the read budget at the start of each of its batches of rows.
+fn next_turn<'a>(budget: Option<&'a ReadBudget>, slot: &mut Option<ReadSlot<'a>>, row: usize) {
+    if let Some(budget) = budget {
+        let into_batch = row % budget.batch_rows();
+        if into_batch == 0 {
+            // give the turn back before queueing up for the next one
+            *slot = None;
+            *slot = Some(budget.acquire());
+        }
+    }
+}
+
+/// Move a partition over batch by batch, checking the required columns on the way. With a
+/// read budget every batch is read in a turn of its own.
+fn move_batches<'d, SP, DP>(
+    src: &mut SP,
+    dst: &mut DP,
+    budget: Option<&ReadBudget>,
+    required: &[bool],
+    names: &[String],
+    offset: usize,
+) -> Result<()>
+where
+    SP: SourcePartition,
+    DP: DestinationPartition<'d>,
+{
+    let mut row = offset;
+    loop {
+        let slot = budget.map(ReadBudget::acquire);
+        let batch = match src.next_batch()? {
+            Some(batch) => batch,
+            None => break,
+        };
+        drop(slot);
+        for (col, column) in batch.columns().iter().enumerate() {
+            if required[col] && column.null_count() > 0 {
+                let null = (0..column.len()).find(|&r| column.is_null(r)).unwrap_or(0);
+                return Err(ConnectorAgentError::UnexpectedNull {
+                    col: names[col].clone(),
+                    row: row + null,
+                });
+            }
+        }
+        row += batch.num_rows();
+        dst.push_batch(batch)?;
+    }
+    Ok(())
+}
+
+/// Write one value of a required column, failing if it is null.
+fn write_required<'s, 'd, 'r, TP>(
+    (ts1, ts2): (TP::TSS, TP::TSD),
+    parser: &'r mut <<TP::S as Source>::Partition as SourcePartition>::Parser<'s>,
+    dst: &'r mut <TP::D as Destination>::Partition<'d>,
+    col: &str,
+    row: usize,
+) -> Result<()>
+where
+    TP: Transport,
+{
+    if TP::process_check_null(ts1, ts2, parser, dst)? {
+        return Err(ConnectorAgentError::UnexpectedNull {
+            col: col.to_string(),
+            row,
+        });
+    }
+    Ok(())
+}
+
+impl<'w, S, TSS, TP> Dispatcher<'w, S, ArrowDestination, TP>
+where
+    TSS: TypeSystem,
+    S: Source<TypeSystem = TSS>,
+    TP: Transport<TSS = TSS, TSD = DummyTypeSystem, S = S, D = ArrowDestination>,
+{
+    /// Run the queries into a fresh `ArrowDestination` and return its record batches.
+    /// Use `to_arrow` instead to set any options.
+    pub fn run_to_arrow<Q>(src: S, queries: &[Q]) -> Result<Vec<RecordBatch>>
+    where
+        Q: ToString,
+    {
+        Self::to_arrow(src, queries).run()
+    }
+
+    /// Start building a run into a fresh `ArrowDestination`.
+    pub fn to_arrow<Q>(src: S, queries: &[Q]) -> ArrowRun<S, TP>
+    where
+        Q: ToString,
+    {
+        ArrowRun {
+            src,
+            queries: queries.iter().map(ToString::to_string).collect(),
+            batch_size: None,
+            keep_source_batches: false,
+            name_case: None,
+            sequential: false,
+            pseudonyms: vec![],
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// What `Dispatcher::read_one_row` and `Dispatcher::read_scalar` do when the query returns
+/// more than they asked for.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ExtraResults {
+    /// Fail with `ConnectorAgentError::TooManyRows` or `ConnectorAgentError::TooManyColumns`.
+    Error,
+    /// Keep the first row (and, for a scalar, the first column).
+    TakeFirst,
+}
+
+impl<'w, S, TSS, TP> Dispatcher<'w, S, MemoryDestination, TP>
+where
+    TSS: TypeSystem,
+    S: Source<TypeSystem = TSS>,
+    TP: Transport<TSS = TSS, TSD = DummyTypeSystem, S = S, D = MemoryDestination>,
+{
+    /// Run `query` as a single partition and return its first row, or None if it has no rows.
+    /// Only that row is parsed and stored. A SQL query is limited to two rows, so that too
+    /// many rows fail without reading them all.
+    pub fn read_one_row(src: S, query: &str, extra: ExtraResults) -> Result<Option<Vec<Value>>> {
+        match Self::read_first_row(src, query, extra, false)? {
+            Some(dst) => Ok(Some(dst.row(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Run `query` as a single partition and return the value in its first row and column.
+    /// None if the query has no rows or the value is null. A query without columns fails with
+    /// `ConnectorAgentError::NoColumns`.
+    pub fn read_scalar<T>(src: S, query: &str, extra: ExtraResults) -> Result<Option<T>>
+    where
+        T: 'static + Send + Clone,
+    {
+        match Self::read_first_row(src, query, extra, true)? {
+            Some(dst) => dst.cell(0, 0),
+            None => Ok(None),
+        }
+    }
+
+    fn read_first_row(
+        mut src: S,
+        query: &str,
+        extra: ExtraResults,
+        scalar: bool,
+    ) -> Result<Option<MemoryDestination>> {
+        let dorder = coordinate(S::DATA_ORDERS, MemoryDestination::DATA_ORDERS)?;
+        src.set_data_order(dorder)?;
+        // a second row is all it takes to tell there is more than one
+        src.set_queries(&[src.limit_query(query, 2)?]);
+        src.fetch_metadata()?;
+        let src_schema = src.schema();
+        let dst_schema = src_schema
+            .iter()
+            .map(|&s| TP::convert_typesystem(s))
+            .collect::<Result<Vec<_>>>()?;
+        let ncols = dst_schema.len();
+        if scalar && ncols == 0 {
+            return Err(ConnectorAgentError::NoColumns);
+        }
+        if scalar && ncols > 1 && extra == ExtraResults::Error {
+            return Err(ConnectorAgentError::TooManyColumns(ncols));
+        }
+        let names = src.names();
+
+        let mut partition = src
+            .partition()?
+            .pop()
+            .ok_or(ConnectorAgentError::OutOfBound)?;
+        partition.prepare()?;
+        let nrows = partition.nrows();
+        if nrows == 0 {
+            return Ok(None);
+        }
+        if nrows > 1 && extra == ExtraResults::Error {
+            return Err(ConnectorAgentError::TooManyRows(nrows));
+        }
+
+        let mut dst = MemoryDestination::new();
+        dst.allocate(1, &names, &dst_schema, dorder)?;
+        {
+            let mut dst_partition = dst
+                .partition(&[1])?
+                .pop()
+                .ok_or(ConnectorAgentError::OutOfBound)?;
+            let mut parser = partition.parser()?;
+            // stop after the first row, the rest is never parsed
+            for (&s1, &s2) in src_schema.iter().zip_eq(&dst_schema) {
+                TP::process(s1, s2, &mut parser, &mut dst_partition)?;
+            }
+            dst_partition.finalize()?;
+        }
+        Ok(Some(dst))
+    }
+}
+
+/// Options for a dispatch that sets up its own `ArrowDestination`, see `Dispatcher::to_arrow`.
+/// Each query is one partition, as with `Dispatcher::new`.
+pub struct ArrowRun<S, TP> {
+    src: S,
+    queries: Vec<String>,
+    batch_size: Option<usize>,
+    keep_source_batches: bool,
+    name_case: Option<NameCase>,
+    sequential: bool,
+    pseudonyms: Vec<(String, Pseudonymizer)>,
+    _phantom: PhantomData<TP>,
+}
+
+impl<S, TSS, TP> ArrowRun<S, TP>
+where
+    TSS: TypeSystem,
+    S: Source<TypeSystem = TSS>,
+    TP: Transport<TSS = TSS, TSD = DummyTypeSystem, S = S, D = ArrowDestination>,
+{
+    /// See `ArrowDestination::batch_size`. A zero batch size fails the run.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = Some(batch_size);
+        self
+    }
+
+    /// See `ArrowDestination::keep_source_batches`.
+    pub fn keep_source_batches(mut self) -> Self {
+        self.keep_source_batches = true;
+        self
+    }
+
+    /// See `Dispatcher::with_name_case`.
+    pub fn with_name_case(mut self, case: NameCase) -> Self {
+        self.name_case = Some(case);
+        self
+    }
+
+    /// See `Dispatcher::sequential`.
+    pub fn sequential(mut self) -> Self {
+        self.sequential = true;
+        self
+    }
+
+    /// See `Dispatcher::with_pseudonymize`.
+    pub fn with_pseudonymize(mut self, col: &str, key: &[u8]) -> Self {
+        self.pseudonyms
+            .push((col.to_string(), Pseudonymizer::new(key)));
+        self
+    }
+
+    pub fn run(self) -> Result<Vec<RecordBatch>> {
+        let mut dst = ArrowDestination::new();
+        if let Some(batch_size) = self.batch_size {
+            dst.batch_size(batch_size)?;
+        }
+        if self.keep_source_batches {
+            dst.keep_source_batches();
+        }
+
+        let mut dispatcher = Dispatcher::<_, _, TP>::new(self.src, &mut dst, &self.queries);
+        dispatcher.name_case = self.name_case;
+        dispatcher.sequential = self.sequential;
+        dispatcher.pseudonyms = self.pseudonyms;
+        let (names, _) = dispatcher.dispatch()?;
+
+        dst.finish(names)
     }
 }
@@ -7,6 +7,73 @@
 //
 
 use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+/// A 2D point, e.g. a Postgres `point`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A circle, e.g. a Postgres `circle`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Circle {
+    pub center: Point,
+    pub radius: f64,
+}
+
+/// A Postgres `pg_snapshot`. Transactions before `xmin` are done and the ones from `xmax` on
+/// are not, in between the ones listed in `xip` were still in progress.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    pub xmin: u64,
+    pub xmax: u64,
+    pub xip: Vec<u64>,
+}
+
+/// The type of a field of a `Record`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordType {
+    I64,
+    F64,
+    Bool,
+    String,
+    Record(Vec<(String, RecordType)>),
+}
+
+/// The names and types of the fields of a `Record`, shared by all the records of a column.
+pub type RecordFields = Arc<[(String, RecordType)]>;
+
+/// A field value of a `Record`, a nested record being the values of its own fields.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordValue {
+    Null,
+    I64(i64),
+    F64(f64),
+    Bool(bool),
+    String(String),
+    Record(Vec<RecordValue>),
+}
+
+/// A composite value, e.g. a row of a Postgres composite type. It carries the names and
+/// types of its fields along with their values, so that the fields of a null nested record
+/// are known as well.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub fields: RecordFields,
+    pub values: Vec<RecordValue>,
+}
+
+impl Default for Record {
+    fn default() -> Self {
+        Record {
+            fields: Vec::new().into(),
+            values: vec![],
+        }
+    }
+}
+
 /// This is a dummy type system used in this library.
 /// For all the sources, their output values must be one of the types defined by DummyTypeSystem.
 /// For all the destinations, they must support writing any value whose type is defined by DummyTypeSystem.
@@ -14,9 +81,15 @@ use chrono::{DateTime, Utc};
 pub enum DummyTypeSystem {
     F64(bool),
     I64(bool),
+    U64(bool),
     Bool(bool),
     String(bool),
     DateTime(bool),
+    Point(bool),
+    Circle(bool),
+    Snapshot(bool),
+    StringList(bool),
+    Record(bool),
 }
 
 impl_typesystem! {
@@ -24,8 +97,24 @@ impl_typesystem! {
     mappings = {
         { F64 => f64 }
         { I64 => i64 }
+        { U64 => u64 }
         { Bool => bool }
         { String => String }
         { DateTime => DateTime<Utc> }
+        { Point => Point }
+        { Circle => Circle }
+        { Snapshot => Snapshot }
+        { StringList => Vec<Option<String>> }
+        { Record => Record }
+    }
+}
+
+impl DummyTypeSystem {
+    pub fn is_nullable(&self) -> bool {
+        use DummyTypeSystem::*;
+        match *self {
+            F64(n) | I64(n) | U64(n) | Bool(n) | String(n) | DateTime(n) | Point(n) | Circle(n)
+            | Snapshot(n) | StringList(n) | Record(n) => n,
+        }
     }
 }
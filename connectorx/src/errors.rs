@@ -39,6 +39,39 @@ pub enum ConnectorAgentError {
     #[error("Only support partition on SPJ query, got {0}.")]
     SQLQueryPartitionNotSupported(String),
 
+    #[error("Columns {0} and {1} both map to {2} after name normalization.")]
+    ColumnNameCollision(String, String, String),
+
+    #[error("Expected a single row, got at least {0}.")]
+    TooManyRows(usize),
+
+    #[error("Expected a single column, got {0}.")]
+    TooManyColumns(usize),
+
+    #[error("Expected a single column, the query returns no columns.")]
+    NoColumns,
+
+    #[error("Column {0} has the pseudo-type {1}, which cannot be mapped to a type. Cast it to a concrete type or skip pseudo-type columns.")]
+    UnmappablePseudoType(String, String),
+
+    #[error("Column {col} is required but has a null in row {row}.")]
+    UnexpectedNull { col: String, row: usize },
+
+    #[error("Required column {0} is not in the result.")]
+    RequiredColumnNotFound(String),
+
+    /// A cell failed to decode, raised instead of `source` by the sources in diagnostic mode.
+    /// `row` counts from the start of the result, as for `UnexpectedNull`. `raw_bytes` are
+    /// the bytes of the cell as the source got them, none for a null.
+    #[error("Cannot decode column {col} in row {row} as {ty}: {source}, raw bytes: {}", raw_hex(.raw_bytes))]
+    CannotDecode {
+        col: String,
+        row: usize,
+        ty: &'static str,
+        raw_bytes: Option<Vec<u8>>,
+        source: Box<ConnectorAgentError>,
+    },
+
     #[error(transparent)]
     IOError(#[from] std::io::Error),
 
@@ -78,6 +111,50 @@ impl ConnectorAgentError {
     pub fn cannot_produce<T>(context: Option<String>) -> Self {
         ConnectorAgentError::CannotProduce(type_name::<T>(), context.into())
     }
+
+    /// A `CannotDecode` with its `row` counted within the partition.
+    pub fn cannot_decode<T>(
+        col: &str,
+        row: usize,
+        raw_bytes: Option<Vec<u8>>,
+        source: ConnectorAgentError,
+    ) -> Self {
+        ConnectorAgentError::CannotDecode {
+            col: col.to_string(),
+            row,
+            ty: type_name::<T>(),
+            raw_bytes,
+            source: Box::new(source),
+        }
+    }
+
+    /// Count the row of a `CannotDecode` from the start of the result, its partition starting
+    /// at `offset`.
+    pub(crate) fn at_row_offset(self, offset: usize) -> Self {
+        match self {
+            ConnectorAgentError::CannotDecode {
+                col,
+                row,
+                ty,
+                raw_bytes,
+                source,
+            } => ConnectorAgentError::CannotDecode {
+                col,
+                row: offset + row,
+                ty,
+                raw_bytes,
+                source,
+            },
+            e => e,
+        }
+    }
+}
+
+fn raw_hex(raw_bytes: &Option<Vec<u8>>) -> String {
+    match raw_bytes {
+        Some(bytes) => hex::encode(bytes),
+        None => "none".to_string(),
+    }
 }
 
 #[derive(Debug)]
@@ -0,0 +1,83 @@
+use crate::errors::ConnectorAgentError;
+use anyhow::anyhow;
+use fehler::{throw, throws};
+use hyper::{body::Bytes, client::HttpConnector, Body, Client, Method, Request, StatusCode};
+use hyper_tls::HttpsConnector;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::runtime::{Builder, Runtime};
+
+/// A blocking HTTP(S) client for the sources that read from web APIs. The runtime and the
+/// connection pool are made once and shared by the clones, so that a source and its
+/// partitions send their requests through the same client, each from its own thread.
+#[derive(Clone)]
+pub(crate) struct HttpClient {
+    rt: Arc<Runtime>,
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+impl HttpClient {
+    #[throws(ConnectorAgentError)]
+    pub fn new() -> Self {
+        // a worker of its own drives the connections while the callers block on responses
+        let rt = Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()?;
+        HttpClient {
+            rt: Arc::new(rt),
+            client: Client::builder().build::<_, Body>(HttpsConnector::new()),
+        }
+    }
+
+    /// Send a request with `headers` and wait for the status and the whole body of the
+    /// response.
+    #[throws(ConnectorAgentError)]
+    pub fn request(
+        &self,
+        method: Method,
+        url: &str,
+        headers: &[(String, String)],
+        body: &str,
+    ) -> (StatusCode, Bytes) {
+        let mut req = Request::builder().method(method).uri(url);
+        for (name, value) in headers {
+            req = req.header(name.as_str(), value.as_str());
+        }
+        let req = req
+            .body(Body::from(body.to_string()))
+            .map_err(|e| anyhow!(e))?;
+
+        let client = &self.client;
+        self.rt
+            .block_on(async {
+                let resp = client.request(req).await?;
+                let status = resp.status();
+                let body = hyper::body::to_bytes(resp.into_body()).await?;
+                Ok::<_, hyper::Error>((status, body))
+            })
+            .map_err(|e| anyhow!(e))?
+    }
+
+    /// GET `url` and parse the response as JSON. A status other than a success is an error.
+    #[throws(ConnectorAgentError)]
+    pub fn get_json(&self, url: &str, headers: &[(String, String)]) -> Value {
+        let (status, body) = self.request(Method::GET, url, headers, "")?;
+        json_response(url, status, &body)?
+    }
+}
+
+/// Parse the `body` of a response to `url` as JSON, a status other than a success being an
+/// error.
+#[throws(ConnectorAgentError)]
+pub(crate) fn json_response(url: &str, status: StatusCode, body: &[u8]) -> Value {
+    if !status.is_success() {
+        throw!(anyhow!(
+            "{} returned {}: {}",
+            url,
+            status,
+            String::from_utf8_lossy(body)
+        ));
+    }
+    serde_json::from_slice(body).map_err(|e| anyhow!("{} did not return JSON: {}", url, e))?
+}
@@ -11,20 +11,31 @@ pub mod typesystem;
 pub mod macros;
 pub(crate) mod constants;
 pub mod data_order;
+pub mod decimal;
 pub mod destinations;
 pub mod dispatcher;
 pub mod dummy_typesystem;
 pub mod errors;
+pub(crate) mod http;
+pub mod metrics;
+pub mod name_case;
+pub mod pseudonym;
+pub mod rate_limit;
+pub mod read_budget;
 pub mod source_router;
 pub mod sources;
 pub mod sql;
 pub mod transports;
 
 pub use crate::data_order::DataOrder;
+pub use crate::decimal::{DecimalFormat, DecimalRounding};
 pub use crate::destinations::{Consume, Destination, DestinationPartition};
-pub use crate::dispatcher::Dispatcher;
+pub use crate::dispatcher::{ArrowRun, Dispatcher, ExtraResults};
 pub use crate::dummy_typesystem::DummyTypeSystem;
 pub use crate::errors::{ConnectorAgentError, Result};
+pub use crate::metrics::{MemoryEstimate, PartitionStats, RunMetrics};
+pub use crate::name_case::NameCase;
+pub use crate::pseudonym::Pseudonymizer;
 pub use crate::sources::{PartitionParser, Source, SourcePartition};
 pub use crate::typesystem::{
     ParameterizedFunc, ParameterizedOn, Realize, Transport, TypeAssoc, TypeConversion, TypeSystem,
@@ -113,6 +113,7 @@ macro_rules! impl_transport {
 
             impl_transport!(@cvtts [$TSS, $TSD] $([ $($TOKENS)+ ])*);
             impl_transport!(@process [$TSS, $TSD] $([ $($TOKENS)+ ])*);
+            impl_transport!(@process_check_null [$TSS, $TSD] $([ $($TOKENS)+ ])*);
             impl_transport!(@processor [$TSS, $TSD] $([ $($TOKENS)+ ])*, $([ $($TOKENS)+ ])*);
         }
     };
@@ -164,6 +165,36 @@ macro_rules! impl_transport {
         }
     };
 
+    (@process_check_null [$TSS:tt, $TSD:tt] $([ $V1:tt [$T1:ty] => $V2:tt [$T2:ty] | conversion $HOW:ident ])*) => {
+        fn process_check_null<'s, 'd, 'r>(
+            ts1: Self::TSS,
+            ts2: Self::TSD,
+            src: &'r mut <<Self::S as $crate::Source>::Partition as $crate::SourcePartition>::Parser<'s>,
+            dst: &'r mut <Self::D as $crate::Destination>::Partition<'d>,
+        ) -> $crate::Result<bool> {
+            match (ts1, ts2) {
+                $(
+                    ($TSS::$V1(true), $TSD::$V2(true)) => {
+                        let val: Option<$T1> = $crate::PartitionParser::parse(src)?;
+                        let is_null = val.is_none();
+                        let val: Option<$T2> = <Self as TypeConversion<Option<$T1>, _>>::convert(val);
+                        $crate::DestinationPartition::write(dst, val)?;
+                        Ok(is_null)
+                    }
+
+                    ($TSS::$V1(false), $TSD::$V2(false)) => {
+                        Self::process(ts1, ts2, src, dst)?;
+                        Ok(false)
+                    }
+                )*
+                #[allow(unreachable_patterns)]
+                _ => fehler::throw!($crate::ConnectorAgentError::NoConversionRule(
+                    format!("{:?}", ts1), format!("{:?}", ts1))
+                )
+            }
+        }
+    };
+
     (@processor [$TSS:tt, $TSD:tt] $([ $V1:tt [$T1:ty] => $V2:tt [$T2:ty] | conversion $HOW:ident ])*, $([ $($TOKENS:tt)+ ])*) => {
         fn processor<'s, 'd>(
             ts1: Self::TSS,
@@ -0,0 +1,84 @@
+use crate::sources::Notice;
+use std::time::Duration;
+
+/// What one partition of a run amounted to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PartitionStats {
+    pub rows: usize,
+    /// Time spent preparing the partition (running its query) and writing it out.
+    pub elapsed: Duration,
+}
+
+/// The most bytes a run held at once in each phase, as far as the crate accounts for them.
+/// This counts the buffers the destination sets up and the read buffers of the partitions,
+/// but not the memory of the database client or of values kept on the heap, like strings.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct MemoryEstimate {
+    /// The buffers of the destination, see `Destination::allocated_bytes`.
+    pub allocate: usize,
+    /// The buffers of the destination plus the read buffers of all the partitions being
+    /// written at the same time, see `SourcePartition::read_buffer_bytes`.
+    pub write: usize,
+}
+
+/// Per partition statistics of a finished run, in the order of the queries.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct RunMetrics {
+    pub partitions: Vec<PartitionStats>,
+    /// Set if the run was given a skew threshold and the skew ratio exceeded it.
+    pub skew_warning: Option<String>,
+    /// Set if the run was asked to account for its memory.
+    pub memory: Option<MemoryEstimate>,
+    /// The notices of the database, if the source was asked to collect them, see
+    /// `NoticePolicy::Collect`.
+    pub notices: Vec<Notice>,
+}
+
+impl RunMetrics {
+    pub fn new(partitions: Vec<PartitionStats>) -> Self {
+        RunMetrics {
+            partitions,
+            skew_warning: None,
+            memory: None,
+            notices: vec![],
+        }
+    }
+
+    pub fn total_rows(&self) -> usize {
+        self.partitions.iter().map(|p| p.rows).sum()
+    }
+
+    /// The row count of the largest partition over the mean row count. 1.0 means perfectly
+    /// balanced partitions, and also stands for a run without any rows.
+    pub fn skew_ratio(&self) -> f64 {
+        let total = self.total_rows();
+        if total == 0 {
+            return 1.0;
+        }
+        let max = self.partitions.iter().map(|p| p.rows).max().unwrap_or(0);
+        let mean = total as f64 / self.partitions.len() as f64;
+        max as f64 / mean
+    }
+
+    /// Describe the skew if the skew ratio exceeds `threshold`.
+    pub fn check_skew(&self, threshold: f64) -> Option<String> {
+        let ratio = self.skew_ratio();
+        if ratio <= threshold {
+            return None;
+        }
+        let (largest, stats) = self
+            .partitions
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, p)| p.rows)?;
+        Some(format!(
+            "partitions are skewed: partition {} has {} of {} rows, {:.2} times the mean over {} partitions (threshold {}), consider repartitioning",
+            largest,
+            stats.rows,
+            self.total_rows(),
+            ratio,
+            self.partitions.len(),
+            threshold
+        ))
+    }
+}
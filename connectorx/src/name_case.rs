@@ -0,0 +1,66 @@
+use crate::errors::ConnectorAgentError;
+use fehler::{throw, throws};
+use std::collections::HashMap;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum NameCase {
+    Lower,
+    Upper,
+    /// camelCase, PascalCase and separated words all become lower snake_case.
+    Snake,
+}
+
+impl NameCase {
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            NameCase::Lower => name.to_lowercase(),
+            NameCase::Upper => name.to_uppercase(),
+            NameCase::Snake => to_snake(name),
+        }
+    }
+}
+
+fn to_snake(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut ret = String::with_capacity(name.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c == ' ' || c == '-' || c == '_' {
+            if !ret.is_empty() && !ret.ends_with('_') {
+                ret.push('_');
+            }
+            continue;
+        }
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next = chars.get(i + 1);
+            // split "fooBar" before the B, and "HTTPServer" before the S
+            let boundary = prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next.map_or(false, |n| n.is_lowercase()));
+            if boundary && !ret.is_empty() && !ret.ends_with('_') {
+                ret.push('_');
+            }
+        }
+        ret.extend(c.to_lowercase());
+    }
+    ret
+}
+
+/// Apply `case` to all the column names, failing if two columns end up with the same name.
+#[throws(ConnectorAgentError)]
+pub fn normalize_names(names: &[String], case: NameCase) -> Vec<String> {
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    let mut ret = Vec::with_capacity(names.len());
+    for name in names {
+        let normalized = case.apply(name);
+        if let Some(other) = seen.insert(normalized.clone(), name) {
+            throw!(ConnectorAgentError::ColumnNameCollision(
+                other.to_string(),
+                name.to_string(),
+                normalized
+            ));
+        }
+        ret.push(normalized);
+    }
+    ret
+}
@@ -0,0 +1,25 @@
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+
+/// Maps values to stable pseudonyms, the hex of their HMAC-SHA256 under a key. The same value
+/// gets the same pseudonym under the same key, in any run and any table, so pseudonymized
+/// join keys still join. A value is hashed by its text, so the integer `42` and the string
+/// `"42"` get the same pseudonym, as they would compare equal after a cast in SQL.
+#[derive(Clone)]
+pub struct Pseudonymizer {
+    mac: Hmac<Sha256>,
+}
+
+impl Pseudonymizer {
+    pub fn new(key: &[u8]) -> Self {
+        Pseudonymizer {
+            mac: Hmac::new_varkey(key).expect("HMAC takes any key length"),
+        }
+    }
+
+    pub fn pseudonym(&self, value: &str) -> String {
+        let mut mac = self.mac.clone();
+        mac.update(value.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+}
@@ -0,0 +1,55 @@
+use crate::errors::ConnectorAgentError;
+use anyhow::anyhow;
+use fehler::{throw, throws};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A token bucket shared by the partitions of a source to cap their combined read rate.
+/// The bucket holds up to one second worth of tokens, so after an idle period a burst of
+/// that size goes through without waiting.
+pub struct RateLimiter {
+    per_sec: f64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl RateLimiter {
+    #[throws(ConnectorAgentError)]
+    pub fn new(per_sec: u64) -> Self {
+        if per_sec == 0 {
+            throw!(anyhow!("rate limit must be positive"));
+        }
+        RateLimiter {
+            per_sec: per_sec as f64,
+            bucket: Mutex::new(Bucket {
+                tokens: per_sec as f64,
+                last: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn per_sec(&self) -> u64 {
+        self.per_sec as u64
+    }
+
+    /// Take `n` tokens, sleeping until the bucket has refilled enough to cover them. A take
+    /// larger than what is available leaves the bucket in debt, which later takes wait off.
+    pub fn acquire(&self, n: usize) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let refill = now.duration_since(bucket.last).as_secs_f64() * self.per_sec;
+            bucket.tokens = (bucket.tokens + refill).min(self.per_sec) - n as f64;
+            bucket.last = now;
+            bucket.tokens
+        };
+        if wait < 0. {
+            thread::sleep(Duration::from_secs_f64(-wait / self.per_sec));
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use crate::errors::ConnectorAgentError;
+use anyhow::anyhow;
+use fehler::{throw, throws};
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex};
+
+/// Hands out read slots to the partitions of a run, so that at most `concurrency` of them
+/// read at the same time. A partition takes a slot for every batch of `batch_rows` rows and
+/// gives it back afterwards. The slots go out in the order they were asked for, so a
+/// partition that wants another batch queues up behind the ones already waiting, and the
+/// reads of large and small partitions are interleaved batch by batch.
+///
+/// The budget only bounds and orders the reads, it does not move work between threads: the
+/// partitions are still spread over the rayon pool by `par_iter`, one task each, and a
+/// partition that waits for its next slot keeps its thread and its open result. A parser
+/// borrows its partition and need not be `Send`, so a partition that started reading
+/// cannot be resumed by another thread.
+pub struct ReadBudget {
+    concurrency: usize,
+    batch_rows: usize,
+    state: Mutex<Queue>,
+    turn: Condvar,
+}
+
+struct Queue {
+    reading: usize,
+    waiting: VecDeque<u64>,
+    next: u64,
+}
+
+/// A read slot, given back to the budget when dropped.
+pub struct ReadSlot<'a> {
+    budget: &'a ReadBudget,
+}
+
+impl ReadBudget {
+    #[throws(ConnectorAgentError)]
+    pub fn new(concurrency: usize, batch_rows: usize) -> Self {
+        if concurrency == 0 {
+            throw!(anyhow!("read concurrency must be positive"));
+        }
+        if batch_rows == 0 {
+            throw!(anyhow!("read batch size must be positive"));
+        }
+        ReadBudget {
+            concurrency,
+            batch_rows,
+            state: Mutex::new(Queue {
+                reading: 0,
+                waiting: VecDeque::new(),
+                next: 0,
+            }),
+            turn: Condvar::new(),
+        }
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    pub fn batch_rows(&self) -> usize {
+        self.batch_rows
+    }
+
+    /// Wait until fewer than `concurrency` slots are out and every earlier caller got one.
+    pub fn acquire(&self) -> ReadSlot<'_> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let ticket = state.next;
+        state.next += 1;
+        state.waiting.push_back(ticket);
+        while state.reading >= self.concurrency || state.waiting.front() != Some(&ticket) {
+            state = self.turn.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+        state.waiting.pop_front();
+        state.reading += 1;
+        // the next in line may fit too
+        self.turn.notify_all();
+        ReadSlot { budget: self }
+    }
+}
+
+impl Drop for ReadSlot<'_> {
+    fn drop(&mut self) {
+        let mut state = self.budget.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.reading -= 1;
+        self.budget.turn.notify_all();
+    }
+}
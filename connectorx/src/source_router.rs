@@ -1,7 +1,8 @@
 use crate::errors::{ConnectorAgentError, Result};
 use crate::sources::postgres::PostgresTypeSystem;
 use crate::sql::{
-    get_partition_range_query, get_partition_range_query_sep, single_col_partition_query,
+    ctid_partition_query, ctid_table_query, get_partition_range_query,
+    get_partition_range_query_sep, single_col_partition_query,
 };
 use anyhow::anyhow;
 use fehler::{throw, throws};
@@ -25,6 +26,14 @@ impl TryFrom<&str> for SourceConn {
     type Error = ConnectorAgentError;
 
     fn try_from(conn: &str) -> Result<SourceConn> {
+        // libpq's `key=value` form, which is how a Unix socket is usually given, e.g.
+        // `host=/var/run/postgresql dbname=tpch`
+        if !conn.contains("://") && conn.parse::<postgres::Config>().is_ok() {
+            return Ok(SourceConn {
+                ty: SourceType::Postgres,
+                conn: conn.into(),
+            });
+        }
         let url = Url::parse(conn).map_err(|e| anyhow!("parse error: {}", e))?;
         match url.scheme() {
             "postgres" | "postgresql" => Ok(SourceConn {
@@ -43,13 +52,28 @@ impl TryFrom<&str> for SourceConn {
 impl SourceType {
     pub fn get_col_range(&self, conn: &str, query: &str, col: &str) -> Result<(i64, i64)> {
         match *self {
+            SourceType::Postgres if is_ctid(col) => pg_get_ctid_range(conn, query),
             SourceType::Postgres => pg_get_partition_range(conn, query, col),
             SourceType::Sqlite => sqlite_get_partition_range(conn, query, col),
         }
     }
 
-    pub fn get_part_query(&self, query: &str, col: &str, lower: i64, upper: i64) -> Result<String> {
+    /// The query of the partition `[lower, upper)` of `query` on `col`. The `last` partition of
+    /// a ctid partitioning has no upper bound, so it also covers the rows appended to the
+    /// table after `get_col_range`.
+    pub fn get_part_query(
+        &self,
+        query: &str,
+        col: &str,
+        lower: i64,
+        upper: i64,
+        last: bool,
+    ) -> Result<String> {
         match *self {
+            SourceType::Postgres if is_ctid(col) => {
+                let upper = if last { None } else { Some(upper) };
+                ctid_partition_query(query, lower, upper, &PostgreSqlDialect {})
+            }
             SourceType::Postgres => {
                 single_col_partition_query(query, col, lower, upper, &PostgreSqlDialect {})
             }
@@ -60,6 +84,32 @@ impl SourceType {
     }
 }
 
+// Partitioning on ctid splits the table by heap block instead of by value, which needs no index.
+fn is_ctid(col: &str) -> bool {
+    col.eq_ignore_ascii_case("ctid")
+}
+
+#[throws(ConnectorAgentError)]
+fn pg_get_ctid_range(conn: &str, query: &str) -> (i64, i64) {
+    let mut client = Client::connect(conn, NoTls)?;
+    let table_query = ctid_table_query(query, &PostgreSqlDialect {})?;
+    let row = client.query_one(table_query.as_str(), &[])?;
+    let (relkind, inherited, nblocks): (String, bool, i64) = (row.get(0), row.get(1), row.get(2));
+
+    // the blocks of a partitioned table or an inheritance parent are those of the tables
+    // below it, which its own size does not count and its ctids do not tell apart
+    if relkind != "r" || inherited {
+        throw!(anyhow!(
+            "cannot partition on ctid, the query does not read from a plain table without \
+             inheritance children (relkind {}, children: {})",
+            relkind,
+            inherited
+        ));
+    }
+
+    (0, (nblocks - 1).max(0))
+}
+
 #[throws(ConnectorAgentError)]
 fn pg_get_partition_range(conn: &str, query: &str, col: &str) -> (i64, i64) {
     let mut client = Client::connect(conn, NoTls)?;
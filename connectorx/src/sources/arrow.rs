@@ -0,0 +1,265 @@
+use crate::data_order::DataOrder;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::errors::{ConnectorAgentError, Result};
+use crate::sources::{PartitionParser, Produce, Source, SourcePartition};
+use anyhow::anyhow;
+use arrow::array::{
+    Array, ArrayRef, BooleanArray, Float64Array, Int64Array, LargeStringArray, StringArray,
+};
+use arrow::datatypes::{DataType as ArrowDataType, SchemaRef};
+use arrow::ipc::reader::FileReader;
+use arrow::record_batch::RecordBatch;
+use fehler::{throw, throws};
+use std::fs::File;
+
+/// Reads Arrow IPC files, each query being the path of a file. Partitions hand their record
+/// batches to a destination that takes batches as they are, and are read cell by cell
+/// otherwise.
+#[derive(Default)]
+pub struct ArrowSource {
+    files: Vec<String>,
+    names: Vec<String>,
+    schema: Vec<DummyTypeSystem>,
+}
+
+impl ArrowSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Source for ArrowSource {
+    const DATA_ORDERS: &'static [DataOrder] = &[DataOrder::RowMajor];
+    type Partition = ArrowSourcePartition;
+    type TypeSystem = DummyTypeSystem;
+
+    #[throws(ConnectorAgentError)]
+    fn set_data_order(&mut self, data_order: DataOrder) {
+        if !matches!(data_order, DataOrder::RowMajor) {
+            throw!(ConnectorAgentError::UnsupportedDataOrder(data_order))
+        }
+    }
+
+    fn set_queries<Q: AsRef<str>>(&mut self, queries: &[Q]) {
+        self.files = queries
+            .iter()
+            .map(|fname| fname.as_ref().to_string())
+            .collect();
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn fetch_metadata(&mut self) {
+        let reader = FileReader::try_new(File::open(&self.files[0])?)?;
+        let schema = reader.schema();
+
+        self.names = schema.fields().iter().map(|f| f.name().clone()).collect();
+        self.schema = schema
+            .fields()
+            .iter()
+            .map(|f| {
+                let nullable = f.is_nullable();
+                Ok(match f.data_type() {
+                    ArrowDataType::Float64 => DummyTypeSystem::F64(nullable),
+                    ArrowDataType::Int64 => DummyTypeSystem::I64(nullable),
+                    ArrowDataType::Boolean => DummyTypeSystem::Bool(nullable),
+                    ArrowDataType::Utf8 | ArrowDataType::LargeUtf8 => {
+                        DummyTypeSystem::String(nullable)
+                    }
+                    ty => throw!(anyhow!(
+                        "column {} has the arrow type {:?}, which is not supported",
+                        f.name(),
+                        ty
+                    )),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.names.clone()
+    }
+
+    fn schema(&self) -> Vec<Self::TypeSystem> {
+        self.schema.clone()
+    }
+
+    fn partition(self) -> Result<Vec<Self::Partition>> {
+        let ncols = self.schema.len();
+        Ok(self
+            .files
+            .into_iter()
+            .map(|f| ArrowSourcePartition::new(&f, ncols))
+            .collect())
+    }
+}
+
+pub struct ArrowSourcePartition {
+    fname: String,
+    schema: Option<SchemaRef>,
+    batches: Vec<RecordBatch>,
+    handed_out: usize,
+    nrows: usize,
+    ncols: usize,
+}
+
+impl ArrowSourcePartition {
+    pub fn new(fname: &str, ncols: usize) -> Self {
+        Self {
+            fname: fname.into(),
+            schema: None,
+            batches: vec![],
+            handed_out: 0,
+            nrows: 0,
+            ncols,
+        }
+    }
+
+    /// The number of rows of each record batch of the file, in order. Empty until prepared.
+    pub fn batch_rows(&self) -> Vec<usize> {
+        self.batches.iter().map(|b| b.num_rows()).collect()
+    }
+}
+
+impl SourcePartition for ArrowSourcePartition {
+    type TypeSystem = DummyTypeSystem;
+    type Parser<'a> = ArrowSourcePartitionParser<'a>;
+
+    /// Read all the record batches of the file.
+    fn prepare(&mut self) -> Result<()> {
+        let reader = FileReader::try_new(File::open(&self.fname)?)?;
+        self.schema = Some(reader.schema());
+        self.batches = reader.collect::<std::result::Result<_, _>>()?;
+        self.handed_out = 0;
+        self.nrows = self.batches.iter().map(|b| b.num_rows()).sum();
+        Ok(())
+    }
+
+    fn parser(&mut self) -> Result<Self::Parser<'_>> {
+        Ok(ArrowSourcePartitionParser {
+            batches: &self.batches,
+            ncols: self.ncols,
+            batch: 0,
+            row: 0,
+            col: 0,
+        })
+    }
+
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    fn release(&mut self) -> bool {
+        self.batches = vec![];
+        true
+    }
+
+    fn batch_schema(&self) -> Option<SchemaRef> {
+        self.schema.clone()
+    }
+
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        // the columns are reference counted, handing out a batch copies no data
+        let batch = self.batches.get(self.handed_out).cloned();
+        self.handed_out += 1;
+        Ok(batch)
+    }
+}
+
+pub struct ArrowSourcePartitionParser<'a> {
+    batches: &'a [RecordBatch],
+    ncols: usize,
+    batch: usize,
+    row: usize,
+    col: usize,
+}
+
+impl<'a> ArrowSourcePartitionParser<'a> {
+    fn next_cell(&mut self) -> Result<(&'a ArrayRef, usize)> {
+        // move past the batches that are done, and any empty ones
+        while self.batch < self.batches.len() && self.row == self.batches[self.batch].num_rows() {
+            self.batch += 1;
+            self.row = 0;
+        }
+        let batch = self
+            .batches
+            .get(self.batch)
+            .ok_or(ConnectorAgentError::OutOfBound)?;
+        let cell = (batch.column(self.col), self.row);
+        self.col += 1;
+        if self.col == self.ncols {
+            self.col = 0;
+            self.row += 1;
+        }
+        Ok(cell)
+    }
+}
+
+impl<'a> PartitionParser<'a> for ArrowSourcePartitionParser<'a> {
+    type TypeSystem = DummyTypeSystem;
+}
+
+fn downcast<A: 'static, T>(array: &ArrayRef) -> Result<&A> {
+    array.as_any().downcast_ref::<A>().ok_or_else(|| {
+        ConnectorAgentError::cannot_produce::<T>(Some(format!("{:?}", array.data_type())))
+    })
+}
+
+macro_rules! impl_produce {
+    ($($t:ty => $A:ty,)+) => {
+        $(
+            impl<'r, 'a> Produce<'r, $t> for ArrowSourcePartitionParser<'a> {
+                fn produce(&mut self) -> Result<$t> {
+                    let (array, row) = self.next_cell()?;
+                    Ok(downcast::<$A, $t>(array)?.value(row))
+                }
+            }
+
+            impl<'r, 'a> Produce<'r, Option<$t>> for ArrowSourcePartitionParser<'a> {
+                fn produce(&mut self) -> Result<Option<$t>> {
+                    let (array, row) = self.next_cell()?;
+                    let array = downcast::<$A, Option<$t>>(array)?;
+                    Ok(if array.is_null(row) {
+                        None
+                    } else {
+                        Some(array.value(row))
+                    })
+                }
+            }
+        )+
+    };
+}
+
+impl_produce!(
+    f64 => Float64Array,
+    i64 => Int64Array,
+    bool => BooleanArray,
+);
+
+fn string_value<T>(array: &ArrayRef, row: usize) -> Result<String> {
+    Ok(match array.data_type() {
+        ArrowDataType::LargeUtf8 => downcast::<LargeStringArray, T>(array)?.value(row),
+        _ => downcast::<StringArray, T>(array)?.value(row),
+    }
+    .to_string())
+}
+
+impl<'r, 'a> Produce<'r, String> for ArrowSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<String> {
+        let (array, row) = self.next_cell()?;
+        string_value::<String>(array, row)
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<String>> for ArrowSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<Option<String>> {
+        let (array, row) = self.next_cell()?;
+        if array.is_null(row) {
+            return Ok(None);
+        }
+        Ok(Some(string_value::<Option<String>>(array, row)?))
+    }
+}
@@ -210,6 +210,8 @@ impl SourcePartition for CSVSourcePartition {
             .has_headers(true)
             .from_reader(File::open(&self.fname)?);
 
+        self.records.clear();
+        self.counter = 0;
         reader.into_records().try_for_each(|v| -> Result<()> {
             self.records.push(v.map_err(|e| anyhow!(e))?);
             Ok(())
@@ -236,6 +238,11 @@ impl SourcePartition for CSVSourcePartition {
             ncols: self.ncols,
         })
     }
+
+    fn release(&mut self) -> bool {
+        self.records = Vec::new();
+        true
+    }
 }
 
 pub struct CSVSourcePartitionParser<'a> {
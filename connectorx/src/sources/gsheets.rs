@@ -0,0 +1,478 @@
+use super::{json, PartitionParser, Produce, Source, SourcePartition};
+use crate::data_order::DataOrder;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::errors::{ConnectorAgentError, Result};
+use crate::http::HttpClient;
+use anyhow::anyhow;
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use fehler::{throw, throws};
+use serde_json::Value;
+use url::Url;
+
+const SHEETS_API: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+
+/// How the Sheets API renders date and time cells.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DateTimeRender {
+    /// Dates come as days since 1899-12-30, indistinguishable from plain numbers, so a
+    /// `DateTime` column has to be declared in the schema to be read as such.
+    SerialNumber,
+    /// Dates come as strings in the sheet's display format and are picked up by inference.
+    FormattedString,
+}
+
+impl DateTimeRender {
+    fn as_param(&self) -> &'static str {
+        match self {
+            DateTimeRender::SerialNumber => "SERIAL_NUMBER",
+            DateTimeRender::FormattedString => "FORMATTED_STRING",
+        }
+    }
+}
+
+/// Reads A1 ranges (e.g. `Sheet1!A1:F500`) of a spreadsheet, one partition per range. The
+/// first range starts with the header row, the others hold only data rows, so that a sheet
+/// can be split by rows, e.g. into `Sheet1!A1:F500` and `Sheet1!A501:F1000`.
+pub struct GSheetsSource {
+    api_url: String,
+    spreadsheet_id: String,
+    token: String,
+    date_render: DateTimeRender,
+    schema: Vec<DummyTypeSystem>,
+    ranges: Vec<String>,
+    names: Vec<String>,
+    first_rows: Option<Vec<Vec<Value>>>,
+    client: Option<HttpClient>,
+}
+
+impl GSheetsSource {
+    /// `token` is an OAuth2 access token, e.g. one minted for a service account with the
+    /// `spreadsheets.readonly` scope. An empty `schema` means inferring it from the first range.
+    pub fn new(spreadsheet_id: &str, token: &str, schema: &[DummyTypeSystem]) -> Self {
+        GSheetsSource {
+            api_url: SHEETS_API.to_string(),
+            spreadsheet_id: spreadsheet_id.to_string(),
+            token: token.to_string(),
+            date_render: DateTimeRender::FormattedString,
+            schema: schema.to_vec(),
+            ranges: vec![],
+            names: vec![],
+            first_rows: None,
+            client: None,
+        }
+    }
+
+    pub fn date_render(&mut self, date_render: DateTimeRender) {
+        self.date_render = date_render;
+    }
+
+    /// Send the requests to `url`, which serves the `spreadsheets` collection of the Sheets
+    /// API, instead of `https://sheets.googleapis.com/v4/spreadsheets`, e.g. a proxy.
+    pub fn api_url(&mut self, url: &str) {
+        self.api_url = url.to_string();
+    }
+
+    pub fn infer_schema(&self, rows: &[Vec<Value>]) -> Vec<DummyTypeSystem> {
+        let max_records_to_read = 50;
+        let num_cols = self.names.len();
+        let cells = rows.iter().take(max_records_to_read).map(|row| {
+            (0..num_cols)
+                .map(|i| row.get(i).filter(|v| !is_empty(v)))
+                .collect()
+        });
+        json::infer_schema(&self.names, cells, parse_datetime)
+    }
+}
+
+impl Source for GSheetsSource {
+    const DATA_ORDERS: &'static [DataOrder] = &[DataOrder::RowMajor];
+    type Partition = GSheetsSourcePartition;
+    type TypeSystem = DummyTypeSystem;
+
+    #[throws(ConnectorAgentError)]
+    fn set_data_order(&mut self, data_order: DataOrder) {
+        if !matches!(data_order, DataOrder::RowMajor) {
+            throw!(ConnectorAgentError::UnsupportedDataOrder(data_order))
+        }
+    }
+
+    fn set_queries<Q: AsRef<str>>(&mut self, queries: &[Q]) {
+        self.ranges = queries.iter().map(|q| q.as_ref().to_string()).collect();
+    }
+
+    fn fetch_metadata(&mut self) -> Result<()> {
+        assert!(!self.ranges.is_empty());
+
+        let client = HttpClient::new()?;
+        let rows = fetch_values(
+            &client,
+            &self.api_url,
+            &self.spreadsheet_id,
+            &self.token,
+            &self.ranges[0],
+            self.date_render,
+        )?;
+        let header = rows
+            .first()
+            .ok_or_else(|| anyhow!("range {} has no header row", self.ranges[0]))?;
+        self.names = header.iter().map(cell_to_string).collect();
+
+        if self.schema.is_empty() {
+            self.schema = self.infer_schema(&rows[1..]);
+        } else if self.schema.len() != self.names.len() {
+            throw!(anyhow!(
+                "schema has {} columns but range {} has {}: {:?}",
+                self.schema.len(),
+                self.ranges[0],
+                self.names.len(),
+                self.names
+            ));
+        }
+
+        // the first partition reads the rows fetched here instead of fetching them again
+        self.first_rows = Some(rows);
+        self.client = Some(client);
+
+        Ok(())
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.names.clone()
+    }
+
+    fn schema(&self) -> Vec<Self::TypeSystem> {
+        self.schema.clone()
+    }
+
+    fn partition(mut self) -> Result<Vec<Self::Partition>> {
+        let ncols = self.names.len();
+        let mut first_rows = self.first_rows.take();
+        let client = self
+            .client
+            .take()
+            .ok_or_else(|| anyhow!("fetch_metadata was not called"))?;
+        Ok(self
+            .ranges
+            .iter()
+            .enumerate()
+            .map(|(i, range)| {
+                let mut partition = GSheetsSourcePartition::new(
+                    &client,
+                    &self.api_url,
+                    &self.spreadsheet_id,
+                    &self.token,
+                    range,
+                    self.date_render,
+                    ncols,
+                );
+                if i == 0 {
+                    partition.fetched = first_rows.take();
+                    partition.header = true;
+                }
+                partition
+            })
+            .collect())
+    }
+}
+
+pub struct GSheetsSourcePartition {
+    client: HttpClient,
+    api_url: String,
+    spreadsheet_id: String,
+    token: String,
+    range: String,
+    date_render: DateTimeRender,
+    fetched: Option<Vec<Vec<Value>>>,
+    header: bool,
+    rows: Vec<Vec<Value>>,
+    counter: usize,
+    nrows: usize,
+    ncols: usize,
+}
+
+impl GSheetsSourcePartition {
+    fn new(
+        client: &HttpClient,
+        api_url: &str,
+        spreadsheet_id: &str,
+        token: &str,
+        range: &str,
+        date_render: DateTimeRender,
+        ncols: usize,
+    ) -> Self {
+        Self {
+            client: client.clone(),
+            api_url: api_url.into(),
+            spreadsheet_id: spreadsheet_id.into(),
+            token: token.into(),
+            range: range.into(),
+            date_render,
+            fetched: None,
+            header: false,
+            rows: vec![],
+            counter: 0,
+            nrows: 0,
+            ncols,
+        }
+    }
+}
+
+impl SourcePartition for GSheetsSourcePartition {
+    type TypeSystem = DummyTypeSystem;
+    type Parser<'a> = GSheetsSourcePartitionParser<'a>;
+
+    fn prepare(&mut self) -> Result<()> {
+        let mut rows = match self.fetched.take() {
+            Some(rows) => rows,
+            None => fetch_values(
+                &self.client,
+                &self.api_url,
+                &self.spreadsheet_id,
+                &self.token,
+                &self.range,
+                self.date_render,
+            )?,
+        };
+        if self.header && !rows.is_empty() {
+            rows.remove(0);
+        }
+        // the API leaves out trailing empty cells of a row
+        for row in &mut rows {
+            row.resize(self.ncols, Value::Null);
+        }
+        self.nrows = rows.len();
+        self.rows = rows;
+        Ok(())
+    }
+
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    // the range is fetched again when prepared again, the first one as well
+    fn release(&mut self) -> bool {
+        self.rows = vec![];
+        true
+    }
+
+    fn parser(&mut self) -> Result<Self::Parser<'_>> {
+        Ok(GSheetsSourcePartitionParser {
+            rows: &self.rows,
+            counter: &mut self.counter,
+            ncols: self.ncols,
+        })
+    }
+}
+
+#[throws(ConnectorAgentError)]
+fn fetch_values(
+    client: &HttpClient,
+    api_url: &str,
+    spreadsheet_id: &str,
+    token: &str,
+    range: &str,
+    date_render: DateTimeRender,
+) -> Vec<Vec<Value>> {
+    let mut url = Url::parse(api_url).map_err(|e| anyhow!(e))?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow!("cannot build the Sheets API url"))?
+        .pop_if_empty()
+        .push(spreadsheet_id)
+        .push("values")
+        .push(range);
+    url.query_pairs_mut()
+        .append_pair("majorDimension", "ROWS")
+        .append_pair("valueRenderOption", "UNFORMATTED_VALUE")
+        .append_pair("dateTimeRenderOption", date_render.as_param());
+
+    let headers = [("Authorization".to_string(), format!("Bearer {}", token))];
+    let mut resp = client.get_json(url.as_str(), &headers)?;
+    match resp.get_mut("values").map(Value::take) {
+        // an empty range has no "values" at all
+        None => vec![],
+        Some(Value::Array(rows)) => rows
+            .into_iter()
+            .map(|row| match row {
+                Value::Array(cells) => Ok(cells),
+                _ => Err(anyhow!("unexpected row in Sheets API response: {}", row).into()),
+            })
+            .collect::<Result<Vec<_>>>()?,
+        Some(v) => throw!(anyhow!("unexpected values in Sheets API response: {}", v)),
+    }
+}
+
+fn cell_to_string(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        v => v.to_string(),
+    }
+}
+
+fn is_empty(v: &Value) -> bool {
+    match v {
+        Value::Null => true,
+        Value::String(s) => s.is_empty(),
+        _ => false,
+    }
+}
+
+/// Parse the date and time formats a sheet displays by default, taking them as UTC. A date
+/// alone is at midnight.
+pub fn parse_datetime(s: &str) -> Option<DateTime<Utc>> {
+    const DATETIME_FORMATS: &[&str] = &[
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+        "%m/%d/%Y %H:%M:%S",
+        "%-m/%-d/%Y %-H:%M:%S",
+    ];
+    const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%m/%d/%Y", "%-m/%-d/%Y"];
+
+    DATETIME_FORMATS
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+        .or_else(|| {
+            DATE_FORMATS
+                .iter()
+                .find_map(|f| NaiveDate::parse_from_str(s, f).ok())
+                .map(|d| d.and_hms(0, 0, 0))
+        })
+        .map(|dt| DateTime::from_utc(dt, Utc))
+}
+
+/// The time of a serial number, which counts days, with the time as a fraction, since
+/// 1899-12-30, to the millisecond.
+pub fn serial_to_datetime(serial: f64) -> DateTime<Utc> {
+    let epoch = NaiveDate::from_ymd(1899, 12, 30).and_hms(0, 0, 0);
+    let millis = (serial * 86_400_000.0).round() as i64;
+    DateTime::from_utc(epoch + Duration::milliseconds(millis), Utc)
+}
+
+pub struct GSheetsSourcePartitionParser<'a> {
+    rows: &'a [Vec<Value>],
+    counter: &'a mut usize,
+    ncols: usize,
+}
+
+impl<'a> GSheetsSourcePartitionParser<'a> {
+    fn next_val(&mut self) -> &'a Value {
+        let v = &self.rows[*self.counter / self.ncols][*self.counter % self.ncols];
+        *self.counter += 1;
+
+        v
+    }
+}
+
+impl<'a> PartitionParser<'a> for GSheetsSourcePartitionParser<'a> {
+    type TypeSystem = DummyTypeSystem;
+}
+
+impl<'r, 'a> Produce<'r, i64> for GSheetsSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<i64> {
+        let v = self.next_val();
+        match v {
+            Value::Number(n) => n
+                .as_i64()
+                .or_else(|| n.as_f64().filter(|f| f.fract() == 0.0).map(|f| f as i64)),
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| ConnectorAgentError::cannot_produce::<i64>(Some(v.to_string())))
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<i64>> for GSheetsSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<Option<i64>> {
+        if is_empty(&self.rows[*self.counter / self.ncols][*self.counter % self.ncols]) {
+            *self.counter += 1;
+            return Ok(None);
+        }
+        Ok(Some(Produce::<i64>::produce(self)?))
+    }
+}
+
+impl<'r, 'a> Produce<'r, f64> for GSheetsSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<f64> {
+        let v = self.next_val();
+        match v {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| ConnectorAgentError::cannot_produce::<f64>(Some(v.to_string())))
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<f64>> for GSheetsSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<Option<f64>> {
+        if is_empty(&self.rows[*self.counter / self.ncols][*self.counter % self.ncols]) {
+            *self.counter += 1;
+            return Ok(None);
+        }
+        Ok(Some(Produce::<f64>::produce(self)?))
+    }
+}
+
+impl<'r, 'a> Produce<'r, bool> for GSheetsSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<bool> {
+        let v = self.next_val();
+        match v {
+            Value::Bool(b) => Some(*b),
+            Value::String(s) => s.to_lowercase().parse().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| ConnectorAgentError::cannot_produce::<bool>(Some(v.to_string())))
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<bool>> for GSheetsSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<Option<bool>> {
+        if is_empty(&self.rows[*self.counter / self.ncols][*self.counter % self.ncols]) {
+            *self.counter += 1;
+            return Ok(None);
+        }
+        Ok(Some(Produce::<bool>::produce(self)?))
+    }
+}
+
+impl<'r, 'a> Produce<'r, String> for GSheetsSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<String> {
+        Ok(cell_to_string(self.next_val()))
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<String>> for GSheetsSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<Option<String>> {
+        let v = self.next_val();
+        if is_empty(v) {
+            return Ok(None);
+        }
+        Ok(Some(cell_to_string(v)))
+    }
+}
+
+impl<'r, 'a> Produce<'r, DateTime<Utc>> for GSheetsSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<DateTime<Utc>> {
+        let v = self.next_val();
+        match v {
+            Value::Number(n) => n.as_f64().map(serial_to_datetime),
+            Value::String(s) => parse_datetime(s),
+            _ => None,
+        }
+        .ok_or_else(|| ConnectorAgentError::cannot_produce::<DateTime<Utc>>(Some(v.to_string())))
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<DateTime<Utc>>> for GSheetsSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<Option<DateTime<Utc>>> {
+        if is_empty(&self.rows[*self.counter / self.ncols][*self.counter % self.ncols]) {
+            *self.counter += 1;
+            return Ok(None);
+        }
+        Ok(Some(Produce::<DateTime<Utc>>::produce(self)?))
+    }
+}
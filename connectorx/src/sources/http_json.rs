@@ -0,0 +1,562 @@
+use super::{json, PartitionParser, Produce, Source, SourcePartition};
+use crate::data_order::DataOrder;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::errors::{ConnectorAgentError, Result};
+use crate::http::HttpClient;
+use anyhow::anyhow;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use fehler::{throw, throws};
+use log::debug;
+use serde_json::{Map, Value};
+use url::Url;
+
+const MAX_RECORDS_TO_INFER: usize = 50;
+
+/// How the pages of an endpoint are reached from the URL given as the query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pagination {
+    /// The URL is the only page.
+    Single,
+    /// Pages are addressed with `offset_param` and `limit_param` query parameters. The
+    /// number of records is read from `total_path` in the first response if given,
+    /// otherwise pages are requested until one comes back empty.
+    Offset {
+        offset_param: String,
+        limit_param: String,
+        page_size: usize,
+        total_path: Option<String>,
+    },
+    /// Each response carries the token of the next page at `token_path`, which is sent back
+    /// as the `token_param` query parameter. The last page has no (or a null) token.
+    NextToken {
+        token_param: String,
+        token_path: String,
+    },
+}
+
+enum Page {
+    Url(String),
+    Fetched(Vec<Value>),
+}
+
+/// Reads JSON endpoints, one partition per page. The queries are the endpoint URLs. Every
+/// record has to be a JSON object; its top-level fields become the columns, and nested
+/// objects and arrays are read as their JSON text.
+pub struct HttpJsonSource {
+    headers: Vec<(String, String)>,
+    records_path: String,
+    pagination: Pagination,
+    schema: Vec<DummyTypeSystem>,
+    urls: Vec<String>,
+    names: Vec<String>,
+    pages: Vec<Page>,
+    client: Option<HttpClient>,
+}
+
+impl HttpJsonSource {
+    /// An empty `schema` means inferring it from the first page.
+    pub fn new(schema: &[DummyTypeSystem]) -> Self {
+        HttpJsonSource {
+            headers: vec![],
+            records_path: String::new(),
+            pagination: Pagination::Single,
+            schema: schema.to_vec(),
+            urls: vec![],
+            names: vec![],
+            pages: vec![],
+            client: None,
+        }
+    }
+
+    /// Send `name: value` with every request, e.g. `Authorization: Bearer <token>`.
+    pub fn header(&mut self, name: &str, value: &str) {
+        self.headers.push((name.to_string(), value.to_string()));
+    }
+
+    /// Where the records array sits in a response, as dot separated field names like
+    /// `data.items`. By default the response itself is the array.
+    pub fn records_path(&mut self, path: &str) {
+        self.records_path = path.to_string();
+    }
+
+    /// Set how the pages are reached (default `Pagination::Single`). An offset `page_size`
+    /// of 0 is an error.
+    #[throws(ConnectorAgentError)]
+    pub fn pagination(&mut self, pagination: Pagination) {
+        if let Pagination::Offset { page_size: 0, .. } = pagination {
+            throw!(anyhow!("page_size must be positive"));
+        }
+        self.pagination = pagination;
+    }
+
+    pub fn infer_schema(&self, records: &[&Value]) -> Vec<DummyTypeSystem> {
+        let cells = records
+            .iter()
+            .map(|record| self.names.iter().map(|name| record.get(name)).collect());
+        json::infer_schema(&self.names, cells, parse_datetime)
+    }
+
+    // Walk the pages of `url` as far as needed to know all of them. Pages fetched on the way
+    // are kept so that they are not requested twice.
+    #[throws(ConnectorAgentError)]
+    fn discover_pages(&self, client: &HttpClient, url: &str) -> Vec<Page> {
+        match &self.pagination {
+            Pagination::Single => {
+                vec![Page::Fetched(self.fetch_records(client, url)?.1)]
+            }
+            Pagination::Offset {
+                offset_param,
+                limit_param,
+                page_size,
+                total_path,
+            } => {
+                let page_url = |offset: usize| -> Result<String> {
+                    let mut u = Url::parse(url).map_err(|e| anyhow!(e))?;
+                    u.query_pairs_mut()
+                        .append_pair(offset_param, &offset.to_string())
+                        .append_pair(limit_param, &page_size.to_string());
+                    Ok(u.to_string())
+                };
+
+                let (resp, first) = self.fetch_records(client, &page_url(0)?)?;
+                match total_path {
+                    Some(path) => {
+                        let total = lookup(&resp, path)
+                            .and_then(Value::as_u64)
+                            .ok_or_else(|| anyhow!("no record count at {} in {}", path, url))?
+                            as usize;
+                        let mut pages = vec![Page::Fetched(first)];
+                        for offset in (*page_size..total).step_by(*page_size) {
+                            pages.push(Page::Url(page_url(offset)?));
+                        }
+                        pages
+                    }
+                    None => {
+                        let mut pages = vec![];
+                        let mut records = first;
+                        let mut offset = 0;
+                        while !records.is_empty() {
+                            pages.push(Page::Fetched(records));
+                            offset += page_size;
+                            records = self.fetch_records(client, &page_url(offset)?)?.1;
+                        }
+                        pages
+                    }
+                }
+            }
+            Pagination::NextToken {
+                token_param,
+                token_path,
+            } => {
+                let mut pages = vec![];
+                let mut page_url = url.to_string();
+                loop {
+                    let (resp, records) = self.fetch_records(client, &page_url)?;
+                    pages.push(Page::Fetched(records));
+                    let token = match lookup(&resp, token_path) {
+                        None | Some(Value::Null) => break,
+                        Some(Value::String(s)) if s.is_empty() => break,
+                        Some(Value::String(s)) => s.clone(),
+                        Some(v) => v.to_string(),
+                    };
+                    let mut u = Url::parse(url).map_err(|e| anyhow!(e))?;
+                    u.query_pairs_mut().append_pair(token_param, &token);
+                    page_url = u.to_string();
+                }
+                pages
+            }
+        }
+    }
+
+    #[throws(ConnectorAgentError)]
+    fn fetch_records(&self, client: &HttpClient, url: &str) -> (Value, Vec<Value>) {
+        let mut resp = client.get_json(url, &self.headers)?;
+        let records = take_records(&mut resp, &self.records_path, url)?;
+        (resp, records)
+    }
+}
+
+impl Source for HttpJsonSource {
+    const DATA_ORDERS: &'static [DataOrder] = &[DataOrder::RowMajor];
+    type Partition = HttpJsonSourcePartition;
+    type TypeSystem = DummyTypeSystem;
+
+    #[throws(ConnectorAgentError)]
+    fn set_data_order(&mut self, data_order: DataOrder) {
+        if !matches!(data_order, DataOrder::RowMajor) {
+            throw!(ConnectorAgentError::UnsupportedDataOrder(data_order))
+        }
+    }
+
+    fn set_queries<Q: AsRef<str>>(&mut self, queries: &[Q]) {
+        self.urls = queries.iter().map(|q| q.as_ref().to_string()).collect();
+    }
+
+    fn fetch_metadata(&mut self) -> Result<()> {
+        assert!(!self.urls.is_empty());
+
+        let client = HttpClient::new()?;
+        let mut pages = vec![];
+        for url in &self.urls {
+            pages.extend(self.discover_pages(&client, url)?);
+        }
+        debug!("{} pages from {} urls", pages.len(), self.urls.len());
+
+        // the columns and their types come from the records fetched so far
+        let sample: Vec<&Value> = pages
+            .iter()
+            .filter_map(|p| match p {
+                Page::Fetched(records) => Some(records),
+                Page::Url(_) => None,
+            })
+            .flatten()
+            .take(MAX_RECORDS_TO_INFER)
+            .collect();
+        if sample.is_empty() {
+            throw!(anyhow!("no records to take the columns from"));
+        }
+
+        // columns in the order their fields first show up
+        let mut names: Vec<String> = vec![];
+        for record in &sample {
+            for key in as_object(record)?.keys() {
+                if !names.contains(key) {
+                    names.push(key.clone());
+                }
+            }
+        }
+        self.names = names;
+
+        if self.schema.is_empty() {
+            self.schema = self.infer_schema(&sample);
+        } else if self.schema.len() != self.names.len() {
+            throw!(anyhow!(
+                "schema has {} columns but the records have {}: {:?}",
+                self.schema.len(),
+                self.names.len(),
+                self.names
+            ));
+        }
+
+        self.pages = pages;
+        self.client = Some(client);
+
+        Ok(())
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.names.clone()
+    }
+
+    fn schema(&self) -> Vec<Self::TypeSystem> {
+        self.schema.clone()
+    }
+
+    fn partition(self) -> Result<Vec<Self::Partition>> {
+        let client = self
+            .client
+            .ok_or_else(|| anyhow!("fetch_metadata was not called"))?;
+        let headers = self.headers;
+        let records_path = self.records_path;
+        let names = self.names;
+        Ok(self
+            .pages
+            .into_iter()
+            .map(|page| {
+                HttpJsonSourcePartition::new(&client, page, &headers, &records_path, &names)
+            })
+            .collect())
+    }
+}
+
+pub struct HttpJsonSourcePartition {
+    client: HttpClient,
+    page: Option<Page>,
+    headers: Vec<(String, String)>,
+    records_path: String,
+    names: Vec<String>,
+    rows: Vec<Vec<Value>>,
+    counter: usize,
+    nrows: usize,
+    ncols: usize,
+}
+
+impl HttpJsonSourcePartition {
+    fn new(
+        client: &HttpClient,
+        page: Page,
+        headers: &[(String, String)],
+        records_path: &str,
+        names: &[String],
+    ) -> Self {
+        Self {
+            client: client.clone(),
+            page: Some(page),
+            headers: headers.to_vec(),
+            records_path: records_path.into(),
+            names: names.to_vec(),
+            rows: vec![],
+            counter: 0,
+            nrows: 0,
+            ncols: names.len(),
+        }
+    }
+}
+
+impl SourcePartition for HttpJsonSourcePartition {
+    type TypeSystem = DummyTypeSystem;
+    type Parser<'a> = HttpJsonSourcePartitionParser<'a>;
+
+    fn prepare(&mut self) -> Result<()> {
+        let records = match self.page.take() {
+            Some(Page::Fetched(records)) => records,
+            Some(Page::Url(url)) => {
+                let mut resp = self.client.get_json(&url, &self.headers)?;
+                let records = take_records(&mut resp, &self.records_path, &url)?;
+                // kept to fetch the page again if it is released
+                self.page = Some(Page::Url(url));
+                records
+            }
+            None => throw!(anyhow!("partition is already prepared")),
+        };
+
+        let mut rows = Vec::with_capacity(records.len());
+        for record in records {
+            let mut obj = match record {
+                Value::Object(obj) => obj,
+                v => throw!(anyhow!("record is not a JSON object: {}", v)),
+            };
+            // fields that are not among the columns are dropped
+            rows.push(
+                self.names
+                    .iter()
+                    .map(|name| obj.remove(name).unwrap_or(Value::Null))
+                    .collect(),
+            );
+        }
+        self.nrows = rows.len();
+        self.rows = rows;
+        self.counter = 0;
+        Ok(())
+    }
+
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    // only the pages read by URL can be fetched again, the others came with the metadata
+    fn release(&mut self) -> bool {
+        match self.page {
+            Some(Page::Url(_)) => {
+                self.rows = vec![];
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn parser(&mut self) -> Result<Self::Parser<'_>> {
+        Ok(HttpJsonSourcePartitionParser {
+            rows: &self.rows,
+            counter: &mut self.counter,
+            ncols: self.ncols,
+        })
+    }
+}
+
+fn lookup<'v>(v: &'v Value, path: &str) -> Option<&'v Value> {
+    if path.is_empty() {
+        return Some(v);
+    }
+    path.split('.').try_fold(v, |v, field| v.get(field))
+}
+
+#[throws(ConnectorAgentError)]
+fn take_records(resp: &mut Value, path: &str, url: &str) -> Vec<Value> {
+    let mut v = resp;
+    if !path.is_empty() {
+        for field in path.split('.') {
+            v = v
+                .get_mut(field)
+                .ok_or_else(|| anyhow!("no field {} in the response of {}", path, url))?;
+        }
+    }
+    match v.take() {
+        Value::Array(records) => records,
+        // an endpoint may leave the array out, or send null, for an empty page
+        Value::Null => vec![],
+        other => throw!(anyhow!(
+            "expecting an array of records at '{}' in the response of {}, got {}",
+            path,
+            url,
+            other
+        )),
+    }
+}
+
+#[throws(ConnectorAgentError)]
+fn as_object(v: &Value) -> &Map<String, Value> {
+    v.as_object()
+        .ok_or_else(|| anyhow!("record is not a JSON object: {}", v))?
+}
+
+fn cell_to_string(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        // nested objects and arrays keep their JSON text
+        v => v.to_string(),
+    }
+}
+
+fn parse_datetime(s: &str) -> Option<DateTime<Utc>> {
+    const DATETIME_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"];
+
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|| {
+            DATETIME_FORMATS
+                .iter()
+                .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+                .or_else(|| {
+                    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                        .ok()
+                        .map(|d| d.and_hms(0, 0, 0))
+                })
+                .map(|dt| DateTime::from_utc(dt, Utc))
+        })
+}
+
+pub struct HttpJsonSourcePartitionParser<'a> {
+    rows: &'a [Vec<Value>],
+    counter: &'a mut usize,
+    ncols: usize,
+}
+
+impl<'a> HttpJsonSourcePartitionParser<'a> {
+    fn next_val(&mut self) -> &'a Value {
+        let v = &self.rows[*self.counter / self.ncols][*self.counter % self.ncols];
+        *self.counter += 1;
+
+        v
+    }
+
+    fn next_is_null(&self) -> bool {
+        self.rows[*self.counter / self.ncols][*self.counter % self.ncols].is_null()
+    }
+}
+
+impl<'a> PartitionParser<'a> for HttpJsonSourcePartitionParser<'a> {
+    type TypeSystem = DummyTypeSystem;
+}
+
+impl<'r, 'a> Produce<'r, i64> for HttpJsonSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<i64> {
+        let v = self.next_val();
+        match v {
+            Value::Number(n) => n.as_i64(),
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| ConnectorAgentError::cannot_produce::<i64>(Some(v.to_string())))
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<i64>> for HttpJsonSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<Option<i64>> {
+        if self.next_is_null() {
+            *self.counter += 1;
+            return Ok(None);
+        }
+        Ok(Some(Produce::<i64>::produce(self)?))
+    }
+}
+
+impl<'r, 'a> Produce<'r, f64> for HttpJsonSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<f64> {
+        let v = self.next_val();
+        match v {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| ConnectorAgentError::cannot_produce::<f64>(Some(v.to_string())))
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<f64>> for HttpJsonSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<Option<f64>> {
+        if self.next_is_null() {
+            *self.counter += 1;
+            return Ok(None);
+        }
+        Ok(Some(Produce::<f64>::produce(self)?))
+    }
+}
+
+impl<'r, 'a> Produce<'r, bool> for HttpJsonSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<bool> {
+        let v = self.next_val();
+        match v {
+            Value::Bool(b) => Some(*b),
+            Value::String(s) => s.to_lowercase().parse().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| ConnectorAgentError::cannot_produce::<bool>(Some(v.to_string())))
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<bool>> for HttpJsonSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<Option<bool>> {
+        if self.next_is_null() {
+            *self.counter += 1;
+            return Ok(None);
+        }
+        Ok(Some(Produce::<bool>::produce(self)?))
+    }
+}
+
+impl<'r, 'a> Produce<'r, String> for HttpJsonSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<String> {
+        let v = self.next_val();
+        if v.is_null() {
+            throw!(ConnectorAgentError::cannot_produce::<String>(None));
+        }
+        Ok(cell_to_string(v))
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<String>> for HttpJsonSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<Option<String>> {
+        let v = self.next_val();
+        if v.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(cell_to_string(v)))
+    }
+}
+
+impl<'r, 'a> Produce<'r, DateTime<Utc>> for HttpJsonSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<DateTime<Utc>> {
+        let v = self.next_val();
+        match v {
+            Value::String(s) => parse_datetime(s),
+            _ => None,
+        }
+        .ok_or_else(|| ConnectorAgentError::cannot_produce::<DateTime<Utc>>(Some(v.to_string())))
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<DateTime<Utc>>> for HttpJsonSourcePartitionParser<'a> {
+    fn produce(&mut self) -> Result<Option<DateTime<Utc>>> {
+        if self.next_is_null() {
+            *self.counter += 1;
+            return Ok(None);
+        }
+        Ok(Some(Produce::<DateTime<Utc>>::produce(self)?))
+    }
+}
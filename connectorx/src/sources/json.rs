@@ -0,0 +1,76 @@
+use crate::dummy_typesystem::DummyTypeSystem;
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Infer the types of the columns `names` from `rows`, which hold the cell of each column,
+/// None for a missing one. Strings that `parse_datetime` takes are dates, a column of
+/// integers and floats is a float column, and a column without any value or of several
+/// other types is a string column.
+pub(crate) fn infer_schema<'v, I>(
+    names: &[String],
+    rows: I,
+    parse_datetime: fn(&str) -> Option<DateTime<Utc>>,
+) -> Vec<DummyTypeSystem>
+where
+    I: IntoIterator<Item = Vec<Option<&'v Value>>>,
+{
+    let num_cols = names.len();
+
+    let mut column_types: Vec<HashSet<DummyTypeSystem>> = vec![HashSet::new(); num_cols];
+    let mut nulls: Vec<bool> = vec![false; num_cols];
+
+    for row in rows {
+        for field_counter in 0..num_cols {
+            let dt = match row.get(field_counter).copied().flatten() {
+                None | Some(Value::Null) => None,
+                Some(Value::Bool(_)) => Some(DummyTypeSystem::Bool(false)),
+                Some(Value::Number(n)) if n.is_i64() => Some(DummyTypeSystem::I64(false)),
+                Some(Value::Number(_)) => Some(DummyTypeSystem::F64(false)),
+                Some(Value::String(s)) if parse_datetime(s).is_some() => {
+                    Some(DummyTypeSystem::DateTime(false))
+                }
+                Some(_) => Some(DummyTypeSystem::String(false)),
+            };
+            match dt {
+                Some(dt) => {
+                    column_types[field_counter].insert(dt);
+                }
+                None => nulls[field_counter] = true,
+            }
+        }
+    }
+
+    let mut schema = vec![];
+    for field_counter in 0..num_cols {
+        let possibilities = &column_types[field_counter];
+        let has_nulls = nulls[field_counter];
+
+        let dt = match possibilities.len() {
+            0 => DummyTypeSystem::String(has_nulls),
+            1 => match possibilities.iter().next().unwrap() {
+                DummyTypeSystem::I64(_) => DummyTypeSystem::I64(has_nulls),
+                DummyTypeSystem::F64(_) => DummyTypeSystem::F64(has_nulls),
+                DummyTypeSystem::Bool(_) => DummyTypeSystem::Bool(has_nulls),
+                DummyTypeSystem::DateTime(_) => DummyTypeSystem::DateTime(has_nulls),
+                _ => DummyTypeSystem::String(has_nulls),
+            },
+            2 if possibilities.contains(&DummyTypeSystem::I64(false))
+                && possibilities.contains(&DummyTypeSystem::F64(false)) =>
+            {
+                // Integer && Float -> Float
+                DummyTypeSystem::F64(has_nulls)
+            }
+            _ => {
+                warn!(
+                    "column {} mixes {:?}, reading it as string",
+                    names[field_counter], possibilities
+                );
+                DummyTypeSystem::String(has_nulls)
+            }
+        };
+        schema.push(dt);
+    }
+    schema
+}
@@ -1,14 +1,24 @@
 // When implementing a data source, be make sure to implement Queryable and
 // Producer for all supported types in crate::types::DataType.
 
+pub mod arrow;
 pub mod csv;
 pub mod dummy;
+pub mod gsheets;
+pub mod http_json;
+mod json;
 pub mod postgres;
+pub mod sharded;
 pub mod sqlite;
+pub mod trino;
 
 use crate::data_order::DataOrder;
 use crate::errors::Result;
 use crate::typesystem::{TypeAssoc, TypeSystem};
+use ::arrow::datatypes::SchemaRef;
+use ::arrow::record_batch::RecordBatch;
+use anyhow::anyhow;
+use std::sync::{Arc, Mutex};
 
 pub trait Source {
     /// Supported data orders, ordering by preference.
@@ -22,6 +32,38 @@ pub trait Source {
 
     fn set_queries<Q: AsRef<str>>(&mut self, queries: &[Q]);
 
+    /// Tell the source that its queries start at the `offset`th of all the queries of the
+    /// run, as under `Dispatcher::with_partition_subset`. Only sources that treat a query by
+    /// its index among all of them need to implement this.
+    fn set_query_offset(&mut self, _offset: usize) {}
+
+    /// Ask `fetch_metadata` to reconcile queries that disagree on the type of a numeric
+    /// column by promoting the column to the widest of their types, integers to float.
+    /// Only sources whose column types can differ between queries need to implement this.
+    fn set_numeric_coercion(&mut self, _coerce: bool) {}
+
+    /// Rewrite `query` to return at most `limit` rows, so that `Dispatcher::read_one_row`
+    /// and `Dispatcher::read_scalar` do not count and hold all the rows of a large result.
+    /// Sources that do not read SQL return the query as it is.
+    fn limit_query(&self, query: &str, _limit: usize) -> Result<String> {
+        Ok(query.to_string())
+    }
+
+    /// Set how many rows each partition reads ahead of the parser. A source that fetches rows
+    /// in batches takes one round-trip per batch, so a larger buffer fills a high-latency
+    /// link better, at the cost of holding that many rows in memory per partition. Sources
+    /// without such a buffer fail.
+    fn set_read_buffer(&mut self, _rows: usize) -> Result<()> {
+        Err(anyhow!("this source has no read buffer to set").into())
+    }
+
+    /// Where the source gathers the notices of the database under `NoticePolicy::Collect`,
+    /// for the dispatcher to put into the `RunMetrics`. None for a source that does not
+    /// collect them.
+    fn notice_log(&self) -> Option<NoticeLog> {
+        None
+    }
+
     fn fetch_metadata(&mut self) -> Result<()>;
 
     fn names(&self) -> Vec<String>;
@@ -47,6 +89,33 @@ pub trait SourcePartition: Sized {
 
     /// Number of cols this `DataSource` got.
     fn ncols(&self) -> usize;
+
+    /// Bytes the partition sets aside to buffer rows while it is parsed, without the values
+    /// the rows point to on the heap. 0 for a source that does not buffer.
+    fn read_buffer_bytes(&self) -> usize {
+        0
+    }
+
+    /// Drop the data `prepare` loaded, keeping `nrows`, and return whether there was any, in
+    /// which case the partition is prepared again before it is read. Sequential runs release
+    /// every partition once its rows are counted, so that only the partition being written
+    /// holds its data. Sources that stream their data in `parser` have nothing to release.
+    fn release(&mut self) -> bool {
+        false
+    }
+
+    /// The schema of the batches `next_batch` hands out, for a source that holds its data as
+    /// Arrow record batches. Given a destination that `accepts_batches` of this schema, the
+    /// dispatcher moves the partition over batch by batch instead of cell by cell.
+    fn batch_schema(&self) -> Option<SchemaRef> {
+        None
+    }
+
+    /// The next record batch of the partition, None once all of it was handed out. Only called
+    /// if `batch_schema` is Some, and never together with `parser` on the same partition.
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        Ok(None)
+    }
 }
 
 pub trait PartitionParser<'a> {
@@ -66,3 +135,43 @@ pub trait PartitionParser<'a> {
 pub trait Produce<'r, T> {
     fn produce(&'r mut self) -> Result<T>;
 }
+
+/// What a source does with the warnings and notices the database sends along with a result,
+/// like a Postgres `RAISE WARNING`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NoticePolicy {
+    /// Drop them.
+    Ignore,
+    /// Log them, warnings with `warn!` and the others with `info!`.
+    Log,
+    /// Gather them into `RunMetrics::notices`.
+    Collect,
+}
+
+/// A warning or notice of the database.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Notice {
+    /// As the database names it, e.g. `WARNING` or `NOTICE`.
+    pub severity: String,
+    /// The SQLSTATE code.
+    pub code: String,
+    pub message: String,
+}
+
+/// The notices a source collected, shared by its connections.
+#[derive(Clone, Debug, Default)]
+pub struct NoticeLog(Arc<Mutex<Vec<Notice>>>);
+
+impl NoticeLog {
+    pub fn push(&self, notice: Notice) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(notice);
+    }
+
+    /// The notices so far, in the order they came, leaving the log empty.
+    pub fn take(&self) -> Vec<Notice> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
@@ -1,63 +1,420 @@
 mod typesystem;
 
 use crate::data_order::DataOrder;
+use crate::decimal::{DecimalFormat, DecimalRounding};
+use crate::dummy_typesystem::{Circle, Point, Record, RecordFields, Snapshot};
 use crate::errors::{ConnectorAgentError, Result};
-use crate::sources::{PartitionParser, Produce, Source, SourcePartition};
-use crate::sql::{count_query, get_limit, limit1_query};
+use crate::rate_limit::RateLimiter;
+use crate::sources::{
+    Notice, NoticeLog, NoticePolicy, PartitionParser, Produce, Source, SourcePartition,
+};
+use crate::sql::{
+    computed_columns_query, count_query, get_limit, limit1_query, limit_query, select_columns_query,
+};
 use anyhow::anyhow;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use csv::{ReaderBuilder, StringRecord, StringRecordsIntoIter};
 use fehler::throw;
 use hex::decode;
-use log::debug;
+use log::{debug, info, warn};
 use postgres::{
     binary_copy::{BinaryCopyOutIter, BinaryCopyOutRow},
+    error::SqlState,
     fallible_iterator::FallibleIterator,
-    CopyOutReader,
+    types::{FromSql, Kind, Type},
+    Column, CopyOutReader, Row, Statement, Transaction,
 };
 use r2d2::{Pool, PooledConnection};
 use r2d2_postgres::{postgres::NoTls, PostgresConnectionManager};
 use rust_decimal::Decimal;
 use serde_json::{from_str, Value};
 use sqlparser::dialect::PostgreSqlDialect;
+use std::error::Error;
 use std::io::BufRead;
 use std::marker::PhantomData;
-pub use typesystem::PostgresTypeSystem;
+use std::sync::Arc;
+use std::time::Duration;
+use typesystem::{record_schema, RecordValues};
+pub use typesystem::{
+    JsonPathStr, Multirange, PostgresTypeSystem, Range, RangeBound, RegOid, Tid, Xid8,
+};
 use uuid::Uuid;
 
 type PgManager = PostgresConnectionManager<NoTls>;
 type PgConn = PooledConnection<PgManager>;
 
+// the cursor a partition reads through under flow control, local to its transaction
+const FLOW_CONTROL_CURSOR: &str = "connectorx_flow_control";
+
 pub enum Binary {}
 pub enum CSV {}
 
+/// How `fetch_metadata` reads a column of one of the reg* types, like `regclass` or `regproc`,
+/// which stand for a row of a catalog table.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RegTypePolicy {
+    /// Read the OID of the row. Only the binary protocol supports it.
+    Oid,
+    /// Read the name Postgres resolves the OID to, like `pg_class` for a `regclass`.
+    Name,
+}
+
+/// What `fetch_metadata` does with a column of a pseudo-type like `record` or `cstring`,
+/// which has no representation to read it into.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PseudoTypePolicy {
+    /// Fail with `ConnectorAgentError::UnmappablePseudoType`.
+    Error,
+    /// Drop the column from the output with a warning.
+    Skip,
+}
+
+/// What `fetch_metadata` does with a materialized view that was refreshed longer ago than
+/// allowed, see `PostgresSource::matview_freshness`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StaleMatviewPolicy {
+    /// Read it as it is with a warning.
+    Warn,
+    /// `REFRESH MATERIALIZED VIEW` it before reading.
+    Refresh,
+    /// `REFRESH MATERIALIZED VIEW CONCURRENTLY` it before reading, which does not lock out
+    /// other readers but needs a unique index on the view.
+    RefreshConcurrently,
+}
+
+struct MatviewFreshness {
+    view: String,
+    refreshed_at: String,
+    max_staleness: Duration,
+    policy: StaleMatviewPolicy,
+}
+
 pub struct PostgresSource<P> {
-    pool: Pool<PgManager>,
+    config: postgres::Config,
+    nconn: usize,
+    // connected on first use, with the configuration the options left
+    pool: Option<Pool<PgManager>>,
     queries: Vec<String>,
     names: Vec<String>,
     schema: Vec<PostgresTypeSystem>,
+    column_types: Vec<Type>,
+    computed_columns: Vec<(String, String)>,
     buf_size: usize,
+    numeric_scale: Option<(u32, DecimalRounding)>,
+    numeric_format: DecimalFormat,
+    pseudo_types: PseudoTypePolicy,
+    reg_types: RegTypePolicy,
+    rate_limit: Option<Arc<RateLimiter>>,
+    flow_control: Option<usize>,
+    refcursor: bool,
+    cursor_rows: Option<Vec<Row>>,
+    cursor_columns: Option<Vec<usize>>,
+    diagnostics: bool,
+    matview_freshness: Option<MatviewFreshness>,
+    notices: Option<NoticeLog>,
     _protocol: PhantomData<P>,
 }
 
 impl<P> PostgresSource<P> {
+    /// Connect with `nconn` connections by a `postgresql://` URL or a libpq `key=value`
+    /// string. A host that is an absolute path is the directory of the server's Unix socket,
+    /// e.g. `host=/var/run/postgresql dbname=tpch`, or `%2Fvar%2Frun%2Fpostgresql` in a URL,
+    /// and the connections go over the socket instead of TCP. Such connections are often
+    /// authenticated as the OS user (`peer`), in which case no password is needed.
     pub fn new(conn: &str, nconn: usize) -> Result<Self> {
-        let manager = PostgresConnectionManager::new(conn.parse()?, NoTls);
-        let pool = Pool::builder().max_size(nconn as u32).build(manager)?;
+        let config: postgres::Config = conn.parse()?;
 
         Ok(Self {
-            pool,
+            config,
+            nconn,
+            pool: None,
             queries: vec![],
             names: vec![],
             schema: vec![],
+            column_types: vec![],
+            computed_columns: vec![],
             buf_size: 32,
+            numeric_scale: None,
+            numeric_format: DecimalFormat::default(),
+            pseudo_types: PseudoTypePolicy::Error,
+            reg_types: RegTypePolicy::Oid,
+            rate_limit: None,
+            flow_control: None,
+            refcursor: false,
+            cursor_rows: None,
+            cursor_columns: None,
+            diagnostics: false,
+            matview_freshness: None,
+            notices: None,
             _protocol: PhantomData,
         })
     }
 
-    pub fn buf_size(&mut self, buf_size: usize) {
+    /// Set how many rows each partition takes off its COPY stream at a time before parsing
+    /// them (default 32). The server streams a COPY without waiting for the client, so this
+    /// does not change the round-trips, only how many undecoded rows every partition holds.
+    /// To fetch more rows per round-trip, read through a cursor with `flow_control`.
+    pub fn buf_size(&mut self, buf_size: usize) -> Result<()> {
+        if buf_size == 0 {
+            throw!(anyhow!("buf_size must be positive"));
+        }
         self.buf_size = buf_size;
+        Ok(())
+    }
+
+    /// Have the partition connections send TCP keepalive probes after `interval` of
+    /// silence, so that a server or proxy with an idle timeout does not drop a connection
+    /// whose partition is stalled behind a slow destination. The probes live below the
+    /// protocol, so they never interleave with an ongoing COPY.
+    pub fn keepalive(&mut self, interval: Duration) {
+        self.config.keepalives(true).keepalives_idle(interval);
+        self.pool = None;
+    }
+
+    /// Handle the `NOTICE`s and `WARNING`s the server sends, e.g. from `RAISE WARNING` in a
+    /// function the queries call, by `policy`. Without it they are logged with `info!`, as
+    /// the client does by default.
+    pub fn notice_policy(&mut self, policy: NoticePolicy) {
+        self.notices = None;
+        match policy {
+            NoticePolicy::Ignore => {
+                self.config.notice_callback(|_| {});
+            }
+            NoticePolicy::Log => {
+                self.config
+                    .notice_callback(|notice| match notice.severity() {
+                        "WARNING" => warn!("{}: {}", notice.severity(), notice.message()),
+                        _ => info!("{}: {}", notice.severity(), notice.message()),
+                    });
+            }
+            NoticePolicy::Collect => {
+                let notices = NoticeLog::default();
+                let log = notices.clone();
+                self.config.notice_callback(move |notice| {
+                    log.push(Notice {
+                        severity: notice.severity().to_string(),
+                        code: notice.code().code().to_string(),
+                        message: notice.message().to_string(),
+                    })
+                });
+                self.notices = Some(notices);
+            }
+        }
+        self.pool = None;
+    }
+
+    /// Cap the combined read throughput of all the partitions at about `bytes_per_sec` bytes
+    /// of COPY data per second. Unlimited by default.
+    pub fn rate_limit(&mut self, bytes_per_sec: u64) -> Result<()> {
+        self.rate_limit = Some(Arc::new(RateLimiter::new(bytes_per_sec)?));
+        Ok(())
+    }
+
+    /// Fail a cell that does not decode with `ConnectorAgentError::CannotDecode`, which tells
+    /// its column, row and the bytes it came as over the wire, its text under `CSV`. Off by
+    /// default, since the bytes may be sensitive.
+    pub fn diagnostics(&mut self, enabled: bool) {
+        self.diagnostics = enabled;
+    }
+
+    /// Reduce every `numeric` value with more than `scale` fractional digits to `scale`
+    /// digits, rounding with `rounding` (`DecimalRounding::default()` is banker's rounding).
+    /// By default values keep the scale they have in the database.
+    pub fn numeric_scale(&mut self, scale: u32, rounding: DecimalRounding) {
+        self.numeric_scale = Some((scale, rounding));
+    }
+
+    /// Set what happens to columns of a pseudo-type (default `PseudoTypePolicy::Error`).
+    pub fn pseudo_types(&mut self, policy: PseudoTypePolicy) {
+        self.pseudo_types = policy;
+    }
+
+    /// Set how columns of the reg* types are read (default `RegTypePolicy::Oid`).
+    pub fn reg_types(&mut self, policy: RegTypePolicy) {
+        self.reg_types = policy;
+    }
+
+    /// Append a column computed by the SQL expression `expr` over the query output, e.g.
+    /// `EXTRACT(year FROM ts)`. Its type is picked up by `fetch_metadata` like any other column.
+    pub fn add_computed_column(&mut self, name: &str, expr: &str) {
+        self.computed_columns
+            .push((name.to_string(), expr.to_string()));
+    }
+
+    /// Before reading, check that the materialized view `view` was refreshed at most
+    /// `max_staleness` ago, and apply `policy` if not. Postgres does not record when a view
+    /// was refreshed, so `refreshed_at` is a query returning that time as a `timestamptz`,
+    /// e.g. from a table the job refreshing the view writes to. A view without a refresh
+    /// time counts as stale. Refreshing a view takes being its owner; without that the view
+    /// is read as it is with a warning. Refreshes by the source are not recorded anywhere.
+    /// `view` is a name as in SQL, optionally schema-qualified, and a name that is not of a
+    /// materialized view fails the refresh.
+    pub fn matview_freshness(
+        &mut self,
+        view: &str,
+        refreshed_at: &str,
+        max_staleness: Duration,
+        policy: StaleMatviewPolicy,
+    ) {
+        self.matview_freshness = Some(MatviewFreshness {
+            view: view.to_string(),
+            refreshed_at: refreshed_at.to_string(),
+            max_staleness,
+            policy,
+        });
+    }
+
+    // the pool, connected once all the options have been set
+    fn pool(&mut self) -> Result<&Pool<PgManager>> {
+        if self.pool.is_none() {
+            self.pool = Some(build_pool(&self.config, self.nconn)?);
+        }
+        Ok(self.pool.as_ref().unwrap())
+    }
+
+    fn check_matview_freshness(&self, conn: &mut PgConn) -> Result<()> {
+        let freshness = match &self.matview_freshness {
+            Some(freshness) => freshness,
+            None => return Ok(()),
+        };
+        let refreshed_at = match conn.query_opt(&*freshness.refreshed_at, &[])? {
+            Some(row) => row.try_get::<_, Option<DateTime<Utc>>>(0)?,
+            None => None,
+        };
+        let stale = match refreshed_at {
+            // a refresh time in the future is not stale either
+            Some(at) => {
+                matches!((Utc::now() - at).to_std(), Ok(age) if age > freshness.max_staleness)
+            }
+            None => true,
+        };
+        if !stale {
+            return Ok(());
+        }
+
+        let refresh = match freshness.policy {
+            StaleMatviewPolicy::Warn => {
+                warn!(
+                    "materialized view {} was last refreshed at {:?}, more than {:?} ago",
+                    freshness.view, refreshed_at, freshness.max_staleness
+                );
+                return Ok(());
+            }
+            StaleMatviewPolicy::Refresh => "REFRESH MATERIALIZED VIEW",
+            StaleMatviewPolicy::RefreshConcurrently => "REFRESH MATERIALIZED VIEW CONCURRENTLY",
+        };
+        // the view as the server names it, quoted, and only if it is a materialized view
+        let view: String = match conn.query_opt(
+            "SELECT format('%I.%I', schemaname, matviewname) FROM pg_matviews \
+             WHERE format('%I.%I', schemaname, matviewname)::regclass = to_regclass($1)",
+            &[&freshness.view],
+        )? {
+            Some(row) => row.try_get(0)?,
+            None => throw!(anyhow!("{} is not a materialized view", freshness.view)),
+        };
+        debug!(
+            "Refreshing materialized view {}, last refreshed at {:?}",
+            view, refreshed_at
+        );
+        match conn.batch_execute(&format!("{} {}", refresh, view)) {
+            Err(e) if e.code() == Some(&SqlState::INSUFFICIENT_PRIVILEGE) => {
+                warn!(
+                    "cannot refresh materialized view {}, reading it as it is: {}",
+                    freshness.view, e
+                );
+                Ok(())
+            }
+            r => Ok(r?),
+        }
+    }
+}
+
+impl PostgresSource<Binary> {
+    /// Read each partition through a cursor, `window_rows` rows at a time, fetching the next
+    /// window only once the destination has written the current one. Unlike a COPY, which
+    /// the server sends as fast as the connection takes it, nothing more than a window per
+    /// partition is ever on its way or buffered. Replaces `buf_size`.
+    pub fn flow_control(&mut self, window_rows: usize) -> Result<()> {
+        if window_rows == 0 {
+            throw!(anyhow!("window_rows must be positive"));
+        }
+        self.flow_control = Some(window_rows);
+        Ok(())
+    }
+
+    /// Treat each query as a call returning a single `refcursor`, like `SELECT f()` for a
+    /// function that opens a cursor and returns it, and read the rows of that cursor instead.
+    /// The call and the FETCH run in one transaction, and a partition holds all of its rows
+    /// before they are parsed, so `flow_control` does not apply. A query returning more
+    /// than one cursor fails; split such a call into one query per cursor.
+    ///
+    /// Each call runs once: the rows of the first one tell the columns and are then read by
+    /// the first partition. The columns of a pseudo-type follow `pseudo_types`, but as the
+    /// rows of a cursor cannot be rewritten, `RegTypePolicy::Name` and `computed_column`
+    /// fail.
+    pub fn follow_refcursor(&mut self) {
+        self.refcursor = true;
+    }
+}
+
+impl PostgresSource<CSV> {
+    /// Parse the `numeric` text of the CSV protocol in `format` instead of the default `.`
+    /// before the fraction and no grouping. Postgres always writes numerics that way, but
+    /// servers and proxies that speak its protocol may format them by locale, e.g.
+    /// `1.234.567,89`.
+    pub fn numeric_format(&mut self, format: DecimalFormat) {
+        self.numeric_format = format;
+    }
+}
+
+fn build_pool(config: &postgres::Config, nconn: usize) -> Result<Pool<PgManager>> {
+    let manager = PostgresConnectionManager::new(config.clone(), NoTls);
+    Ok(Pool::builder().max_size(nconn as u32).build(manager)?)
+}
+
+// The columns of a query as the source reads them.
+struct ResolvedColumns {
+    names: Vec<String>,
+    schema: Vec<PostgresTypeSystem>,
+    types: Vec<Type>,
+    // the index of each of them among all the columns of the query
+    kept: Vec<usize>,
+}
+
+/// Map the `columns` a query returns, leaving out or failing on those of a pseudo-type as
+/// `policy` says.
+fn resolve_columns(columns: &[Column], policy: PseudoTypePolicy) -> Result<ResolvedColumns> {
+    let mut resolved = ResolvedColumns {
+        names: vec![],
+        schema: vec![],
+        types: vec![],
+        kept: vec![],
+    };
+    for (i, col) in columns.iter().enumerate() {
+        if let Kind::Pseudo = col.type_().kind() {
+            match policy {
+                PseudoTypePolicy::Error => {
+                    throw!(ConnectorAgentError::UnmappablePseudoType(
+                        col.name().to_string(),
+                        col.type_().name().to_string()
+                    ))
+                }
+                PseudoTypePolicy::Skip => {
+                    warn!(
+                        "skipping column {} of pseudo-type {}",
+                        col.name(),
+                        col.type_().name()
+                    );
+                    continue;
+                }
+            }
+        }
+        resolved.names.push(col.name().to_string());
+        resolved.schema.push(PostgresTypeSystem::from(col.type_()));
+        resolved.types.push(col.type_().clone());
+        resolved.kept.push(i);
     }
+    Ok(resolved)
 }
 
 impl<P> Source for PostgresSource<P>
@@ -80,30 +437,83 @@ where
         self.queries = queries.iter().map(|q| q.as_ref().to_string()).collect();
     }
 
+    fn limit_query(&self, query: &str, limit: usize) -> Result<String> {
+        limit_query(query, limit, &PostgreSqlDialect {})
+    }
+
+    /// Under flow control the rows of a cursor FETCH, one round-trip each, and otherwise
+    /// `buf_size`.
+    fn set_read_buffer(&mut self, rows: usize) -> Result<()> {
+        self.buf_size(rows)?;
+        if self.flow_control.is_some() {
+            self.flow_control = Some(rows);
+        }
+        Ok(())
+    }
+
+    fn notice_log(&self) -> Option<NoticeLog> {
+        self.notices.clone()
+    }
+
     fn fetch_metadata(&mut self) -> Result<()> {
         assert!(!self.queries.is_empty());
 
-        let mut conn = self.pool.get()?;
+        if !self.computed_columns.is_empty() {
+            if self.refcursor {
+                throw!(anyhow!(
+                    "computed columns cannot be added to the rows of a refcursor"
+                ));
+            }
+            let columns = std::mem::take(&mut self.computed_columns);
+            self.queries = self
+                .queries
+                .iter()
+                .map(|q| computed_columns_query(q, &columns, &PostgreSqlDialect {}))
+                .collect::<Result<Vec<_>>>()?;
+        }
+
+        let mut conn = self.pool()?.get()?;
+        self.check_matview_freshness(&mut conn)?;
+        if self.refcursor {
+            // the FETCH can only be described while the cursor is open, so the rows are read
+            // here once and handed to the first partition
+            let mut tx = conn.transaction()?;
+            let (stmt, rows) = fetch_cursor(&mut tx, &self.queries[0])?;
+            tx.commit()?;
+            let columns = resolve_columns(stmt.columns(), self.pseudo_types)?;
+            if self.reg_types == RegTypePolicy::Name
+                && columns
+                    .schema
+                    .iter()
+                    .any(|dt| matches!(dt, PostgresTypeSystem::RegOid(_)))
+            {
+                throw!(anyhow!(
+                    "the reg* columns of a refcursor can only be read as OIDs"
+                ));
+            }
+            if columns.kept.len() < stmt.columns().len() {
+                self.cursor_columns = Some(columns.kept);
+            }
+            self.names = columns.names;
+            self.schema = columns.schema;
+            self.column_types = columns.types;
+            self.cursor_rows = Some(rows);
+            return Ok(());
+        }
+
         let mut success = false;
         let mut zero_tuple = true;
         let mut error = None;
+        let mut skipped = false;
         for query in &self.queries {
             // assuming all the partition queries yield same schema
             match conn.query_opt(&limit1_query(query, &PostgreSqlDialect {})?[..], &[]) {
                 Ok(Some(row)) => {
-                    let (names, types) = row
-                        .columns()
-                        .iter()
-                        .map(|col| {
-                            (
-                                col.name().to_string(),
-                                PostgresTypeSystem::from(col.type_()),
-                            )
-                        })
-                        .unzip();
-
-                    self.names = names;
-                    self.schema = types;
+                    let columns = resolve_columns(row.columns(), self.pseudo_types)?;
+                    skipped = columns.kept.len() < row.columns().len();
+                    self.names = columns.names;
+                    self.schema = columns.schema;
+                    self.column_types = columns.types;
 
                     success = true;
                     zero_tuple = false;
@@ -118,6 +528,26 @@ where
             }
         }
 
+        // the server resolves the names when casting them to text
+        let mut as_text = vec![false; self.schema.len()];
+        if self.reg_types == RegTypePolicy::Name {
+            for (dt, as_text) in self.schema.iter_mut().zip(&mut as_text) {
+                if let PostgresTypeSystem::RegOid(nullable) = *dt {
+                    *dt = PostgresTypeSystem::Text(nullable);
+                    *as_text = true;
+                }
+            }
+        }
+
+        if skipped || as_text.contains(&true) {
+            let names = &self.names;
+            self.queries = self
+                .queries
+                .iter()
+                .map(|q| select_columns_query(q, names, &as_text, &PostgreSqlDialect {}))
+                .collect::<Result<Vec<_>>>()?;
+        }
+
         if !success {
             if zero_tuple {
                 // try to use COPY command get the column headers
@@ -150,17 +580,42 @@ where
         self.schema.clone()
     }
 
-    fn partition(self) -> Result<Vec<Self::Partition>> {
+    fn partition(mut self) -> Result<Vec<Self::Partition>> {
+        let pool = self.pool()?.clone();
+        let record_fields = self
+            .column_types
+            .iter()
+            .map(record_schema)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| anyhow!(e))?;
         let mut ret = vec![];
+        let mut cursor_rows = self.cursor_rows;
         for query in self.queries {
-            let conn = self.pool.get()?;
+            let conn = pool.get()?;
 
-            ret.push(PostgresSourcePartition::<P>::new(
+            let partition = PostgresSourcePartition::<P>::new(
                 conn,
                 &query,
                 &self.schema,
                 self.buf_size,
-            ));
+                self.numeric_scale,
+                self.rate_limit.clone(),
+            );
+            ret.push(
+                partition
+                    .flow_control(self.flow_control)
+                    .refcursor(self.refcursor)
+                    .fetched(cursor_rows.take())
+                    .cursor_columns(self.cursor_columns.as_deref())
+                    .column_types(&self.column_types)
+                    .record_fields(&record_fields)
+                    .numeric_format(self.numeric_format)
+                    .diagnostics(if self.diagnostics {
+                        Some(&self.names)
+                    } else {
+                        None
+                    }),
+            );
         }
         Ok(ret)
     }
@@ -170,24 +625,114 @@ pub struct PostgresSourcePartition<P> {
     conn: PgConn,
     query: String,
     schema: Vec<PostgresTypeSystem>,
+    column_types: Vec<Type>,
+    record_fields: Vec<Option<RecordFields>>,
     nrows: usize,
     ncols: usize,
     buf_size: usize,
+    numeric_scale: Option<(u32, DecimalRounding)>,
+    numeric_format: DecimalFormat,
+    rate_limit: Option<Arc<RateLimiter>>,
+    flow_control: Option<usize>,
+    refcursor: bool,
+    fetched: Option<Vec<Row>>,
+    cursor_columns: Option<Vec<usize>>,
+    names: Option<Vec<String>>,
     _protocol: PhantomData<P>,
 }
 
 impl<P> PostgresSourcePartition<P> {
-    pub fn new(conn: PgConn, query: &str, schema: &[PostgresTypeSystem], buf_size: usize) -> Self {
+    pub fn new(
+        conn: PgConn,
+        query: &str,
+        schema: &[PostgresTypeSystem],
+        buf_size: usize,
+        numeric_scale: Option<(u32, DecimalRounding)>,
+        rate_limit: Option<Arc<RateLimiter>>,
+    ) -> Self {
         Self {
             conn,
             query: query.to_string(),
             schema: schema.to_vec(),
+            column_types: vec![],
+            record_fields: vec![],
             nrows: 0,
             ncols: schema.len(),
             buf_size,
+            numeric_scale,
+            numeric_format: DecimalFormat::default(),
+            rate_limit,
+            flow_control: None,
+            refcursor: false,
+            fetched: None,
+            cursor_columns: None,
+            names: None,
             _protocol: PhantomData,
         }
     }
+
+    /// Read through a cursor `window_rows` at a time, see `PostgresSource::flow_control`.
+    pub fn flow_control(mut self, window_rows: Option<usize>) -> Self {
+        self.flow_control = window_rows;
+        self
+    }
+
+    /// Read the rows of the cursor the query returns, see `PostgresSource::follow_refcursor`.
+    pub fn refcursor(mut self, refcursor: bool) -> Self {
+        self.refcursor = refcursor;
+        self
+    }
+
+    /// The rows of the cursor, if they were already fetched while reading the metadata.
+    pub fn fetched(mut self, rows: Option<Vec<Row>>) -> Self {
+        self.fetched = rows;
+        self
+    }
+
+    /// The index of each column among those of the cursor, if some of them are skipped.
+    pub fn cursor_columns(mut self, columns: Option<&[usize]>) -> Self {
+        self.cursor_columns = columns.map(|columns| columns.to_vec());
+        self
+    }
+
+    /// The column types as the server describes them. A COPY decodes the composite columns by
+    /// these, as only they know the fields of a composite.
+    pub fn column_types(mut self, column_types: &[Type]) -> Self {
+        self.column_types = column_types.to_vec();
+        self
+    }
+
+    /// The fields of each composite column, shared by all the records read from it.
+    pub fn record_fields(mut self, fields: &[Option<RecordFields>]) -> Self {
+        self.record_fields = fields.to_vec();
+        self
+    }
+
+    /// The format of the numerics the CSV protocol reads as text, see
+    /// `PostgresSource::numeric_format`.
+    pub fn numeric_format(mut self, format: DecimalFormat) -> Self {
+        self.numeric_format = format;
+        self
+    }
+
+    /// Report the cells that do not decode under the column `names`, if given, see
+    /// `PostgresSource::diagnostics`.
+    pub fn diagnostics(mut self, names: Option<&[String]>) -> Self {
+        self.names = names.map(|names| names.to_vec());
+        self
+    }
+
+    fn copy_types(&self) -> Vec<Type> {
+        self.schema
+            .iter()
+            .enumerate()
+            .map(|(i, &dt)| match (dt, self.column_types.get(i)) {
+                (PostgresTypeSystem::Composite(_), Some(ty))
+                | (PostgresTypeSystem::Multirange(_), Some(ty)) => ty.clone(),
+                _ => dt.into(),
+            })
+            .collect()
+    }
 }
 
 impl SourcePartition for PostgresSourcePartition<Binary> {
@@ -195,6 +740,17 @@ impl SourcePartition for PostgresSourcePartition<Binary> {
     type Parser<'a> = PostgresBinarySourcePartitionParser<'a>;
 
     fn prepare(&mut self) -> Result<()> {
+        if self.refcursor {
+            if self.fetched.is_none() {
+                let mut tx = self.conn.transaction()?;
+                let (_, rows) = fetch_cursor(&mut tx, &self.query)?;
+                tx.commit()?;
+                self.fetched = Some(rows);
+            }
+            self.nrows = self.fetched.as_ref().map_or(0, |rows| rows.len());
+            return Ok(());
+        }
+
         let dialect = PostgreSqlDialect {};
         self.nrows = match get_limit(&self.query, &dialect)? {
             None => {
@@ -209,16 +765,50 @@ impl SourcePartition for PostgresSourcePartition<Binary> {
     }
 
     fn parser(&mut self) -> Result<Self::Parser<'_>> {
+        if self.refcursor {
+            return Ok(PostgresBinarySourcePartitionParser::from_rows(
+                self.fetched.take().unwrap_or_default(),
+                &self.schema,
+                self.buf_size,
+                self.numeric_scale,
+                self.rate_limit.clone(),
+            )
+            .columns(self.cursor_columns.clone())
+            .record_fields(self.record_fields.clone())
+            .diagnostics(self.names.clone()));
+        }
+
+        if let Some(window) = self.flow_control {
+            let mut tx = self.conn.transaction()?;
+            tx.batch_execute(&format!(
+                "DECLARE {} NO SCROLL CURSOR FOR {}",
+                FLOW_CONTROL_CURSOR, self.query
+            ))?;
+            return Ok(PostgresBinarySourcePartitionParser::from_cursor(
+                tx,
+                window,
+                &self.schema,
+                self.numeric_scale,
+                self.rate_limit.clone(),
+            )
+            .record_fields(self.record_fields.clone())
+            .diagnostics(self.names.clone()));
+        }
+
         let query = format!("COPY ({}) TO STDOUT WITH BINARY", self.query);
+        let pg_schema = self.copy_types();
         let reader = self.conn.copy_out(&*query)?; // unless reading the data, it seems like issue the query is fast
-        let pg_schema: Vec<_> = self.schema.iter().map(|&dt| dt.into()).collect();
         let iter = BinaryCopyOutIter::new(reader, &pg_schema);
 
         Ok(PostgresBinarySourcePartitionParser::new(
             iter,
             &self.schema,
             self.buf_size,
-        ))
+            self.numeric_scale,
+            self.rate_limit.clone(),
+        )
+        .record_fields(self.record_fields.clone())
+        .diagnostics(self.names.clone()))
     }
 
     fn nrows(&self) -> usize {
@@ -228,6 +818,15 @@ impl SourcePartition for PostgresSourcePartition<Binary> {
     fn ncols(&self) -> usize {
         self.ncols
     }
+
+    fn read_buffer_bytes(&self) -> usize {
+        // the rows of a refcursor are all held from prepare on
+        let fetched = self
+            .fetched
+            .as_ref()
+            .map_or(0, |rows| rows.capacity() * std::mem::size_of::<Row>());
+        fetched + self.flow_control.unwrap_or(self.buf_size) * std::mem::size_of::<BinaryRow>()
+    }
 }
 
 impl SourcePartition for PostgresSourcePartition<CSV> {
@@ -254,7 +853,11 @@ impl SourcePartition for PostgresSourcePartition<CSV> {
             iter,
             &self.schema,
             self.buf_size,
-        ))
+            self.numeric_scale,
+            self.rate_limit.clone(),
+        )
+        .numeric_format(self.numeric_format)
+        .diagnostics(self.names.clone()))
     }
 
     fn nrows(&self) -> usize {
@@ -264,15 +867,46 @@ impl SourcePartition for PostgresSourcePartition<CSV> {
     fn ncols(&self) -> usize {
         self.ncols
     }
+
+    fn read_buffer_bytes(&self) -> usize {
+        self.buf_size * std::mem::size_of::<StringRecord>()
+    }
+}
+
+enum BinaryRows<'a> {
+    Copy(BinaryCopyOutIter<'a>),
+    Cursor(Transaction<'a>),
+    Fetched(std::vec::IntoIter<Row>),
+}
+
+enum BinaryRow {
+    Copy(BinaryCopyOutRow),
+    Fetched(Row),
+}
+
+impl BinaryRow {
+    fn try_get<'a, T: FromSql<'a>>(&'a self, idx: usize) -> Result<T> {
+        Ok(match self {
+            BinaryRow::Copy(row) => row.try_get(idx)?,
+            BinaryRow::Fetched(row) => row.try_get(idx)?,
+        })
+    }
 }
 
 pub struct PostgresBinarySourcePartitionParser<'a> {
-    iter: BinaryCopyOutIter<'a>,
+    rows: BinaryRows<'a>,
     buf_size: usize,
-    rowbuf: Vec<BinaryCopyOutRow>,
+    rowbuf: Vec<BinaryRow>,
     ncols: usize,
     current_col: usize,
     current_row: usize,
+    numeric_scale: Option<(u32, DecimalRounding)>,
+    rate_limit: Option<Arc<RateLimiter>>,
+    peak_buffered_rows: usize,
+    rows_done: usize,
+    columns: Option<Vec<usize>>,
+    record_fields: Vec<Option<RecordFields>>,
+    names: Option<Vec<String>>,
 }
 
 impl<'a> PostgresBinarySourcePartitionParser<'a> {
@@ -280,30 +914,167 @@ impl<'a> PostgresBinarySourcePartitionParser<'a> {
         iter: BinaryCopyOutIter<'a>,
         schema: &[PostgresTypeSystem],
         buf_size: usize,
+        numeric_scale: Option<(u32, DecimalRounding)>,
+        rate_limit: Option<Arc<RateLimiter>>,
     ) -> Self {
         Self {
-            iter,
+            rows: BinaryRows::Copy(iter),
+            buf_size,
+            rowbuf: Vec::with_capacity(buf_size),
+            ncols: schema.len(),
+            current_row: 0,
+            current_col: 0,
+            numeric_scale,
+            rate_limit,
+            peak_buffered_rows: 0,
+            rows_done: 0,
+            columns: None,
+            record_fields: vec![],
+            names: None,
+        }
+    }
+
+    /// Parse the rows of the cursor `FLOW_CONTROL_CURSOR` declared in `tx`, fetching
+    /// `window` rows whenever the previous ones are all parsed.
+    pub fn from_cursor(
+        tx: Transaction<'a>,
+        window: usize,
+        schema: &[PostgresTypeSystem],
+        numeric_scale: Option<(u32, DecimalRounding)>,
+        rate_limit: Option<Arc<RateLimiter>>,
+    ) -> Self {
+        Self {
+            rows: BinaryRows::Cursor(tx),
+            buf_size: window,
+            rowbuf: Vec::with_capacity(window),
+            ncols: schema.len(),
+            current_row: 0,
+            current_col: 0,
+            numeric_scale,
+            rate_limit,
+            peak_buffered_rows: 0,
+            rows_done: 0,
+            columns: None,
+            record_fields: vec![],
+            names: None,
+        }
+    }
+
+    /// Parse `rows` that were already fetched.
+    pub fn from_rows(
+        rows: Vec<Row>,
+        schema: &[PostgresTypeSystem],
+        buf_size: usize,
+        numeric_scale: Option<(u32, DecimalRounding)>,
+        rate_limit: Option<Arc<RateLimiter>>,
+    ) -> Self {
+        Self {
+            rows: BinaryRows::Fetched(rows.into_iter()),
             buf_size,
             rowbuf: Vec::with_capacity(buf_size),
             ncols: schema.len(),
             current_row: 0,
             current_col: 0,
+            numeric_scale,
+            rate_limit,
+            peak_buffered_rows: 0,
+            rows_done: 0,
+            columns: None,
+            record_fields: vec![],
+            names: None,
+        }
+    }
+
+    /// Read the `columns` of each row, by their index in it, instead of all of them in order.
+    pub fn columns(mut self, columns: Option<Vec<usize>>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// The fields of each composite column, which its records are read with.
+    pub fn record_fields(mut self, fields: Vec<Option<RecordFields>>) -> Self {
+        self.record_fields = fields;
+        self
+    }
+
+    /// Report the cells that do not decode under the column `names`, if given.
+    pub fn diagnostics(mut self, names: Option<Vec<String>>) -> Self {
+        self.names = names;
+        self
+    }
+
+    // the fields of the composite column `cidx`
+    fn fields_of(&self, cidx: usize) -> Result<RecordFields> {
+        match self.record_fields.get(cidx) {
+            Some(Some(fields)) => Ok(fields.clone()),
+            _ => throw!(anyhow!(
+                "the fields of composite column {} are unknown",
+                cidx
+            )),
+        }
+    }
+
+    /// The most rows that have been held at once, read but not all parsed yet.
+    pub fn peak_buffered_rows(&self) -> usize {
+        self.peak_buffered_rows
+    }
+
+    fn get<'r, T: FromSql<'r>>(&'r self, ridx: usize, cidx: usize) -> Result<T> {
+        let row = &self.rowbuf[ridx];
+        let idx = self.columns.as_ref().map_or(cidx, |columns| columns[cidx]);
+        match (row.try_get(idx), &self.names) {
+            (Err(e), Some(names)) => {
+                let raw = row.try_get::<RawBytes>(idx).ok().and_then(|raw| raw.0);
+                throw!(ConnectorAgentError::cannot_decode::<T>(
+                    &names[cidx],
+                    self.rows_done + ridx,
+                    raw,
+                    e
+                ))
+            }
+            (val, _) => val,
         }
     }
 
     fn next_loc(&mut self) -> Result<(usize, usize)> {
         if self.current_row >= self.rowbuf.len() {
             if !self.rowbuf.is_empty() {
+                self.rows_done += self.rowbuf.len();
                 self.rowbuf.drain(..);
             }
 
-            for _ in 0..self.buf_size {
-                match self.iter.next()? {
-                    Some(row) => {
-                        self.rowbuf.push(row);
+            match &mut self.rows {
+                BinaryRows::Copy(iter) => {
+                    for _ in 0..self.buf_size {
+                        match iter.next()? {
+                            Some(row) => {
+                                self.rowbuf.push(BinaryRow::Copy(row));
+                            }
+                            None => break,
+                        }
+                    }
+                }
+                BinaryRows::Cursor(tx) => {
+                    let fetch = format!("FETCH {} FROM {}", self.buf_size, FLOW_CONTROL_CURSOR);
+                    self.rowbuf
+                        .extend(tx.query(&*fetch, &[])?.into_iter().map(BinaryRow::Fetched));
+                }
+                BinaryRows::Fetched(rows) => {
+                    self.rowbuf
+                        .extend(rows.by_ref().take(self.buf_size).map(BinaryRow::Fetched));
+                }
+            }
+            self.peak_buffered_rows = self.peak_buffered_rows.max(self.rowbuf.len());
+
+            if let Some(limiter) = &self.rate_limit {
+                // the tuple header and the length prefix of every field, then the fields
+                let mut nbytes = self.rowbuf.len() * (2 + 4 * self.ncols);
+                for row in &self.rowbuf {
+                    for cidx in 0..self.ncols {
+                        nbytes += row.try_get::<RawLen>(cidx)?.0;
                     }
-                    None => break,
                 }
+                limiter.acquire(nbytes);
             }
 
             if self.rowbuf.is_empty() {
@@ -320,6 +1091,89 @@ impl<'a> PostgresBinarySourcePartitionParser<'a> {
     }
 }
 
+// The size of a field on the wire, whatever its type.
+struct RawLen(usize);
+
+impl<'a> FromSql<'a> for RawLen {
+    fn from_sql(
+        _: &Type,
+        raw: &'a [u8],
+    ) -> std::result::Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(RawLen(raw.len()))
+    }
+
+    fn from_sql_null(_: &Type) -> std::result::Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(RawLen(0))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+// The bytes of a field on the wire, whatever its type, none for a null.
+struct RawBytes(Option<Vec<u8>>);
+
+impl<'a> FromSql<'a> for RawBytes {
+    fn from_sql(
+        _: &Type,
+        raw: &'a [u8],
+    ) -> std::result::Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(RawBytes(Some(raw.to_vec())))
+    }
+
+    fn from_sql_null(_: &Type) -> std::result::Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(RawBytes(None))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+// The name of a cursor handed back as a `refcursor`.
+struct CursorName(String);
+
+impl<'a> FromSql<'a> for CursorName {
+    fn from_sql(
+        _: &Type,
+        raw: &'a [u8],
+    ) -> std::result::Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(CursorName(std::str::from_utf8(raw)?.to_string()))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::REFCURSOR
+    }
+}
+
+/// Run `query` in `tx` and fetch all the rows of the one cursor it hands back, along with the
+/// statement that describes their columns.
+fn fetch_cursor(tx: &mut Transaction<'_>, query: &str) -> Result<(Statement, Vec<Row>)> {
+    let cursor = returned_cursor(tx, query)?;
+    let stmt = tx.prepare(&format!("FETCH ALL FROM {}", cursor))?;
+    let rows = tx.query(&stmt, &[])?;
+    Ok((stmt, rows))
+}
+
+/// Run `query` in `tx` and return the one cursor it hands back, quoted for a FETCH.
+fn returned_cursor(tx: &mut Transaction<'_>, query: &str) -> Result<String> {
+    let mut names = vec![];
+    for row in tx.query(query, &[])? {
+        for cidx in 0..row.len() {
+            names.push(row.try_get::<_, CursorName>(cidx)?.0);
+        }
+    }
+    match names.as_slice() {
+        [name] => Ok(format!("\"{}\"", name.replace('"', "\"\""))),
+        _ => Err(anyhow!(
+            "expected the query to return a single refcursor, got {}",
+            names.len()
+        )
+        .into()),
+    }
+}
+
 impl<'a> PartitionParser<'a> for PostgresBinarySourcePartitionParser<'a> {
     type TypeSystem = PostgresTypeSystem;
 }
@@ -330,8 +1184,7 @@ macro_rules! impl_produce {
             impl<'r, 'a> Produce<'r, $t> for PostgresBinarySourcePartitionParser<'a> {
                 fn produce(&'r mut self) -> Result<$t> {
                     let (ridx, cidx) = self.next_loc()?;
-                    let row = &self.rowbuf[ridx];
-                    let val = row.try_get(cidx)?;
+                    let val = self.get(ridx, cidx)?;
                     Ok(val)
                 }
             }
@@ -339,8 +1192,7 @@ macro_rules! impl_produce {
             impl<'r, 'a> Produce<'r, Option<$t>> for PostgresBinarySourcePartitionParser<'a> {
                 fn produce(&'r mut self) -> Result<Option<$t>> {
                     let (ridx, cidx) = self.next_loc()?;
-                    let row = &self.rowbuf[ridx];
-                    let val = row.try_get(cidx)?;
+                    let val = self.get(ridx, cidx)?;
                     Ok(val)
                 }
             }
@@ -355,7 +1207,6 @@ impl_produce!(
     i64,
     f32,
     f64,
-    Decimal,
     bool,
     &'r str,
     Vec<u8>,
@@ -365,8 +1216,65 @@ impl_produce!(
     NaiveDate,
     Uuid,
     Value,
+    Point,
+    Circle,
+    Xid8,
+    Tid,
+    Snapshot,
+    Vec<Option<String>>,
+    RegOid,
+    JsonPathStr,
+    Multirange,
 );
 
+impl<'r, 'a> Produce<'r, Record> for PostgresBinarySourcePartitionParser<'a> {
+    fn produce(&'r mut self) -> Result<Record> {
+        let (ridx, cidx) = self.next_loc()?;
+        let RecordValues(values) = self.get(ridx, cidx)?;
+        Ok(Record {
+            fields: self.fields_of(cidx)?,
+            values,
+        })
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<Record>> for PostgresBinarySourcePartitionParser<'a> {
+    fn produce(&'r mut self) -> Result<Option<Record>> {
+        let (ridx, cidx) = self.next_loc()?;
+        let values: Option<RecordValues> = self.get(ridx, cidx)?;
+        Ok(match values {
+            Some(RecordValues(values)) => Some(Record {
+                fields: self.fields_of(cidx)?,
+                values,
+            }),
+            None => None,
+        })
+    }
+}
+
+fn rescale(val: Decimal, numeric_scale: Option<(u32, DecimalRounding)>) -> Decimal {
+    match numeric_scale {
+        Some((scale, rounding)) => rounding.rescale(val, scale),
+        None => val,
+    }
+}
+
+impl<'r, 'a> Produce<'r, Decimal> for PostgresBinarySourcePartitionParser<'a> {
+    fn produce(&'r mut self) -> Result<Decimal> {
+        let (ridx, cidx) = self.next_loc()?;
+        let val = self.get(ridx, cidx)?;
+        Ok(rescale(val, self.numeric_scale))
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<Decimal>> for PostgresBinarySourcePartitionParser<'a> {
+    fn produce(&'r mut self) -> Result<Option<Decimal>> {
+        let (ridx, cidx) = self.next_loc()?;
+        let val: Option<Decimal> = self.get(ridx, cidx)?;
+        Ok(val.map(|v| rescale(v, self.numeric_scale)))
+    }
+}
+
 pub struct PostgresCSVSourceParser<'a> {
     iter: StringRecordsIntoIter<CopyOutReader<'a>>,
     buf_size: usize,
@@ -374,6 +1282,11 @@ pub struct PostgresCSVSourceParser<'a> {
     ncols: usize,
     current_col: usize,
     current_row: usize,
+    numeric_scale: Option<(u32, DecimalRounding)>,
+    numeric_format: DecimalFormat,
+    rate_limit: Option<Arc<RateLimiter>>,
+    rows_done: usize,
+    names: Option<Vec<String>>,
 }
 
 impl<'a> PostgresCSVSourceParser<'a> {
@@ -381,6 +1294,8 @@ impl<'a> PostgresCSVSourceParser<'a> {
         iter: StringRecordsIntoIter<CopyOutReader<'a>>,
         schema: &[PostgresTypeSystem],
         buf_size: usize,
+        numeric_scale: Option<(u32, DecimalRounding)>,
+        rate_limit: Option<Arc<RateLimiter>>,
     ) -> Self {
         Self {
             iter,
@@ -389,12 +1304,45 @@ impl<'a> PostgresCSVSourceParser<'a> {
             ncols: schema.len(),
             current_row: 0,
             current_col: 0,
+            numeric_scale,
+            numeric_format: DecimalFormat::default(),
+            rate_limit,
+            rows_done: 0,
+            names: None,
+        }
+    }
+
+    /// Parse the numerics in `format`.
+    pub fn numeric_format(mut self, format: DecimalFormat) -> Self {
+        self.numeric_format = format;
+        self
+    }
+
+    /// Report the cells that do not parse under the column `names`, if given, their text as
+    /// the raw bytes.
+    pub fn diagnostics(mut self, names: Option<Vec<String>>) -> Self {
+        self.names = names;
+        self
+    }
+
+    fn cannot_parse<T>(&self, ridx: usize, cidx: usize) -> ConnectorAgentError {
+        let cell = &self.rowbuf[ridx][cidx];
+        let err = ConnectorAgentError::cannot_produce::<T>(Some(cell.into()));
+        match &self.names {
+            Some(names) => ConnectorAgentError::cannot_decode::<T>(
+                &names[cidx],
+                self.rows_done + ridx,
+                Some(cell.as_bytes().to_vec()),
+                err,
+            ),
+            None => err,
         }
     }
 
     fn next_loc(&mut self) -> Result<(usize, usize)> {
         if self.current_row >= self.rowbuf.len() {
             if !self.rowbuf.is_empty() {
+                self.rows_done += self.rowbuf.len();
                 self.rowbuf.drain(..);
             }
 
@@ -406,6 +1354,16 @@ impl<'a> PostgresCSVSourceParser<'a> {
                 }
             }
 
+            if let Some(limiter) = &self.rate_limit {
+                // the fields plus a separator or line break after each
+                let nbytes = self
+                    .rowbuf
+                    .iter()
+                    .map(|row| row.as_byte_record().as_slice().len() + self.ncols)
+                    .sum();
+                limiter.acquire(nbytes);
+            }
+
             if self.rowbuf.is_empty() {
                 throw!(anyhow!("Postgres EOF"));
             }
@@ -431,7 +1389,7 @@ macro_rules! impl_csv_produce {
                 fn produce(&'r mut self) -> Result<$t> {
                     let (ridx, cidx) = self.next_loc()?;
                     self.rowbuf[ridx][cidx].parse().map_err(|_| {
-                        ConnectorAgentError::cannot_produce::<$t>(Some(self.rowbuf[ridx][cidx].into()))
+                        self.cannot_parse::<$t>(ridx, cidx)
                     })
                 }
             }
@@ -442,7 +1400,7 @@ macro_rules! impl_csv_produce {
                     match &self.rowbuf[ridx][cidx][..] {
                         "" => Ok(None),
                         v => Ok(Some(v.parse().map_err(|_| {
-                            ConnectorAgentError::cannot_produce::<$t>(Some(self.rowbuf[ridx][cidx].into()))
+                            self.cannot_parse::<$t>(ridx, cidx)
                         })?)),
                     }
                 }
@@ -451,7 +1409,35 @@ macro_rules! impl_csv_produce {
     };
 }
 
-impl_csv_produce!(i8, i16, i32, i64, f32, f64, Decimal, Uuid,);
+impl_csv_produce!(i8, i16, i32, i64, f32, f64, Uuid,);
+
+impl<'r, 'a> Produce<'r, Decimal> for PostgresCSVSourceParser<'a> {
+    fn produce(&'r mut self) -> Result<Decimal> {
+        let (ridx, cidx) = self.next_loc()?;
+        let v = &self.rowbuf[ridx][cidx];
+        let val = self
+            .numeric_format
+            .parse(v)
+            .ok_or_else(|| self.cannot_parse::<Decimal>(ridx, cidx))?;
+        Ok(rescale(val, self.numeric_scale))
+    }
+}
+
+impl<'r, 'a> Produce<'r, Option<Decimal>> for PostgresCSVSourceParser<'a> {
+    fn produce(&'r mut self) -> Result<Option<Decimal>> {
+        let (ridx, cidx) = self.next_loc()?;
+        match &self.rowbuf[ridx][cidx][..] {
+            "" => Ok(None),
+            v => {
+                let val = self
+                    .numeric_format
+                    .parse(v)
+                    .ok_or_else(|| self.cannot_parse::<Decimal>(ridx, cidx))?;
+                Ok(Some(rescale(val, self.numeric_scale)))
+            }
+        }
+    }
+}
 
 impl<'r, 'a> Produce<'r, bool> for PostgresCSVSourceParser<'a> {
     fn produce(&mut self) -> Result<bool> {
@@ -459,9 +1445,7 @@ impl<'r, 'a> Produce<'r, bool> for PostgresCSVSourceParser<'a> {
         let ret = match &self.rowbuf[ridx][cidx][..] {
             "t" => true,
             "f" => false,
-            _ => throw!(ConnectorAgentError::cannot_produce::<bool>(Some(
-                self.rowbuf[ridx][cidx].into()
-            ))),
+            _ => throw!(self.cannot_parse::<bool>(ridx, cidx)),
         };
         Ok(ret)
     }
@@ -474,9 +1458,7 @@ impl<'r, 'a> Produce<'r, Option<bool>> for PostgresCSVSourceParser<'a> {
             "" => None,
             "t" => Some(true),
             "f" => Some(false),
-            _ => throw!(ConnectorAgentError::cannot_produce::<bool>(Some(
-                self.rowbuf[ridx][cidx].into()
-            ))),
+            _ => throw!(self.cannot_parse::<bool>(ridx, cidx)),
         };
         Ok(ret)
     }
@@ -485,11 +1467,9 @@ impl<'r, 'a> Produce<'r, Option<bool>> for PostgresCSVSourceParser<'a> {
 impl<'r, 'a> Produce<'r, DateTime<Utc>> for PostgresCSVSourceParser<'a> {
     fn produce(&mut self) -> Result<DateTime<Utc>> {
         let (ridx, cidx) = self.next_loc()?;
-        self.rowbuf[ridx][cidx].parse().map_err(|_| {
-            ConnectorAgentError::cannot_produce::<DateTime<Utc>>(Some(
-                self.rowbuf[ridx][cidx].into(),
-            ))
-        })
+        self.rowbuf[ridx][cidx]
+            .parse()
+            .map_err(|_| self.cannot_parse::<DateTime<Utc>>(ridx, cidx))
     }
 }
 
@@ -498,9 +1478,11 @@ impl<'r, 'a> Produce<'r, Option<DateTime<Utc>>> for PostgresCSVSourceParser<'a>
         let (ridx, cidx) = self.next_loc()?;
         match &self.rowbuf[ridx][cidx][..] {
             "" => Ok(None),
-            v => Ok(Some(v.parse().map_err(|_| {
-                ConnectorAgentError::cannot_produce::<DateTime<Utc>>(Some(v.into()))
-            })?)),
+            v => {
+                Ok(Some(v.parse().map_err(|_| {
+                    self.cannot_parse::<DateTime<Utc>>(ridx, cidx)
+                })?))
+            }
         }
     }
 }
@@ -508,9 +1490,8 @@ impl<'r, 'a> Produce<'r, Option<DateTime<Utc>>> for PostgresCSVSourceParser<'a>
 impl<'r, 'a> Produce<'r, NaiveDate> for PostgresCSVSourceParser<'a> {
     fn produce(&mut self) -> Result<NaiveDate> {
         let (ridx, cidx) = self.next_loc()?;
-        NaiveDate::parse_from_str(&self.rowbuf[ridx][cidx], "%Y-%m-%d").map_err(|_| {
-            ConnectorAgentError::cannot_produce::<NaiveDate>(Some(self.rowbuf[ridx][cidx].into()))
-        })
+        NaiveDate::parse_from_str(&self.rowbuf[ridx][cidx], "%Y-%m-%d")
+            .map_err(|_| self.cannot_parse::<NaiveDate>(ridx, cidx))
     }
 }
 
@@ -519,9 +1500,10 @@ impl<'r, 'a> Produce<'r, Option<NaiveDate>> for PostgresCSVSourceParser<'a> {
         let (ridx, cidx) = self.next_loc()?;
         match &self.rowbuf[ridx][cidx][..] {
             "" => Ok(None),
-            v => Ok(Some(NaiveDate::parse_from_str(v, "%Y-%m-%d").map_err(
-                |_| ConnectorAgentError::cannot_produce::<NaiveDate>(Some(v.into())),
-            )?)),
+            v => Ok(Some(
+                NaiveDate::parse_from_str(v, "%Y-%m-%d")
+                    .map_err(|_| self.cannot_parse::<NaiveDate>(ridx, cidx))?,
+            )),
         }
     }
 }
@@ -529,11 +1511,8 @@ impl<'r, 'a> Produce<'r, Option<NaiveDate>> for PostgresCSVSourceParser<'a> {
 impl<'r, 'a> Produce<'r, NaiveDateTime> for PostgresCSVSourceParser<'a> {
     fn produce(&mut self) -> Result<NaiveDateTime> {
         let (ridx, cidx) = self.next_loc()?;
-        NaiveDateTime::parse_from_str(&self.rowbuf[ridx][cidx], "%Y-%m-%d %H:%M:%S").map_err(|_| {
-            ConnectorAgentError::cannot_produce::<NaiveDateTime>(Some(
-                self.rowbuf[ridx][cidx].into(),
-            ))
-        })
+        NaiveDateTime::parse_from_str(&self.rowbuf[ridx][cidx], "%Y-%m-%d %H:%M:%S")
+            .map_err(|_| self.cannot_parse::<NaiveDateTime>(ridx, cidx))
     }
 }
 
@@ -543,9 +1522,8 @@ impl<'r, 'a> Produce<'r, Option<NaiveDateTime>> for PostgresCSVSourceParser<'a>
         match &self.rowbuf[ridx][cidx][..] {
             "" => Ok(None),
             v => Ok(Some(
-                NaiveDateTime::parse_from_str(v, "%Y-%m-%d %H:%M:%S").map_err(|_| {
-                    ConnectorAgentError::cannot_produce::<NaiveDateTime>(Some(v.into()))
-                })?,
+                NaiveDateTime::parse_from_str(v, "%Y-%m-%d %H:%M:%S")
+                    .map_err(|_| self.cannot_parse::<NaiveDateTime>(ridx, cidx))?,
             )),
         }
     }
@@ -554,9 +1532,8 @@ impl<'r, 'a> Produce<'r, Option<NaiveDateTime>> for PostgresCSVSourceParser<'a>
 impl<'r, 'a> Produce<'r, NaiveTime> for PostgresCSVSourceParser<'a> {
     fn produce(&mut self) -> Result<NaiveTime> {
         let (ridx, cidx) = self.next_loc()?;
-        NaiveTime::parse_from_str(&self.rowbuf[ridx][cidx], "%H:%M:%S").map_err(|_| {
-            ConnectorAgentError::cannot_produce::<NaiveTime>(Some(self.rowbuf[ridx][cidx].into()))
-        })
+        NaiveTime::parse_from_str(&self.rowbuf[ridx][cidx], "%H:%M:%S")
+            .map_err(|_| self.cannot_parse::<NaiveTime>(ridx, cidx))
     }
 }
 
@@ -565,9 +1542,10 @@ impl<'r, 'a> Produce<'r, Option<NaiveTime>> for PostgresCSVSourceParser<'a> {
         let (ridx, cidx) = self.next_loc()?;
         match &self.rowbuf[ridx][cidx][..] {
             "" => Ok(None),
-            v => Ok(Some(NaiveTime::parse_from_str(v, "%H:%M:%S").map_err(
-                |_| ConnectorAgentError::cannot_produce::<NaiveTime>(Some(v.into())),
-            )?)),
+            v => Ok(Some(
+                NaiveTime::parse_from_str(v, "%H:%M:%S")
+                    .map_err(|_| self.cannot_parse::<NaiveTime>(ridx, cidx))?,
+            )),
         }
     }
 }
@@ -611,7 +1589,7 @@ impl<'r, 'a> Produce<'r, Value> for PostgresCSVSourceParser<'a> {
     fn produce(&'r mut self) -> Result<Value> {
         let (ridx, cidx) = self.next_loc()?;
         let v = &self.rowbuf[ridx][cidx];
-        from_str(v).map_err(|_| ConnectorAgentError::cannot_produce::<Value>(Some(v.into())))
+        from_str(v).map_err(|_| self.cannot_parse::<Value>(ridx, cidx))
     }
 }
 
@@ -621,8 +1599,7 @@ impl<'r, 'a> Produce<'r, Option<Value>> for PostgresCSVSourceParser<'a> {
 
         match &self.rowbuf[ridx][cidx][..] {
             "" => Ok(None),
-            v => from_str(v)
-                .map_err(|_| ConnectorAgentError::cannot_produce::<Value>(Some(v.into()))),
+            v => from_str(v).map_err(|_| self.cannot_parse::<Value>(ridx, cidx)),
         }
     }
 }
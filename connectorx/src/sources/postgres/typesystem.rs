@@ -1,10 +1,17 @@
+use crate::dummy_typesystem::{
+    Circle, Point, Record, RecordFields, RecordType, RecordValue, Snapshot,
+};
+use bytes::Buf;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
-use postgres::types::Type;
+use postgres::types::{Field, FromSql, Kind, Type};
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use serde_json::Value;
+use std::error::Error;
+use std::fmt;
 use uuid::Uuid;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum PostgresTypeSystem {
     Bool(bool),
     Float4(bool),
@@ -25,7 +32,17 @@ pub enum PostgresTypeSystem {
     UUID(bool),
     JSON(bool),
     JSONB(bool),
+    JsonPath(bool),
     Enum(bool),
+    Point(bool),
+    Circle(bool),
+    Xid8(bool),
+    Tid(bool),
+    Snapshot(bool),
+    TextArray(bool),
+    RegOid(bool),
+    Composite(bool),
+    Multirange(bool),
 }
 
 impl_typesystem! {
@@ -47,6 +64,16 @@ impl_typesystem! {
         { Date => NaiveDate }
         { UUID => Uuid }
         { JSON | JSONB => Value }
+        { JsonPath => JsonPathStr }
+        { Point => Point }
+        { Circle => Circle }
+        { Xid8 => Xid8 }
+        { Tid => Tid }
+        { Snapshot => Snapshot }
+        { TextArray => Vec<Option<String>> }
+        { RegOid => RegOid }
+        { Composite => Record }
+        { Multirange => Multirange }
     }
 }
 
@@ -73,8 +100,18 @@ impl<'a> From<&'a Type> for PostgresTypeSystem {
             "uuid" => UUID(true),
             "json" => JSON(true),
             "jsonb" => JSONB(true),
+            "jsonpath" => JsonPath(true),
+            "point" => Point(true),
+            "circle" => Circle(true),
+            "xid8" => Xid8(true),
+            "tid" => Tid(true),
+            "pg_snapshot" => Snapshot(true),
+            "_text" => TextArray(true),
+            name if REG_TYPES.contains(&name) => RegOid(true),
+            name if multirange_element(name).is_some() => Multirange(true),
             _ => match ty.kind() {
                 postgres::types::Kind::Enum(_) => Enum(true),
+                postgres::types::Kind::Composite(_) => Composite(true),
                 _ => unimplemented!("{}", ty.name()),
             },
         }
@@ -105,7 +142,472 @@ impl<'a> From<PostgresTypeSystem> for Type {
             UUID(_) => Type::UUID,
             JSON(_) => Type::JSON,
             JSONB(_) => Type::JSONB,
+            JsonPath(_) => Type::JSONPATH,
             Enum(_) => Type::TEXT,
+            Point(_) => Type::POINT,
+            Circle(_) => Type::CIRCLE,
+            Xid8(_) => Type::XID8,
+            Tid(_) => Type::TID,
+            Snapshot(_) => Type::PG_SNAPSHOT,
+            // COPY decodes a field by this type, a text one would take the array's header and
+            // length prefixes for characters
+            TextArray(_) => Type::TEXT_ARRAY,
+            // all the reg* types are an oid on the wire
+            RegOid(_) => Type::REGCLASS,
+            // a COPY has to decode a composite by the column's own type, the one with the
+            // fields, see `PostgresSourcePartition::column_types`
+            Composite(_) => Type::RECORD,
+            // the same goes for a multirange, whose type tells how to decode its bounds
+            Multirange(_) => Type::UNKNOWN,
         }
     }
 }
+
+// The binary wire format of `point` is two big-endian float8s, x followed by y.
+impl<'a> FromSql<'a> for Point {
+    fn from_sql(_ty: &Type, mut raw: &'a [u8]) -> Result<Point, Box<dyn Error + Sync + Send>> {
+        if raw.len() != 16 {
+            return Err(format!("invalid point buffer size: {}", raw.len()).into());
+        }
+        let x = raw.get_f64();
+        let y = raw.get_f64();
+        Ok(Point { x, y })
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::POINT
+    }
+}
+
+// A `circle` is its center as a `point`, then the float8 radius.
+impl<'a> FromSql<'a> for Circle {
+    fn from_sql(_ty: &Type, mut raw: &'a [u8]) -> Result<Circle, Box<dyn Error + Sync + Send>> {
+        if raw.len() != 24 {
+            return Err(format!("invalid circle buffer size: {}", raw.len()).into());
+        }
+        let x = raw.get_f64();
+        let y = raw.get_f64();
+        let radius = raw.get_f64();
+        Ok(Circle {
+            center: Point { x, y },
+            radius,
+        })
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::CIRCLE
+    }
+}
+
+/// A 64-bit transaction id, an unsigned big-endian int8 on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Xid8(pub u64);
+
+impl<'a> FromSql<'a> for Xid8 {
+    fn from_sql(_ty: &Type, mut raw: &'a [u8]) -> Result<Xid8, Box<dyn Error + Sync + Send>> {
+        if raw.len() != 8 {
+            return Err(format!("invalid xid8 buffer size: {}", raw.len()).into());
+        }
+        Ok(Xid8(raw.get_u64()))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::XID8
+    }
+}
+
+/// The physical location of a row version, e.g. a `ctid`: the number of its block in the
+/// table and its index among the tuples of that block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tid {
+    pub block: u32,
+    pub index: u16,
+}
+
+// Written the way Postgres writes it, `(block,index)`.
+impl fmt::Display for Tid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({},{})", self.block, self.index)
+    }
+}
+
+// A big-endian uint4 block number followed by a big-endian uint2 tuple index.
+impl<'a> FromSql<'a> for Tid {
+    fn from_sql(_ty: &Type, mut raw: &'a [u8]) -> Result<Tid, Box<dyn Error + Sync + Send>> {
+        if raw.len() != 6 {
+            return Err(format!("invalid tid buffer size: {}", raw.len()).into());
+        }
+        let block = raw.get_u32();
+        let index = raw.get_u16();
+        Ok(Tid { block, index })
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::TID
+    }
+}
+
+/// The OID aliases, which name a row of a catalog table by its OID.
+pub(crate) const REG_TYPES: &[&str] = &[
+    "regclass",
+    "regcollation",
+    "regconfig",
+    "regdictionary",
+    "regnamespace",
+    "regoper",
+    "regoperator",
+    "regproc",
+    "regprocedure",
+    "regrole",
+    "regtype",
+];
+
+/// The OID a reg* value stands for, an unsigned big-endian int4 on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegOid(pub u32);
+
+impl<'a> FromSql<'a> for RegOid {
+    fn from_sql(_ty: &Type, mut raw: &'a [u8]) -> Result<RegOid, Box<dyn Error + Sync + Send>> {
+        if raw.len() != 4 {
+            return Err(format!("invalid oid buffer size: {}", raw.len()).into());
+        }
+        Ok(RegOid(raw.get_u32()))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        REG_TYPES.contains(&ty.name())
+    }
+}
+
+/// A `jsonpath` in its canonical text form. On the wire it is a version byte, always 1 so far,
+/// followed by that text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPathStr(pub String);
+
+impl<'a> FromSql<'a> for JsonPathStr {
+    fn from_sql(_ty: &Type, raw: &'a [u8]) -> Result<JsonPathStr, Box<dyn Error + Sync + Send>> {
+        match raw.split_first() {
+            Some((1, text)) => Ok(JsonPathStr(std::str::from_utf8(text)?.to_string())),
+            Some((version, _)) => Err(format!("unsupported jsonpath version: {}", version).into()),
+            None => Err("empty jsonpath buffer".into()),
+        }
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::JSONPATH
+    }
+}
+
+// The binary wire format of `pg_snapshot` is the int4 count of in-progress transactions, then
+// xmin, xmax and the in-progress ones as int8s.
+impl<'a> FromSql<'a> for Snapshot {
+    fn from_sql(_ty: &Type, mut raw: &'a [u8]) -> Result<Snapshot, Box<dyn Error + Sync + Send>> {
+        if raw.len() < 20 {
+            return Err(format!("invalid pg_snapshot buffer size: {}", raw.len()).into());
+        }
+        let nxip = raw.get_i32();
+        if nxip < 0 || raw.len() != 16 + nxip as usize * 8 {
+            return Err(format!("invalid pg_snapshot with {} in-progress ids", nxip).into());
+        }
+        let xmin = raw.get_u64();
+        let xmax = raw.get_u64();
+        let xip = (0..nxip).map(|_| raw.get_u64()).collect();
+        Ok(Snapshot { xmin, xmax, xip })
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::PG_SNAPSHOT
+    }
+}
+
+type FromSqlResult<T> = Result<T, Box<dyn Error + Sync + Send>>;
+
+// The field types a composite can have, nested composites included. Numerics are read as
+// float8s and enums as their labels.
+fn record_type(ty: &Type) -> FromSqlResult<RecordType> {
+    Ok(if [Type::INT2, Type::INT4, Type::INT8].contains(ty) {
+        RecordType::I64
+    } else if [Type::FLOAT4, Type::FLOAT8, Type::NUMERIC].contains(ty) {
+        RecordType::F64
+    } else if *ty == Type::BOOL {
+        RecordType::Bool
+    } else if [Type::TEXT, Type::VARCHAR, Type::BPCHAR, Type::NAME].contains(ty) {
+        RecordType::String
+    } else {
+        match ty.kind() {
+            Kind::Enum(_) => RecordType::String,
+            Kind::Composite(fields) => RecordType::Record(record_fields(fields)?),
+            _ => return Err(format!("unsupported type of a composite field: {}", ty).into()),
+        }
+    })
+}
+
+fn record_fields(fields: &[Field]) -> FromSqlResult<Vec<(String, RecordType)>> {
+    fields
+        .iter()
+        .map(|f| Ok((f.name().to_string(), record_type(f.type_())?)))
+        .collect()
+}
+
+fn record_value(ty: &Type, raw: &[u8]) -> FromSqlResult<RecordValue> {
+    Ok(if *ty == Type::INT2 {
+        RecordValue::I64(i16::from_sql(ty, raw)? as i64)
+    } else if *ty == Type::INT4 {
+        RecordValue::I64(i32::from_sql(ty, raw)? as i64)
+    } else if *ty == Type::INT8 {
+        RecordValue::I64(i64::from_sql(ty, raw)?)
+    } else if *ty == Type::FLOAT4 {
+        RecordValue::F64(f32::from_sql(ty, raw)? as f64)
+    } else if *ty == Type::FLOAT8 {
+        RecordValue::F64(f64::from_sql(ty, raw)?)
+    } else if *ty == Type::NUMERIC {
+        let val = Decimal::from_sql(ty, raw)?;
+        RecordValue::F64(
+            val.to_f64()
+                .ok_or_else(|| format!("cannot convert decimal {:?} to float64", val))?,
+        )
+    } else if *ty == Type::BOOL {
+        RecordValue::Bool(bool::from_sql(ty, raw)?)
+    } else {
+        match ty.kind() {
+            Kind::Composite(fields) => RecordValue::Record(record_values(fields, raw)?),
+            // text types and enum labels are all plain UTF-8
+            _ => RecordValue::String(std::str::from_utf8(raw)?.to_string()),
+        }
+    })
+}
+
+// The binary wire format of a composite is the int4 count of its fields, then for each field
+// the oid of its type and the int4 length of its value, -1 for a null, followed by the value.
+fn record_values(fields: &[Field], mut raw: &[u8]) -> FromSqlResult<Vec<RecordValue>> {
+    if raw.len() < 4 {
+        return Err(format!("invalid composite buffer size: {}", raw.len()).into());
+    }
+    let nfields = raw.get_i32();
+    if nfields as usize != fields.len() {
+        return Err(format!("composite of {} fields, expected {}", nfields, fields.len()).into());
+    }
+    fields
+        .iter()
+        .map(|field| {
+            if raw.len() < 8 {
+                return Err("truncated composite buffer".into());
+            }
+            let _oid = raw.get_u32();
+            let len = raw.get_i32();
+            if len < 0 {
+                return Ok(RecordValue::Null);
+            }
+            if raw.len() < len as usize {
+                return Err("truncated composite buffer".into());
+            }
+            let (value, rest) = raw.split_at(len as usize);
+            raw = rest;
+            record_value(field.type_(), value)
+        })
+        .collect()
+}
+
+/// The fields of the composite type `ty`, none for another type. They are worked out once
+/// per column and shared by the records read from it, which only decode their values.
+pub(crate) fn record_schema(ty: &Type) -> FromSqlResult<Option<RecordFields>> {
+    match ty.kind() {
+        Kind::Composite(fields) => Ok(Some(record_fields(fields)?.into())),
+        _ => Ok(None),
+    }
+}
+
+/// The values of the fields of a composite, without the names and types in `Record`.
+pub(crate) struct RecordValues(pub Vec<RecordValue>);
+
+impl<'a> FromSql<'a> for RecordValues {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> FromSqlResult<RecordValues> {
+        match ty.kind() {
+            Kind::Composite(fields) => Ok(RecordValues(record_values(fields, raw)?)),
+            _ => Err(format!("not a composite type: {}", ty).into()),
+        }
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        matches!(ty.kind(), Kind::Composite(_))
+    }
+}
+
+impl<'a> FromSql<'a> for Record {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> FromSqlResult<Record> {
+        match record_schema(ty)? {
+            Some(fields) => Ok(Record {
+                fields,
+                values: RecordValues::from_sql(ty, raw)?.0,
+            }),
+            None => Err(format!("not a composite type: {}", ty).into()),
+        }
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        RecordValues::accepts(ty)
+    }
+}
+
+/// The ranges of a Postgres multirange, in order. An empty multirange has no ranges.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Multirange(pub Vec<Range>);
+
+/// A range of a multirange, which is never empty. A side without a bound is unbounded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range {
+    pub lower: Option<RangeBound>,
+    pub upper: Option<RangeBound>,
+    pub lower_inc: bool,
+    pub upper_inc: bool,
+}
+
+/// A bound of a range, of the element type of its multirange.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeBound {
+    Int(i64),
+    Numeric(Decimal),
+    Date(NaiveDate),
+    Timestamp(NaiveDateTime),
+    TimestampTz(DateTime<Utc>),
+}
+
+impl fmt::Display for RangeBound {
+    /// As Postgres writes the bound in a range literal, timestamps in UTC, but unquoted.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeBound::Int(v) => write!(f, "{}", v),
+            RangeBound::Numeric(v) => write!(f, "{}", v),
+            RangeBound::Date(v) => write!(f, "{}", v.format("%Y-%m-%d")),
+            RangeBound::Timestamp(v) => write!(f, "{}", v.format("%Y-%m-%d %H:%M:%S%.f")),
+            RangeBound::TimestampTz(v) => write!(f, "{}", v.format("%Y-%m-%d %H:%M:%S%.f+00")),
+        }
+    }
+}
+
+impl fmt::Display for Range {
+    /// The range literal, like `[1,5)` or `["2021-01-01 00:00:00+00","2021-01-02 00:00:00+00")`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bound = |bound: &Option<RangeBound>| {
+            let bound = bound.as_ref().map_or(String::new(), |b| b.to_string());
+            if bound.contains(|c| " ,()[]\"\\".contains(c)) {
+                format!("\"{}\"", bound.replace('\\', "\\\\").replace('"', "\\\""))
+            } else {
+                bound
+            }
+        };
+        write!(
+            f,
+            "{}{},{}{}",
+            if self.lower_inc { '[' } else { '(' },
+            bound(&self.lower),
+            bound(&self.upper),
+            if self.upper_inc { ']' } else { ')' },
+        )
+    }
+}
+
+// The built-in multiranges and the types of their bounds.
+fn multirange_element(name: &str) -> Option<Type> {
+    Some(match name {
+        "int4multirange" => Type::INT4,
+        "int8multirange" => Type::INT8,
+        "nummultirange" => Type::NUMERIC,
+        "datemultirange" => Type::DATE,
+        "tsmultirange" => Type::TIMESTAMP,
+        "tstzmultirange" => Type::TIMESTAMPTZ,
+        _ => return None,
+    })
+}
+
+fn range_bound(ty: &Type, raw: &[u8]) -> FromSqlResult<RangeBound> {
+    Ok(if *ty == Type::INT4 {
+        RangeBound::Int(i32::from_sql(ty, raw)? as i64)
+    } else if *ty == Type::INT8 {
+        RangeBound::Int(i64::from_sql(ty, raw)?)
+    } else if *ty == Type::NUMERIC {
+        RangeBound::Numeric(Decimal::from_sql(ty, raw)?)
+    } else if *ty == Type::DATE {
+        RangeBound::Date(NaiveDate::from_sql(ty, raw)?)
+    } else if *ty == Type::TIMESTAMP {
+        RangeBound::Timestamp(NaiveDateTime::from_sql(ty, raw)?)
+    } else {
+        RangeBound::TimestampTz(DateTime::<Utc>::from_sql(ty, raw)?)
+    })
+}
+
+const RANGE_EMPTY: u8 = 0x01;
+const RANGE_LB_INC: u8 = 0x02;
+const RANGE_UB_INC: u8 = 0x04;
+const RANGE_LB_INF: u8 = 0x08;
+const RANGE_UB_INF: u8 = 0x10;
+
+// The binary wire format of a range is a flags byte, then the lower and the upper bound
+// unless they are infinite, each one as its int4 length followed by its value. None for an
+// empty range, which a multirange leaves out anyway.
+fn range(ty: &Type, mut raw: &[u8]) -> FromSqlResult<Option<Range>> {
+    if raw.is_empty() {
+        return Err("empty range buffer".into());
+    }
+    let flags = raw.get_u8();
+    if flags & RANGE_EMPTY != 0 {
+        return Ok(None);
+    }
+    let mut bound = |infinite: u8| -> FromSqlResult<Option<RangeBound>> {
+        if flags & infinite != 0 {
+            return Ok(None);
+        }
+        if raw.len() < 4 {
+            return Err("truncated range bound".into());
+        }
+        let len = raw.get_i32();
+        if len < 0 || raw.len() < len as usize {
+            return Err(format!("invalid range bound length: {}", len).into());
+        }
+        let (value, rest) = raw.split_at(len as usize);
+        raw = rest;
+        range_bound(ty, value).map(Some)
+    };
+    let lower = bound(RANGE_LB_INF)?;
+    let upper = bound(RANGE_UB_INF)?;
+    Ok(Some(Range {
+        lower,
+        upper,
+        lower_inc: flags & RANGE_LB_INC != 0,
+        upper_inc: flags & RANGE_UB_INC != 0,
+    }))
+}
+
+// The binary wire format of a multirange is the int4 count of its ranges, then each range as
+// its int4 length followed by the range.
+impl<'a> FromSql<'a> for Multirange {
+    fn from_sql(ty: &Type, mut raw: &'a [u8]) -> FromSqlResult<Multirange> {
+        let element = multirange_element(ty.name())
+            .ok_or_else(|| format!("not a multirange type: {}", ty))?;
+        if raw.len() < 4 {
+            return Err(format!("invalid multirange buffer size: {}", raw.len()).into());
+        }
+        let nranges = raw.get_i32();
+        if nranges < 0 {
+            return Err(format!("invalid multirange with {} ranges", nranges).into());
+        }
+        let mut ranges = Vec::with_capacity(nranges as usize);
+        for _ in 0..nranges {
+            if raw.len() < 4 {
+                return Err("truncated multirange".into());
+            }
+            let len = raw.get_i32();
+            if len < 0 || raw.len() < len as usize {
+                return Err(format!("invalid range length: {}", len).into());
+            }
+            let (value, rest) = raw.split_at(len as usize);
+            raw = rest;
+            ranges.extend(range(&element, value)?);
+        }
+        Ok(Multirange(ranges))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        multirange_element(ty.name()).is_some()
+    }
+}
@@ -0,0 +1,182 @@
+use super::Source;
+use crate::data_order::DataOrder;
+use crate::errors::{ConnectorAgentError, Result};
+use anyhow::anyhow;
+use fehler::{throw, throws};
+use log::debug;
+use std::fmt::Debug;
+
+/// Reads the partitions from different databases, like the shards of a table split over
+/// several hosts by a shard key. Each shard is a source of its own, connected to its host,
+/// and every query runs on exactly one of them. All the shards that get a query have to
+/// return the same columns of the same types.
+pub struct ShardedSource<S: Source> {
+    shards: Vec<S>,
+    routes: Option<Vec<usize>>,
+    queries: Vec<String>,
+    offset: usize,
+    names: Vec<String>,
+    schema: Vec<S::TypeSystem>,
+}
+
+impl<S> ShardedSource<S>
+where
+    S: Source,
+{
+    /// Run the `i`th query on the `i`th shard.
+    pub fn new(shards: Vec<S>) -> Self {
+        ShardedSource {
+            shards,
+            routes: None,
+            queries: vec![],
+            offset: 0,
+            names: vec![],
+            schema: vec![],
+        }
+    }
+
+    /// Run the `i`th query on the shard `routes[i]` instead, for more than one partition per
+    /// shard. The partitions keep the order of the queries. Under
+    /// `Dispatcher::with_partition_subset` the routes are still those of all the queries.
+    pub fn with_routes(mut self, routes: &[usize]) -> Self {
+        self.routes = Some(routes.to_vec());
+        self
+    }
+
+    // The shard of each of the queries set, which are those from `offset` on.
+    #[throws(ConnectorAgentError)]
+    fn routes(&self) -> Vec<usize> {
+        let nqueries = self.offset + self.queries.len();
+        let mut routes = match &self.routes {
+            Some(routes) => routes.clone(),
+            None => (0..nqueries).collect(),
+        };
+        if routes.len() != nqueries {
+            throw!(anyhow!(
+                "{} queries but {} routes to shards",
+                nqueries,
+                routes.len()
+            ));
+        }
+        if let Some(shard) = routes.iter().find(|&&shard| shard >= self.shards.len()) {
+            throw!(anyhow!(
+                "there are {} shards, no shard {}",
+                self.shards.len(),
+                shard
+            ));
+        }
+        routes.split_off(self.offset)
+    }
+}
+
+impl<S> Source for ShardedSource<S>
+where
+    S: Source,
+    S::TypeSystem: PartialEq + Debug,
+{
+    const DATA_ORDERS: &'static [DataOrder] = S::DATA_ORDERS;
+    type Partition = S::Partition;
+    type TypeSystem = S::TypeSystem;
+
+    fn set_data_order(&mut self, data_order: DataOrder) -> Result<()> {
+        for shard in &mut self.shards {
+            shard.set_data_order(data_order)?;
+        }
+        Ok(())
+    }
+
+    fn set_queries<Q: AsRef<str>>(&mut self, queries: &[Q]) {
+        self.queries = queries.iter().map(|q| q.as_ref().to_string()).collect();
+    }
+
+    fn set_query_offset(&mut self, offset: usize) {
+        self.offset = offset;
+    }
+
+    fn set_numeric_coercion(&mut self, coerce: bool) {
+        for shard in &mut self.shards {
+            shard.set_numeric_coercion(coerce);
+        }
+    }
+
+    fn limit_query(&self, query: &str, limit: usize) -> Result<String> {
+        match self.shards.first() {
+            Some(shard) => shard.limit_query(query, limit),
+            None => Ok(query.to_string()),
+        }
+    }
+
+    fn fetch_metadata(&mut self) -> Result<()> {
+        assert!(!self.queries.is_empty());
+
+        let routes = self.routes()?;
+        let mut first = None;
+        for (i, shard) in self.shards.iter_mut().enumerate() {
+            let queries: Vec<_> = routes
+                .iter()
+                .zip(&self.queries)
+                .filter(|(&route, _)| route == i)
+                .map(|(_, q)| q.as_str())
+                .collect();
+            if queries.is_empty() {
+                continue;
+            }
+            debug!(
+                "Fetching metadata of shard {} for {} queries",
+                i,
+                queries.len()
+            );
+            shard.set_queries(&queries);
+            shard.fetch_metadata()?;
+
+            let (names, schema) = (shard.names(), shard.schema());
+            match first {
+                None => {
+                    first = Some(i);
+                    self.names = names;
+                    self.schema = schema;
+                }
+                Some(j) if names != self.names || schema != self.schema => throw!(anyhow!(
+                    "shard {} returns the columns {:?} of types {:?}, but shard {} returns {:?} of types {:?}",
+                    i,
+                    names,
+                    schema,
+                    j,
+                    self.names,
+                    self.schema
+                )),
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.names.clone()
+    }
+
+    fn schema(&self) -> Vec<Self::TypeSystem> {
+        self.schema.clone()
+    }
+
+    fn partition(self) -> Result<Vec<Self::Partition>> {
+        let routes = self.routes()?;
+        let mut partitions = vec![];
+        for (i, shard) in self.shards.into_iter().enumerate() {
+            partitions.push(if routes.contains(&i) {
+                shard.partition()?.into_iter()
+            } else {
+                vec![].into_iter()
+            });
+        }
+        // each shard hands out its partitions in the order of its queries
+        routes
+            .iter()
+            .map(|&i| {
+                partitions[i]
+                    .next()
+                    .ok_or_else(|| anyhow!("shard {} has fewer partitions than queries", i).into())
+            })
+            .collect()
+    }
+}
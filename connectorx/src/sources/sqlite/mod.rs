@@ -5,7 +5,7 @@ use fallible_streaming_iterator::FallibleStreamingIterator;
 use crate::data_order::DataOrder;
 use crate::errors::{ConnectorAgentError, Result};
 use crate::sources::{PartitionParser, Produce, Source, SourcePartition};
-use crate::sql::{count_query, get_limit, limit1_query};
+use crate::sql::{computed_columns_query, count_query, get_limit, limit1_query, limit_query};
 use anyhow::anyhow;
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use derive_more::{Deref, DerefMut};
@@ -14,8 +14,10 @@ use log::debug;
 use owning_ref::OwningHandle;
 use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::types::{Type, ValueRef};
 use rusqlite::{Row, Rows, Statement};
 use sqlparser::dialect::SQLiteDialect;
+use std::convert::TryFrom;
 pub use typesystem::SqliteTypeSystem;
 
 #[derive(Deref, DerefMut)]
@@ -26,6 +28,9 @@ pub struct SqliteSource {
     queries: Vec<String>,
     names: Vec<String>,
     schema: Vec<SqliteTypeSystem>,
+    computed_columns: Vec<(String, String)>,
+    numeric_coercion: bool,
+    diagnostics: bool,
 }
 
 impl SqliteSource {
@@ -40,8 +45,24 @@ impl SqliteSource {
             queries: vec![],
             names: vec![],
             schema: vec![],
+            computed_columns: vec![],
+            numeric_coercion: false,
+            diagnostics: false,
         })
     }
+
+    /// Append a column computed by the SQL expression `expr` (e.g. `a + b`) over the query
+    /// output. Its type is picked up by `fetch_metadata` like any other column.
+    pub fn add_computed_column(&mut self, name: &str, expr: &str) {
+        self.computed_columns
+            .push((name.to_string(), expr.to_string()));
+    }
+
+    /// Fail a cell that does not decode with `ConnectorAgentError::CannotDecode`, which tells
+    /// its column, row and raw bytes. Off by default, since the bytes may be sensitive.
+    pub fn diagnostics(&mut self, enabled: bool) {
+        self.diagnostics = enabled;
+    }
 }
 
 impl Source for SqliteSource
@@ -63,16 +84,37 @@ where
         self.queries = queries.iter().map(|q| q.as_ref().to_string()).collect();
     }
 
+    fn limit_query(&self, query: &str, limit: usize) -> Result<String> {
+        limit_query(query, limit, &SQLiteDialect {})
+    }
+
+    fn set_numeric_coercion(&mut self, coerce: bool) {
+        self.numeric_coercion = coerce;
+    }
+
     fn fetch_metadata(&mut self) -> Result<()> {
         assert!(!self.queries.is_empty());
+
+        if !self.computed_columns.is_empty() {
+            let columns = std::mem::take(&mut self.computed_columns);
+            self.queries = self
+                .queries
+                .iter()
+                .map(|q| computed_columns_query(q, &columns, &SQLiteDialect {}))
+                .collect::<Result<Vec<_>>>()?;
+        }
         let conn = self.pool.get()?;
         let mut success = false;
         let mut zero_tuple = true;
         let mut error = None;
+        let mut types: Vec<Option<SqliteTypeSystem>> = vec![];
         for query in &self.queries {
-            // assuming all the partition queries yield same schema
+            // assuming all the partition queries yield same schema, unless coercing numeric
+            // columns, where the types of every query are looked at. A column null in the
+            // first row of a query without a declared type takes its type from a later query
             let mut names = vec![];
-            let mut types = vec![];
+            let mut query_types = vec![];
+            let mut untyped = None;
 
             match conn.query_row(&limit1_query(query, &SQLiteDialect {})?[..], [], |row| {
                 zero_tuple = false;
@@ -80,12 +122,20 @@ where
                     names.push(col.name().to_string());
                     match row.get_ref(i) {
                         Ok(vr) => {
-                            types.push(SqliteTypeSystem::from((col.decl_type(), vr.data_type())))
+                            match SqliteTypeSystem::try_from((col.decl_type(), vr.data_type())) {
+                                Ok(ty) => query_types.push(Some(ty)),
+                                // a null tells nothing about the type, leave it to the next query
+                                Err(_) if vr.data_type() == Type::Null => query_types.push(None),
+                                Err(e) => {
+                                    untyped = Some(e);
+                                    query_types.push(None);
+                                }
+                            }
                         }
                         Err(e) => {
                             debug!("cannot get ref at {} on query: {}", i, query);
                             error = Some(e);
-                            types.clear(); // clear types and return directly when error occurs
+                            query_types.clear(); // clear types and return directly when error occurs
                         }
                     }
                 });
@@ -101,12 +151,28 @@ where
                     error = Some(e);
                 }
             }
+            if let Some(e) = untyped {
+                throw!(e);
+            }
 
-            if !names.is_empty() && !types.is_empty() {
-                success = true;
-                self.names = names;
-                self.schema = types;
-                break;
+            if !names.is_empty() && !query_types.is_empty() {
+                if success {
+                    for (ty, other) in types.iter_mut().zip(query_types) {
+                        *ty = match (*ty, other) {
+                            (Some(ty), Some(other)) if self.numeric_coercion => {
+                                Some(ty.coerce_numeric(other))
+                            }
+                            (ty, other) => ty.or(other),
+                        };
+                    }
+                } else {
+                    success = true;
+                    self.names = names;
+                    types = query_types;
+                }
+                if !self.numeric_coercion && types.iter().all(Option::is_some) {
+                    break;
+                }
             }
         }
 
@@ -128,6 +194,21 @@ where
             ))
         }
 
+        self.schema = self
+            .names
+            .iter()
+            .zip(types)
+            .map(|(name, ty)| {
+                ty.ok_or_else(|| {
+                    anyhow!(
+                        "column {} has no declared type and is null in every probed row",
+                        name
+                    )
+                    .into()
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
         Ok(())
     }
 
@@ -144,7 +225,12 @@ where
         for query in self.queries {
             let conn = self.pool.get()?;
 
-            ret.push(SqliteSourcePartition::new(conn, &query, &self.schema));
+            let partition = SqliteSourcePartition::new(conn, &query, &self.schema);
+            ret.push(if self.diagnostics {
+                partition.diagnostics(&self.names)
+            } else {
+                partition
+            });
         }
         Ok(ret)
     }
@@ -156,6 +242,7 @@ pub struct SqliteSourcePartition {
     schema: Vec<SqliteTypeSystem>,
     nrows: usize,
     ncols: usize,
+    names: Option<Vec<String>>,
 }
 
 impl SqliteSourcePartition {
@@ -170,8 +257,16 @@ impl SqliteSourcePartition {
             schema: schema.to_vec(),
             nrows: 0,
             ncols: schema.len(),
+            names: None,
         }
     }
+
+    /// Report the cells that do not decode under the column `names`, see
+    /// `SqliteSource::diagnostics`.
+    pub fn diagnostics(mut self, names: &[String]) -> Self {
+        self.names = Some(names.to_vec());
+        self
+    }
 }
 
 impl SourcePartition for SqliteSourcePartition {
@@ -192,7 +287,9 @@ impl SourcePartition for SqliteSourcePartition {
     }
 
     fn parser(&mut self) -> Result<Self::Parser<'_>> {
-        SqliteSourcePartitionParser::new(&self.conn, self.query.as_str(), &self.schema)
+        let parser =
+            SqliteSourcePartitionParser::new(&self.conn, self.query.as_str(), &self.schema)?;
+        Ok(parser.diagnostics(self.names.clone()))
     }
 
     fn nrows(&self) -> usize {
@@ -208,6 +305,8 @@ pub struct SqliteSourcePartitionParser<'a> {
     rows: OwningHandle<Box<Statement<'a>>, DummyBox<Rows<'a>>>,
     ncols: usize,
     current_col: usize,
+    current_row: usize,
+    names: Option<Vec<String>>,
 }
 
 impl<'a> SqliteSourcePartitionParser<'a> {
@@ -229,12 +328,23 @@ impl<'a> SqliteSourcePartitionParser<'a> {
             rows,
             ncols: schema.len(),
             current_col: 0,
+            current_row: 0,
+            names: None,
         })
     }
 
+    /// Report the cells that do not decode under the column `names`, if given.
+    pub fn diagnostics(mut self, names: Option<Vec<String>>) -> Self {
+        self.names = names;
+        self
+    }
+
     fn next_loc(&mut self) -> Result<(&Row, usize)> {
         let row: &Row = match self.current_col {
-            0 => (*self.rows).next()?.ok_or_else(|| anyhow!("Sqlite EOF"))?,
+            0 => {
+                self.current_row += 1;
+                (*self.rows).next()?.ok_or_else(|| anyhow!("Sqlite EOF"))?
+            }
             _ => (*self.rows)
                 .get()
                 .ok_or_else(|| anyhow!("Sqlite empty current row"))?,
@@ -243,28 +353,62 @@ impl<'a> SqliteSourcePartitionParser<'a> {
         self.current_col = (self.current_col + 1) % self.ncols;
         Ok((row, col))
     }
+
+    fn decode_error<T>(
+        &self,
+        col: usize,
+        raw: Option<Vec<u8>>,
+        e: rusqlite::Error,
+    ) -> ConnectorAgentError {
+        let name = self.names.as_ref().map_or("", |names| names[col].as_str());
+        ConnectorAgentError::cannot_decode::<T>(name, self.current_row - 1, raw, e.into())
+    }
 }
 
 impl<'a> PartitionParser<'a> for SqliteSourcePartitionParser<'a> {
     type TypeSystem = SqliteTypeSystem;
 }
 
+// The bytes of a cell as SQLite stores it, numbers as their big-endian bytes.
+fn raw_bytes(value: rusqlite::Result<ValueRef<'_>>) -> Option<Vec<u8>> {
+    match value.ok()? {
+        ValueRef::Null => None,
+        ValueRef::Integer(v) => Some(v.to_be_bytes().to_vec()),
+        ValueRef::Real(v) => Some(v.to_be_bytes().to_vec()),
+        ValueRef::Text(v) | ValueRef::Blob(v) => Some(v.to_vec()),
+    }
+}
+
 macro_rules! impl_produce {
     ($($t: ty,)+) => {
         $(
             impl<'r, 'a> Produce<'r, $t> for SqliteSourcePartitionParser<'a> {
                 fn produce(&'r mut self) -> Result<$t> {
+                    let diagnose = self.names.is_some();
                     let (row, col) = self.next_loc()?;
-                    let val = row.get(col)?;
-                    Ok(val)
+                    match row.get(col) {
+                        Ok(val) => Ok(val),
+                        Err(e) if diagnose => {
+                            let raw = raw_bytes(row.get_ref(col));
+                            Err(self.decode_error::<$t>(col, raw, e))
+                        }
+                        Err(e) => Err(e.into()),
+                    }
                 }
             }
 
             impl<'r, 'a> Produce<'r, Option<$t>> for SqliteSourcePartitionParser<'a> {
                 fn produce(&'r mut self) -> Result<Option<$t>> {
+                    let diagnose = self.names.is_some();
                     let (row, col) = self.next_loc()?;
-                    let val = row.get(col)?;
-                    Ok(val)
+                    match row.get(col) {
+                        Ok(val) => Ok(val),
+                        Err(e) if diagnose => {
+                            let raw = raw_bytes(row.get_ref(col));
+                            Err(self.decode_error::<Option<$t>>(col, raw, e))
+                        }
+                        Err(e) => Err(e.into()),
+                    }
                 }
             }
         )+
@@ -1,7 +1,11 @@
+use crate::errors::ConnectorAgentError;
+use anyhow::anyhow;
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use fehler::{throw, throws};
 use rusqlite::types::Type;
+use std::convert::TryFrom;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum SqliteTypeSystem {
     Bool(bool),
     Int8(bool),
@@ -31,20 +35,45 @@ impl_typesystem! {
     }
 }
 
-impl From<Type> for SqliteTypeSystem {
-    fn from(ty: Type) -> SqliteTypeSystem {
+impl SqliteTypeSystem {
+    /// The type of a column holding values of both `self` and `other` when both are numeric:
+    /// the wider integer, or real if either is real. `self` for any other pair.
+    pub fn coerce_numeric(self, other: SqliteTypeSystem) -> SqliteTypeSystem {
+        use SqliteTypeSystem::*;
+        let rank = |ty| match ty {
+            Int2(_) => Some(0),
+            Int4(_) => Some(1),
+            Int8(_) => Some(2),
+            Real(_) => Some(3),
+            _ => None,
+        };
+        match (rank(self), rank(other)) {
+            (Some(a), Some(b)) if b > a => other,
+            _ => self,
+        }
+    }
+}
+
+impl TryFrom<Type> for SqliteTypeSystem {
+    type Error = ConnectorAgentError;
+
+    #[throws(ConnectorAgentError)]
+    fn try_from(ty: Type) -> SqliteTypeSystem {
         use SqliteTypeSystem::*;
         match ty {
             Type::Integer => Int8(true),
             Type::Real => Real(true),
             Type::Text => Text(true),
-            _ => unimplemented!("{}", ty),
+            _ => throw!(anyhow!("cannot tell a column type from a {} value", ty)),
         }
     }
 }
 
-impl From<(Option<&str>, Type)> for SqliteTypeSystem {
-    fn from(types: (Option<&str>, Type)) -> SqliteTypeSystem {
+impl TryFrom<(Option<&str>, Type)> for SqliteTypeSystem {
+    type Error = ConnectorAgentError;
+
+    #[throws(ConnectorAgentError)]
+    fn try_from(types: (Option<&str>, Type)) -> SqliteTypeSystem {
         use SqliteTypeSystem::*;
         match types {
             // derive from column's declare type, some rules refer to:
@@ -66,11 +95,11 @@ impl From<(Option<&str>, Type)> for SqliteTypeSystem {
                         Real(true)
                     }
                     _ if s.contains("blob") => Blob(true),
-                    _ => ty.into(),
+                    _ => SqliteTypeSystem::try_from(ty)?,
                 }
             }
             // derive from value type directly if no declare type available
-            (None, ty) => ty.into(),
+            (None, ty) => SqliteTypeSystem::try_from(ty)?,
         }
     }
 }
@@ -0,0 +1,443 @@
+mod typesystem;
+
+use crate::data_order::DataOrder;
+use crate::errors::{ConnectorAgentError, Result};
+use crate::http::{json_response, HttpClient};
+use crate::sources::{PartitionParser, Produce, Source, SourcePartition};
+use anyhow::anyhow;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use fehler::{throw, throws};
+use hyper::{Method, StatusCode};
+use log::debug;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+pub use typesystem::TrinoTypeSystem;
+use uuid::Uuid;
+
+/// Reads the results of queries run by a Trino (or Presto) coordinator over its HTTP
+/// protocol, one partition per page of results. Trino hands out the pages one after another,
+/// each response pointing to the next, so `fetch_metadata` runs the queries to the end and
+/// keeps all the pages in memory; the partitions then only parse them.
+pub struct TrinoSource {
+    endpoint: String,
+    headers: Vec<(String, String)>,
+    poll_interval: Duration,
+    queries: Vec<String>,
+    names: Vec<String>,
+    schema: Vec<TrinoTypeSystem>,
+    pages: Vec<Vec<Vec<Value>>>,
+}
+
+impl TrinoSource {
+    /// Run the queries as `user` on the coordinator at `endpoint`, like
+    /// `http://localhost:8080`.
+    pub fn new(endpoint: &str, user: &str) -> Self {
+        TrinoSource {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            headers: vec![("X-Trino-User".to_string(), user.to_string())],
+            poll_interval: Duration::from_millis(50),
+            queries: vec![],
+            names: vec![],
+            schema: vec![],
+            pages: vec![],
+        }
+    }
+
+    /// Send `name: value` with every request, e.g. `X-Trino-Catalog: hive` to set the
+    /// catalog, or `X-Presto-User: <user>` for a Presto coordinator.
+    pub fn header(&mut self, name: &str, value: &str) {
+        self.headers.push((name.to_string(), value.to_string()));
+    }
+
+    /// How long to wait before asking again while a query has no results yet, or when the
+    /// coordinator is busy. 50ms by default.
+    pub fn poll_interval(&mut self, interval: Duration) {
+        self.poll_interval = interval;
+    }
+
+    // Run `query` to the end and return its columns and pages of rows.
+    #[throws(ConnectorAgentError)]
+    fn run_query(
+        &self,
+        client: &HttpClient,
+        query: &str,
+    ) -> (Vec<(String, TrinoTypeSystem)>, Vec<Vec<Vec<Value>>>) {
+        let mut columns = None;
+        let mut pages = vec![];
+        let mut resp = self.send(
+            client,
+            Method::POST,
+            &format!("{}/v1/statement", self.endpoint),
+            query,
+        )?;
+        loop {
+            if let Some(error) = resp.get("error") {
+                throw!(anyhow!(
+                    "Trino query failed: {}",
+                    error.get("message").and_then(Value::as_str).unwrap_or("")
+                ));
+            }
+            if columns.is_none() {
+                if let Some(cols) = resp.get("columns") {
+                    columns = Some(parse_columns(cols)?);
+                }
+            }
+            let rows = match resp.get_mut("data").map(Value::take) {
+                Some(Value::Array(rows)) => rows,
+                _ => vec![],
+            };
+
+            let next = match resp.get("nextUri").and_then(Value::as_str) {
+                Some(next) => next.to_string(),
+                None => {
+                    pages.push(rows);
+                    break;
+                }
+            };
+            if rows.is_empty() {
+                // still queued or running, with nothing to hand out yet
+                debug!(
+                    "query {} is {}",
+                    resp.get("id").and_then(Value::as_str).unwrap_or(""),
+                    lookup_state(&resp)
+                );
+                thread::sleep(self.poll_interval);
+            } else {
+                pages.push(rows);
+            }
+            resp = self.send(client, Method::GET, &next, "")?;
+        }
+
+        let columns = columns.ok_or_else(|| anyhow!("query returned no columns: {}", query))?;
+        let mut parsed = vec![];
+        for page in pages.into_iter().filter(|p| !p.is_empty()) {
+            let mut rows = Vec::with_capacity(page.len());
+            for row in page {
+                match row {
+                    Value::Array(row) if row.len() == columns.len() => rows.push(row),
+                    row => throw!(anyhow!(
+                        "expecting a row of {} values, got {}",
+                        columns.len(),
+                        row
+                    )),
+                }
+            }
+            parsed.push(rows);
+        }
+        (columns, parsed)
+    }
+
+    // Send a request, asking again for as long as the coordinator is busy.
+    #[throws(ConnectorAgentError)]
+    fn send(&self, client: &HttpClient, method: Method, uri: &str, body: &str) -> Value {
+        loop {
+            let (status, resp) = client.request(method.clone(), uri, &self.headers, body)?;
+            if status == StatusCode::SERVICE_UNAVAILABLE {
+                thread::sleep(self.poll_interval);
+                continue;
+            }
+            break json_response(uri, status, &resp)?;
+        }
+    }
+}
+
+impl Source for TrinoSource {
+    const DATA_ORDERS: &'static [DataOrder] = &[DataOrder::RowMajor];
+    type Partition = TrinoSourcePartition;
+    type TypeSystem = TrinoTypeSystem;
+
+    #[throws(ConnectorAgentError)]
+    fn set_data_order(&mut self, data_order: DataOrder) {
+        if !matches!(data_order, DataOrder::RowMajor) {
+            throw!(ConnectorAgentError::UnsupportedDataOrder(data_order))
+        }
+    }
+
+    fn set_queries<Q: AsRef<str>>(&mut self, queries: &[Q]) {
+        self.queries = queries.iter().map(|q| q.as_ref().to_string()).collect();
+    }
+
+    fn fetch_metadata(&mut self) -> Result<()> {
+        assert!(!self.queries.is_empty());
+
+        let client = HttpClient::new()?;
+        let mut columns: Option<Vec<(String, TrinoTypeSystem)>> = None;
+        let mut pages = vec![];
+        for query in &self.queries {
+            let (cols, query_pages) = self.run_query(&client, query)?;
+            match &columns {
+                Some(first) if *first != cols => throw!(anyhow!(
+                    "the queries return different columns: {:?} and {:?}",
+                    first,
+                    cols
+                )),
+                Some(_) => {}
+                None => columns = Some(cols),
+            }
+            pages.extend(query_pages);
+        }
+        debug!("{} pages from {} queries", pages.len(), self.queries.len());
+
+        let (names, schema) = columns.unwrap_or_default().into_iter().unzip();
+        self.names = names;
+        self.schema = schema;
+        self.pages = pages;
+        Ok(())
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.names.clone()
+    }
+
+    fn schema(&self) -> Vec<Self::TypeSystem> {
+        self.schema.clone()
+    }
+
+    fn partition(self) -> Result<Vec<Self::Partition>> {
+        let ncols = self.names.len();
+        Ok(self
+            .pages
+            .into_iter()
+            .map(|rows| TrinoSourcePartition::new(rows, ncols))
+            .collect())
+    }
+}
+
+#[throws(ConnectorAgentError)]
+fn parse_columns(cols: &Value) -> Vec<(String, TrinoTypeSystem)> {
+    let cols = cols
+        .as_array()
+        .ok_or_else(|| anyhow!("expecting an array of columns, got {}", cols))?;
+    let mut columns = vec![];
+    for col in cols {
+        let name = col.get("name").and_then(Value::as_str);
+        let ty = col.get("type").and_then(Value::as_str);
+        match (name, ty) {
+            (Some(name), Some(ty)) => {
+                let dt = TrinoTypeSystem::from_type_name(ty)
+                    .ok_or_else(|| anyhow!("column {} has the unsupported type {}", name, ty))?;
+                columns.push((name.to_string(), dt));
+            }
+            _ => throw!(anyhow!(
+                "expecting a column with a name and a type, got {}",
+                col
+            )),
+        }
+    }
+    columns
+}
+
+fn lookup_state(resp: &Value) -> &str {
+    resp.get("stats")
+        .and_then(|stats| stats.get("state"))
+        .and_then(Value::as_str)
+        .unwrap_or("UNKNOWN")
+}
+
+pub struct TrinoSourcePartition {
+    rows: Vec<Vec<Value>>,
+    counter: usize,
+    nrows: usize,
+    ncols: usize,
+}
+
+impl TrinoSourcePartition {
+    fn new(rows: Vec<Vec<Value>>, ncols: usize) -> Self {
+        Self {
+            nrows: rows.len(),
+            rows,
+            counter: 0,
+            ncols,
+        }
+    }
+}
+
+impl SourcePartition for TrinoSourcePartition {
+    type TypeSystem = TrinoTypeSystem;
+    type Parser<'a> = TrinoSourcePartitionParser<'a>;
+
+    // the page was fetched with the metadata
+    fn prepare(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    fn parser(&mut self) -> Result<Self::Parser<'_>> {
+        Ok(TrinoSourcePartitionParser {
+            rows: &self.rows,
+            counter: &mut self.counter,
+            ncols: self.ncols,
+        })
+    }
+}
+
+pub struct TrinoSourcePartitionParser<'a> {
+    rows: &'a [Vec<Value>],
+    counter: &'a mut usize,
+    ncols: usize,
+}
+
+impl<'a> TrinoSourcePartitionParser<'a> {
+    fn next_val(&mut self) -> &'a Value {
+        let v = &self.rows[*self.counter / self.ncols][*self.counter % self.ncols];
+        *self.counter += 1;
+
+        v
+    }
+}
+
+impl<'a> PartitionParser<'a> for TrinoSourcePartitionParser<'a> {
+    type TypeSystem = TrinoTypeSystem;
+}
+
+// How a value of the JSON Trino sends is read, None if it does not hold a `Self`.
+trait FromJson: Sized {
+    fn from_json(v: &Value) -> Option<Self>;
+}
+
+impl FromJson for bool {
+    fn from_json(v: &Value) -> Option<Self> {
+        v.as_bool()
+    }
+}
+
+macro_rules! impl_from_json_int {
+    ($($t: ty),+) => {
+        $(
+            impl FromJson for $t {
+                fn from_json(v: &Value) -> Option<Self> {
+                    <$t>::try_from(v.as_i64()?).ok()
+                }
+            }
+        )+
+    };
+}
+
+impl_from_json_int!(i8, i16, i32, i64);
+
+impl FromJson for f64 {
+    fn from_json(v: &Value) -> Option<Self> {
+        match v {
+            Value::Number(n) => n.as_f64(),
+            // NaN and the infinities are sent as strings
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromJson for f32 {
+    fn from_json(v: &Value) -> Option<Self> {
+        f64::from_json(v).map(|f| f as f32)
+    }
+}
+
+impl FromJson for Decimal {
+    fn from_json(v: &Value) -> Option<Self> {
+        Decimal::from_str(v.as_str()?).ok()
+    }
+}
+
+impl FromJson for String {
+    fn from_json(v: &Value) -> Option<Self> {
+        match v {
+            Value::String(s) => Some(s.clone()),
+            // arrays, maps and rows keep their JSON text
+            v => Some(v.to_string()),
+        }
+    }
+}
+
+impl FromJson for NaiveDate {
+    fn from_json(v: &Value) -> Option<Self> {
+        NaiveDate::parse_from_str(v.as_str()?, "%Y-%m-%d").ok()
+    }
+}
+
+impl FromJson for NaiveTime {
+    fn from_json(v: &Value) -> Option<Self> {
+        NaiveTime::parse_from_str(v.as_str()?, "%H:%M:%S%.f").ok()
+    }
+}
+
+impl FromJson for NaiveDateTime {
+    fn from_json(v: &Value) -> Option<Self> {
+        NaiveDateTime::parse_from_str(v.as_str()?, "%Y-%m-%d %H:%M:%S%.f").ok()
+    }
+}
+
+impl FromJson for DateTime<Utc> {
+    // Trino names the zone after the timestamp, like `UTC` or `+01:00`. Other names like
+    // `Europe/Berlin` cannot be read, convert those with `at_timezone(ts, 'UTC')`.
+    fn from_json(v: &Value) -> Option<Self> {
+        let s = v.as_str()?;
+        let split = s.rfind(' ')?;
+        match &s[split + 1..] {
+            "UTC" | "Z" => NaiveDateTime::from_json(&Value::from(&s[..split]))
+                .map(|ts| DateTime::from_utc(ts, Utc)),
+            _ => DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f %:z")
+                .ok()
+                .map(|ts| ts.with_timezone(&Utc)),
+        }
+    }
+}
+
+impl FromJson for Uuid {
+    fn from_json(v: &Value) -> Option<Self> {
+        Uuid::parse_str(v.as_str()?).ok()
+    }
+}
+
+macro_rules! impl_produce {
+    ($($t: ty,)+) => {
+        $(
+            impl<'r, 'a> Produce<'r, $t> for TrinoSourcePartitionParser<'a> {
+                fn produce(&'r mut self) -> Result<$t> {
+                    let v = self.next_val();
+                    <$t>::from_json(v)
+                        .ok_or_else(|| ConnectorAgentError::cannot_produce::<$t>(Some(v.to_string())))
+                }
+            }
+
+            impl<'r, 'a> Produce<'r, Option<$t>> for TrinoSourcePartitionParser<'a> {
+                fn produce(&'r mut self) -> Result<Option<$t>> {
+                    let v = self.next_val();
+                    if v.is_null() {
+                        return Ok(None);
+                    }
+                    <$t>::from_json(v).map(Some).ok_or_else(|| {
+                        ConnectorAgentError::cannot_produce::<$t>(Some(v.to_string()))
+                    })
+                }
+            }
+        )+
+    };
+}
+
+impl_produce!(
+    bool,
+    i8,
+    i16,
+    i32,
+    i64,
+    f32,
+    f64,
+    Decimal,
+    String,
+    NaiveDate,
+    NaiveTime,
+    NaiveDateTime,
+    DateTime<Utc>,
+    Uuid,
+);
@@ -0,0 +1,81 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+/// The types of Trino (and Presto) columns. Trino does not tell whether a column can hold
+/// nulls, so all of them are nullable.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TrinoTypeSystem {
+    Boolean(bool),
+    TinyInt(bool),
+    SmallInt(bool),
+    Integer(bool),
+    BigInt(bool),
+    Real(bool),
+    Double(bool),
+    Decimal(bool),
+    Varchar(bool),
+    Varbinary(bool),
+    Date(bool),
+    Time(bool),
+    Timestamp(bool),
+    TimestampTz(bool),
+    Json(bool),
+    Uuid(bool),
+    IpAddress(bool),
+}
+
+impl_typesystem! {
+    system = TrinoTypeSystem,
+    mappings = {
+        { Boolean => bool }
+        { TinyInt => i8 }
+        { SmallInt => i16 }
+        { Integer => i32 }
+        { BigInt => i64 }
+        { Real => f32 }
+        { Double => f64 }
+        { Decimal => Decimal }
+        { Varchar | Varbinary | Json | IpAddress => String }
+        { Date => NaiveDate }
+        { Time => NaiveTime }
+        { Timestamp => NaiveDateTime }
+        { TimestampTz => DateTime<Utc> }
+        { Uuid => Uuid }
+    }
+}
+
+impl TrinoTypeSystem {
+    /// The type of a column from its type as Trino writes it, like `varchar(10)` or
+    /// `timestamp(3) with time zone`. Arrays, maps and rows are read as `Json`, a `varbinary`
+    /// as the base64 text Trino sends and a `time with time zone` as text. None if the type
+    /// is not supported.
+    pub fn from_type_name(ty: &str) -> Option<Self> {
+        use TrinoTypeSystem::*;
+        let ty = ty.trim().to_lowercase();
+        let with_tz = ty.ends_with(" with time zone");
+        let base = ty.split(&['(', ' '][..]).next().unwrap_or_default();
+        let dt = match base {
+            "boolean" => Boolean(true),
+            "tinyint" => TinyInt(true),
+            "smallint" => SmallInt(true),
+            "integer" | "int" => Integer(true),
+            "bigint" => BigInt(true),
+            "real" => Real(true),
+            "double" => Double(true),
+            "decimal" => Decimal(true),
+            "varchar" | "char" => Varchar(true),
+            "varbinary" => Varbinary(true),
+            "date" => Date(true),
+            "time" if with_tz => Varchar(true),
+            "time" => Time(true),
+            "timestamp" if with_tz => TimestampTz(true),
+            "timestamp" => Timestamp(true),
+            "json" | "array" | "map" | "row" => Json(true),
+            "uuid" => Uuid(true),
+            "ipaddress" => IpAddress(true),
+            _ => return None,
+        };
+        Some(dt)
+    }
+}
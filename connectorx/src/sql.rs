@@ -3,8 +3,8 @@ use anyhow::anyhow;
 use fehler::{throw, throws};
 use log::{debug, trace};
 use sqlparser::ast::{
-    BinaryOperator, Expr, Function, FunctionArg, Ident, ObjectName, Query, Select, SelectItem,
-    SetExpr, Statement, TableAlias, TableFactor, TableWithJoins, Value,
+    BinaryOperator, DataType, Expr, Function, FunctionArg, Ident, ObjectName, Query, Select,
+    SelectItem, SetExpr, Statement, TableAlias, TableFactor, TableWithJoins, Value,
 };
 use sqlparser::dialect::Dialect;
 use sqlparser::parser::Parser;
@@ -133,6 +133,49 @@ pub fn limit1_query<T: Dialect>(sql: &str, dialect: &T) -> String {
     sql
 }
 
+/// Rewrite `sql` to return at most `limit` rows, keeping a smaller limit it already has. A
+/// limit that is not a number is kept by wrapping the query.
+#[throws(ConnectorAgentError)]
+pub fn limit_query<T: Dialect>(sql: &str, limit: usize, dialect: &T) -> String {
+    trace!("Incoming query: {}", sql);
+
+    let mut ast = Parser::parse_sql(dialect, sql)?;
+    if ast.len() != 1 {
+        throw!(ConnectorAgentError::SQLQueryNotSupported(sql.to_string()));
+    }
+
+    let limit_expr = |n: usize| Some(Expr::Value(Value::Number(n.to_string(), false)));
+    let ast_limit = match ast.remove(0) {
+        Statement::Query(mut q) => match q.limit.as_ref().map(|e| e.to_string().parse()) {
+            None => {
+                q.limit = limit_expr(limit);
+                Statement::Query(q)
+            }
+            Some(Ok(n)) => {
+                q.limit = limit_expr(limit.min(n));
+                Statement::Query(q)
+            }
+            Some(Err(_)) => {
+                let mut wrapped = wrap_query(
+                    q,
+                    vec![SelectItem::Wildcard],
+                    None,
+                    String::from("CXTMPTAB_LIMIT"),
+                );
+                if let Statement::Query(wrapped) = &mut wrapped {
+                    wrapped.limit = limit_expr(limit);
+                }
+                wrapped
+            }
+        },
+        _ => throw!(ConnectorAgentError::SQLQueryNotSupported(sql.to_string())),
+    };
+
+    let sql = format!("{}", ast_limit);
+    debug!("Transformed limit {} query: {}", limit, sql);
+    sql
+}
+
 #[throws(ConnectorAgentError)]
 pub fn single_col_partition_query<T: Dialect>(
     query: &str,
@@ -349,3 +392,231 @@ pub fn get_partition_range_query_sep<T: Dialect>(
     );
     (sql_min, sql_max)
 }
+
+// ctid is a physical column of a single base table, so partitioning on it only works when the
+// query reads straight from one table.
+#[throws(ConnectorAgentError)]
+fn single_table_select<'a>(ast: &'a mut [Statement], query: &str) -> &'a mut Select {
+    if ast.len() != 1 {
+        throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string()));
+    }
+
+    match &mut ast[0] {
+        Statement::Query(q) => match &mut q.body {
+            SetExpr::Select(select)
+                if select.from.len() == 1
+                    && select.from[0].joins.is_empty()
+                    && matches!(select.from[0].relation, TableFactor::Table { .. }) =>
+            {
+                &mut **select
+            }
+            _ => throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string())),
+        },
+        _ => throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string())),
+    }
+}
+
+/// Query the `relkind` of the table read by `query` as text, whether other tables inherit
+/// from it, and its number of heap blocks as an int8.
+#[throws(ConnectorAgentError)]
+pub fn ctid_table_query<T: Dialect>(query: &str, dialect: &T) -> String {
+    trace!("Incoming query: {}", query);
+
+    let mut ast = Parser::parse_sql(dialect, query)?;
+    let select = single_table_select(&mut ast, query)?;
+    let table = match &select.from[0].relation {
+        TableFactor::Table { name, .. } => name.to_string(),
+        _ => unreachable!(),
+    };
+
+    let sql = format!(
+        "SELECT c.relkind::text, \
+                EXISTS (SELECT 1 FROM pg_inherits WHERE inhparent = c.oid), \
+                pg_relation_size(c.oid) / current_setting('block_size')::int8 \
+         FROM pg_class c WHERE c.oid = '{}'::regclass",
+        table.replace('\'', "''")
+    );
+    debug!("Transformed ctid table query: {}", sql);
+    sql
+}
+
+/// Restrict `query` to the tuples stored in heap blocks `[lower, upper)`, or from `lower` on
+/// without an `upper`.
+#[throws(ConnectorAgentError)]
+pub fn ctid_partition_query<T: Dialect>(
+    query: &str,
+    lower: i64,
+    upper: Option<i64>,
+    dialect: &T,
+) -> String {
+    trace!("Incoming query: {}", query);
+
+    let mut ast = Parser::parse_sql(dialect, query)?;
+    let select = single_table_select(&mut ast, query)?;
+
+    let tid = |block: i64| Expr::Cast {
+        expr: Box::new(Expr::Value(Value::SingleQuotedString(format!(
+            "({},0)",
+            block
+        )))),
+        data_type: DataType::Custom(ObjectName(vec![Ident {
+            value: "tid".to_string(),
+            quote_style: None,
+        }])),
+    };
+    let ctid = || {
+        Box::new(Expr::Identifier(Ident {
+            value: "ctid".to_string(),
+            quote_style: None,
+        }))
+    };
+
+    let mut selection = Expr::BinaryOp {
+        left: ctid(),
+        op: BinaryOperator::GtEq,
+        right: Box::new(tid(lower)),
+    };
+    if let Some(upper) = upper {
+        selection = Expr::BinaryOp {
+            left: Box::new(selection),
+            op: BinaryOperator::And,
+            right: Box::new(Expr::BinaryOp {
+                left: ctid(),
+                op: BinaryOperator::Lt,
+                right: Box::new(tid(upper)),
+            }),
+        };
+    }
+    if let Some(expr) = select.selection.take() {
+        selection = Expr::BinaryOp {
+            left: Box::new(Expr::Nested(Box::new(expr))),
+            op: BinaryOperator::And,
+            right: Box::new(selection),
+        };
+    }
+    select.selection = Some(selection);
+
+    let sql = format!("{}", ast[0]);
+    debug!("Transformed ctid partition query: {}", sql);
+    sql
+}
+
+#[throws(ConnectorAgentError)]
+fn parse_expr<T: Dialect>(expr: &str, dialect: &T) -> Expr {
+    // parse as `SELECT <expr>` so that trailing garbage after the expression is caught as well
+    let mut ast = Parser::parse_sql(dialect, &format!("SELECT {}", expr))?;
+    match ast.pop() {
+        Some(Statement::Query(q)) if ast.is_empty() => match q.body {
+            SetExpr::Select(mut select)
+                if select.from.is_empty() && select.projection.len() == 1 =>
+            {
+                match select.projection.pop() {
+                    Some(SelectItem::UnnamedExpr(e)) => e,
+                    _ => throw!(anyhow!("not a single expression: {}", expr)),
+                }
+            }
+            _ => throw!(anyhow!("not a single expression: {}", expr)),
+        },
+        _ => throw!(anyhow!("not a single expression: {}", expr)),
+    }
+}
+
+/// Append the `(name, expr)` columns to the output of `query`. The expressions are evaluated on
+/// the output columns of `query`.
+#[throws(ConnectorAgentError)]
+pub fn computed_columns_query<T: Dialect>(
+    query: &str,
+    columns: &[(String, String)],
+    dialect: &T,
+) -> String {
+    trace!("Incoming query: {}", query);
+    const COMP_TMP_TAB_NAME: &str = "CXTMPTAB_COMP";
+
+    let mut ast = Parser::parse_sql(dialect, query)?;
+    if ast.len() != 1 {
+        throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string()));
+    }
+
+    let mut projection = vec![SelectItem::QualifiedWildcard(ObjectName(vec![Ident {
+        value: COMP_TMP_TAB_NAME.to_string(),
+        quote_style: None,
+    }]))];
+    for (name, expr) in columns {
+        projection.push(SelectItem::ExprWithAlias {
+            expr: parse_expr(expr, dialect)?,
+            alias: Ident {
+                value: name.clone(),
+                quote_style: Some('"'),
+            },
+        });
+    }
+
+    let ast_comp = match &mut ast[0] {
+        Statement::Query(q) => {
+            wrap_query(q.clone(), projection, None, COMP_TMP_TAB_NAME.to_string())
+        }
+        _ => throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string())),
+    };
+
+    let sql = format!("{}", ast_comp);
+    debug!("Transformed computed columns query: {}", sql);
+    sql
+}
+
+/// Keep only the output `columns` of `query`, in the given order, casting those marked in
+/// `as_text` to text under their own names.
+#[throws(ConnectorAgentError)]
+pub fn select_columns_query<T: Dialect>(
+    query: &str,
+    columns: &[String],
+    as_text: &[bool],
+    dialect: &T,
+) -> String {
+    trace!("Incoming query: {}", query);
+    const SEL_TMP_TAB_NAME: &str = "CXTMPTAB_SEL";
+
+    let mut ast = Parser::parse_sql(dialect, query)?;
+    if ast.len() != 1 {
+        throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string()));
+    }
+
+    let projection = columns
+        .iter()
+        .zip(as_text)
+        .map(|(name, &as_text)| {
+            let name = Ident {
+                value: name.clone(),
+                quote_style: Some('"'),
+            };
+            let column = Expr::CompoundIdentifier(vec![
+                Ident {
+                    value: SEL_TMP_TAB_NAME.to_string(),
+                    quote_style: None,
+                },
+                name.clone(),
+            ]);
+            if as_text {
+                SelectItem::ExprWithAlias {
+                    expr: Expr::Cast {
+                        expr: Box::new(column),
+                        data_type: DataType::Text,
+                    },
+                    alias: name,
+                }
+            } else {
+                SelectItem::UnnamedExpr(column)
+            }
+        })
+        .collect();
+
+    let ast_sel = match &mut ast[0] {
+        Statement::Query(q) => {
+            wrap_query(q.clone(), projection, None, SEL_TMP_TAB_NAME.to_string())
+        }
+        _ => throw!(ConnectorAgentError::SQLQueryNotSupported(query.to_string())),
+    };
+
+    let sql = format!("{}", ast_sel);
+    debug!("Transformed select columns query: {}", sql);
+    sql
+}
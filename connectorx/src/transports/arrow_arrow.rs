@@ -0,0 +1,18 @@
+use crate::destinations::arrow::ArrowDestination;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::sources::arrow::ArrowSource;
+use crate::typesystem::TypeConversion;
+
+pub struct ArrowArrowTransport;
+
+impl_transport!(
+    name = ArrowArrowTransport,
+    systems = DummyTypeSystem => DummyTypeSystem,
+    route = ArrowSource => ArrowDestination,
+    mappings = {
+        { F64[f64]       => F64[f64]       | conversion all}
+        { I64[i64]       => I64[i64]       | conversion all}
+        { Bool[bool]     => Bool[bool]     | conversion all}
+        { String[String] => String[String] | conversion all}
+    }
+);
@@ -0,0 +1,32 @@
+use crate::destinations::aggregate::AggregateDestination;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::sources::dummy::DummySource;
+use crate::typesystem::TypeConversion;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+pub struct DummyAggregateTransport;
+
+impl_transport!(
+    name = DummyAggregateTransport,
+    systems = DummyTypeSystem => DummyTypeSystem,
+    route = DummySource => AggregateDestination,
+    mappings = {
+        { F64[f64]                => F64[f64]                | conversion all}
+        { I64[i64]                => I64[i64]                | conversion all}
+        { Bool[bool]              => Bool[bool]              | conversion all}
+        { String[String]          => String[String]          | conversion all}
+        { DateTime[DateTime<Utc>] => DateTime[DateTime<Utc>] | conversion all}
+    }
+);
+
+impl TypeConversion<NaiveDateTime, DateTime<Utc>> for DummyAggregateTransport {
+    fn convert(val: NaiveDateTime) -> DateTime<Utc> {
+        DateTime::from_utc(val, Utc)
+    }
+}
+
+impl TypeConversion<NaiveDate, DateTime<Utc>> for DummyAggregateTransport {
+    fn convert(val: NaiveDate) -> DateTime<Utc> {
+        DateTime::from_utc(val.and_hms(0, 0, 0), Utc)
+    }
+}
@@ -0,0 +1,20 @@
+use crate::destinations::arrow::ArrowDestination;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::sources::gsheets::GSheetsSource;
+use crate::typesystem::TypeConversion;
+use chrono::{DateTime, Utc};
+
+pub struct GSheetsArrowTransport;
+
+impl_transport!(
+    name = GSheetsArrowTransport,
+    systems = DummyTypeSystem => DummyTypeSystem,
+    route = GSheetsSource => ArrowDestination,
+    mappings = {
+        { F64[f64]                => F64[f64]                | conversion all}
+        { I64[i64]                => I64[i64]                | conversion all}
+        { Bool[bool]              => Bool[bool]              | conversion all}
+        { String[String]          => String[String]          | conversion all}
+        { DateTime[DateTime<Utc>] => DateTime[DateTime<Utc>] | conversion all}
+    }
+);
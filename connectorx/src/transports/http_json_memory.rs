@@ -0,0 +1,20 @@
+use crate::destinations::memory::MemoryDestination;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::sources::http_json::HttpJsonSource;
+use crate::typesystem::TypeConversion;
+use chrono::{DateTime, Utc};
+
+pub struct HttpJsonMemoryTransport;
+
+impl_transport!(
+    name = HttpJsonMemoryTransport,
+    systems = DummyTypeSystem => DummyTypeSystem,
+    route = HttpJsonSource => MemoryDestination,
+    mappings = {
+        { F64[f64]                => F64[f64]                | conversion all}
+        { I64[i64]                => I64[i64]                | conversion all}
+        { Bool[bool]              => Bool[bool]              | conversion all}
+        { String[String]          => String[String]          | conversion all}
+        { DateTime[DateTime<Utc>] => DateTime[DateTime<Utc>] | conversion all}
+    }
+);
@@ -1,13 +1,37 @@
+mod arrow_arrow;
 mod csv_arrow;
 mod csv_memory;
+mod dummy_aggregate;
 mod dummy_arrow;
+mod dummy_callback;
 mod dummy_memory;
+mod gsheets_arrow;
+mod gsheets_memory;
+mod http_json_arrow;
+mod http_json_memory;
+mod postgres_aggregate;
 mod postgres_arrow;
+mod postgres_callback;
+mod postgres_long_format;
 mod postgres_memory;
+mod trino_arrow;
+mod trino_memory;
 
+pub use arrow_arrow::ArrowArrowTransport;
 pub use csv_arrow::CSVArrowTransport;
 pub use csv_memory::CSVMemoryTransport;
+pub use dummy_aggregate::DummyAggregateTransport;
 pub use dummy_arrow::DummyArrowTransport;
+pub use dummy_callback::DummyCallbackTransport;
 pub use dummy_memory::DummyMemoryTransport;
+pub use gsheets_arrow::GSheetsArrowTransport;
+pub use gsheets_memory::GSheetsMemoryTransport;
+pub use http_json_arrow::HttpJsonArrowTransport;
+pub use http_json_memory::HttpJsonMemoryTransport;
+pub use postgres_aggregate::PostgresAggregateTransport;
 pub use postgres_arrow::PostgresArrowTransport;
+pub use postgres_callback::PostgresCallbackTransport;
+pub use postgres_long_format::PostgresLongFormatTransport;
 pub use postgres_memory::PostgresMemoryTransport;
+pub use trino_arrow::TrinoArrowTransport;
+pub use trino_memory::TrinoMemoryTransport;
@@ -1,6 +1,8 @@
 use crate::destinations::arrow::ArrowDestination;
-use crate::dummy_typesystem::DummyTypeSystem;
-use crate::sources::postgres::{Binary, PostgresSource, PostgresTypeSystem};
+use crate::dummy_typesystem::{Circle, DummyTypeSystem, Point, Record, Snapshot};
+use crate::sources::postgres::{
+    Binary, JsonPathStr, Multirange, PostgresSource, PostgresTypeSystem, RegOid, Tid, Xid8,
+};
 use crate::typesystem::TypeConversion;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use uuid::Uuid;
@@ -26,16 +28,56 @@ impl_transport!(
         { Date[NaiveDate]            => DateTime[DateTime<Utc>] | conversion half }
         { UUID[Uuid]                 => String[String]          | conversion half }
         { Char[&'r str]              => String[String]          | conversion none}
+        { JsonPath[JsonPathStr]      => String[String]          | conversion half }
+        { RegOid[RegOid]             => I64[i64]                | conversion half }
+        { Point[Point]               => Point[Point]            | conversion all }
+        { Circle[Circle]             => Circle[Circle]          | conversion all }
+        { Xid8[Xid8]                 => U64[u64]                | conversion half }
+        { Tid[Tid]                   => String[String]          | conversion half }
+        { Snapshot[Snapshot]         => Snapshot[Snapshot]      | conversion all }
+        { TextArray[Vec<Option<String>>] => StringList[Vec<Option<String>>] | conversion all }
+        { Multirange[Multirange]     => StringList[Vec<Option<String>>] | conversion half }
+        { Composite[Record]          => Record[Record]          | conversion all }
         // { Time[NaiveTime]            => String[String]          | conversion half }
     }
 );
 
+impl TypeConversion<Multirange, Vec<Option<String>>> for PostgresArrowTransport {
+    fn convert(val: Multirange) -> Vec<Option<String>> {
+        val.0.iter().map(|range| Some(range.to_string())).collect()
+    }
+}
+
 impl TypeConversion<Uuid, String> for PostgresArrowTransport {
     fn convert(val: Uuid) -> String {
         val.to_string()
     }
 }
 
+impl TypeConversion<JsonPathStr, String> for PostgresArrowTransport {
+    fn convert(val: JsonPathStr) -> String {
+        val.0
+    }
+}
+
+impl TypeConversion<RegOid, i64> for PostgresArrowTransport {
+    fn convert(val: RegOid) -> i64 {
+        val.0 as i64
+    }
+}
+
+impl TypeConversion<Xid8, u64> for PostgresArrowTransport {
+    fn convert(val: Xid8) -> u64 {
+        val.0
+    }
+}
+
+impl TypeConversion<Tid, String> for PostgresArrowTransport {
+    fn convert(val: Tid) -> String {
+        val.to_string()
+    }
+}
+
 impl TypeConversion<NaiveTime, String> for PostgresArrowTransport {
     fn convert(val: NaiveTime) -> String {
         val.to_string()
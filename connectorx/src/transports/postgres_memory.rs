@@ -1,6 +1,8 @@
 use crate::destinations::memory::MemoryDestination;
 use crate::dummy_typesystem::DummyTypeSystem;
-use crate::sources::postgres::{Binary, PostgresSource, PostgresTypeSystem, CSV};
+use crate::sources::postgres::{
+    Binary, JsonPathStr, PostgresSource, PostgresTypeSystem, RegOid, CSV,
+};
 use crate::typesystem::TypeConversion;
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use std::marker::PhantomData;
@@ -50,6 +52,8 @@ impl_transport!(
         { Date[NaiveDate]            => DateTime[DateTime<Utc>] | conversion half }
         { UUID[Uuid]                 => String[String]          | conversion half }
         { Char[&'r str]              => String[String]          | conversion none }
+        { JsonPath[JsonPathStr]      => String[String]          | conversion half }
+        { RegOid[RegOid]             => I64[i64]                | conversion half }
         // { Time[NaiveTime]            => String[String]          | conversion half }
     }
 );
@@ -60,6 +64,18 @@ impl<P> TypeConversion<Uuid, String> for PostgresMemoryTransport<P> {
     }
 }
 
+impl<P> TypeConversion<JsonPathStr, String> for PostgresMemoryTransport<P> {
+    fn convert(val: JsonPathStr) -> String {
+        val.0
+    }
+}
+
+impl<P> TypeConversion<RegOid, i64> for PostgresMemoryTransport<P> {
+    fn convert(val: RegOid) -> i64 {
+        val.0 as i64
+    }
+}
+
 impl<P> TypeConversion<NaiveTime, String> for PostgresMemoryTransport<P> {
     fn convert(val: NaiveTime) -> String {
         val.to_string()
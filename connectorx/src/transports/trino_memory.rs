@@ -0,0 +1,66 @@
+use crate::destinations::memory::MemoryDestination;
+use crate::dummy_typesystem::DummyTypeSystem;
+use crate::sources::trino::{TrinoSource, TrinoTypeSystem};
+use crate::typesystem::TypeConversion;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use num_traits::ToPrimitive;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+pub struct TrinoMemoryTransport;
+
+impl_transport!(
+    name = TrinoMemoryTransport,
+    systems = TrinoTypeSystem => DummyTypeSystem,
+    route = TrinoSource => MemoryDestination,
+    mappings = {
+        { Boolean[bool]                 => Bool[bool]              | conversion all }
+        { TinyInt[i8]                   => I64[i64]                | conversion all }
+        { SmallInt[i16]                 => I64[i64]                | conversion all }
+        { Integer[i32]                  => I64[i64]                | conversion all }
+        { BigInt[i64]                   => I64[i64]                | conversion all }
+        { Real[f32]                     => F64[f64]                | conversion all }
+        { Double[f64]                   => F64[f64]                | conversion all }
+        { Decimal[Decimal]              => F64[f64]                | conversion half }
+        { Varchar[String]               => String[String]          | conversion all }
+        { Varbinary[String]             => String[String]          | conversion none }
+        { Json[String]                  => String[String]          | conversion none }
+        { IpAddress[String]             => String[String]          | conversion none }
+        { Date[NaiveDate]               => DateTime[DateTime<Utc>] | conversion half }
+        { Time[NaiveTime]               => String[String]          | conversion half }
+        { Timestamp[NaiveDateTime]      => DateTime[DateTime<Utc>] | conversion half }
+        { TimestampTz[DateTime<Utc>]    => DateTime[DateTime<Utc>] | conversion all }
+        { Uuid[Uuid]                    => String[String]          | conversion half }
+    }
+);
+
+impl TypeConversion<Decimal, f64> for TrinoMemoryTransport {
+    fn convert(val: Decimal) -> f64 {
+        val.to_f64()
+            .unwrap_or_else(|| panic!("cannot convert decimal {:?} to float64", val))
+    }
+}
+
+impl TypeConversion<NaiveDate, DateTime<Utc>> for TrinoMemoryTransport {
+    fn convert(val: NaiveDate) -> DateTime<Utc> {
+        DateTime::from_utc(val.and_hms(0, 0, 0), Utc)
+    }
+}
+
+impl TypeConversion<NaiveTime, String> for TrinoMemoryTransport {
+    fn convert(val: NaiveTime) -> String {
+        val.to_string()
+    }
+}
+
+impl TypeConversion<NaiveDateTime, DateTime<Utc>> for TrinoMemoryTransport {
+    fn convert(val: NaiveDateTime) -> DateTime<Utc> {
+        DateTime::from_utc(val, Utc)
+    }
+}
+
+impl TypeConversion<Uuid, String> for TrinoMemoryTransport {
+    fn convert(val: Uuid) -> String {
+        val.to_string()
+    }
+}
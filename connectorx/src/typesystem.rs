@@ -84,6 +84,19 @@ pub trait Transport {
         dst: &'r mut <Self::D as Destination>::Partition<'d>,
     ) -> Result<()>;
 
+    /// Same as `process`, but also tells whether the value written was null. The ones
+    /// `impl_transport!` generates do; by default no value is reported as null, so the
+    /// required columns of other transports are not checked.
+    fn process_check_null<'s, 'd, 'r>(
+        ts1: Self::TSS,
+        ts2: Self::TSD,
+        src: &'r mut <<Self::S as Source>::Partition as SourcePartition>::Parser<'s>,
+        dst: &'r mut <Self::D as Destination>::Partition<'d>,
+    ) -> Result<bool> {
+        Self::process(ts1, ts2, src, dst)?;
+        Ok(false)
+    }
+
     #[allow(clippy::type_complexity)]
     fn processor<'s, 'd>(
         ts1: Self::TSS,
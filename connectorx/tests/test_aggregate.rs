@@ -0,0 +1,144 @@
+use connectorx::{
+    destinations::{
+        aggregate::{Aggregate, AggregateDestination},
+        memory::Value,
+    },
+    impl_transport,
+    sources::{
+        dummy::DummySource,
+        sqlite::{SqliteSource, SqliteTypeSystem},
+    },
+    transports::DummyAggregateTransport,
+    Dispatcher, DummyTypeSystem, TypeConversion,
+};
+use rusqlite::{types::Value as SqliteValue, Connection};
+use std::env;
+use std::fs;
+
+struct SqliteAggregateTransport;
+
+impl_transport!(
+    name = SqliteAggregateTransport,
+    systems = SqliteTypeSystem => DummyTypeSystem,
+    route = SqliteSource => AggregateDestination,
+    mappings = {
+        { Int8[i64]      => I64[i64]       | conversion all }
+        { Real[f64]      => F64[f64]       | conversion all }
+        { Text[Box<str>] => String[String] | conversion half }
+    }
+);
+
+impl TypeConversion<Box<str>, String> for SqliteAggregateTransport {
+    fn convert(val: Box<str>) -> String {
+        val.to_string()
+    }
+}
+
+fn sales_db() -> String {
+    let path = env::temp_dir().join(format!("aggregate_{}.db", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE sales(id INTEGER NOT NULL, region TEXT, qty INTEGER, price REAL);
+         INSERT INTO sales VALUES
+            (0, 'north', 3, 1.5), (1, 'south', NULL, 2.0), (2, 'north', 5, NULL),
+            (3, NULL, 1, 4.0), (4, 'south', 2, 0.5), (5, 'east', NULL, NULL),
+            (6, NULL, 7, 3.0), (7, 'north', 4, 2.5), (8, 'south', 6, 1.0);",
+    )
+    .unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+fn to_value(v: SqliteValue) -> Value {
+    match v {
+        SqliteValue::Null => Value::Null,
+        SqliteValue::Integer(i) => Value::I64(i),
+        SqliteValue::Real(f) => Value::F64(f),
+        SqliteValue::Text(s) => Value::String(s),
+        SqliteValue::Blob(_) => unreachable!(),
+    }
+}
+
+fn sorted(mut rows: Vec<Vec<Value>>) -> Vec<Vec<Value>> {
+    rows.sort_by_key(|row| format!("{:?}", row));
+    rows
+}
+
+#[test]
+fn test_aggregate_matches_group_by() {
+    let db = sales_db();
+    let queries = [
+        "SELECT region, qty, price FROM sales WHERE id < 4",
+        "SELECT region, qty, price FROM sales WHERE id >= 4",
+    ];
+
+    let mut destination = AggregateDestination::new(
+        &["region"],
+        &[
+            ("qty", Aggregate::Sum),
+            ("qty", Aggregate::Count),
+            ("price", Aggregate::Min),
+            ("price", Aggregate::Max),
+            ("qty", Aggregate::Avg),
+        ],
+    );
+    Dispatcher::<_, _, SqliteAggregateTransport>::new(
+        SqliteSource::new(&db, 2).unwrap(),
+        &mut destination,
+        &queries,
+    )
+    .run()
+    .expect("run dispatcher");
+
+    assert_eq!(
+        vec![
+            "region",
+            "sum(qty)",
+            "count(qty)",
+            "min(price)",
+            "max(price)",
+            "avg(qty)"
+        ],
+        destination.names()
+    );
+    let streamed = destination.finish().unwrap();
+
+    let conn = Connection::open(&db).unwrap();
+    let mut stmt = conn
+        .prepare(
+            "SELECT region, sum(qty), count(qty), min(price), max(price), avg(qty) \
+             FROM sales GROUP BY region",
+        )
+        .unwrap();
+    let expected: Vec<Vec<Value>> = stmt
+        .query_map([], |row| {
+            (0..6)
+                .map(|i| row.get::<_, SqliteValue>(i).map(to_value))
+                .collect()
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(4, streamed.len());
+    assert_eq!(sorted(expected), sorted(streamed));
+}
+
+#[test]
+fn test_aggregate_unsupported() {
+    let schema = [DummyTypeSystem::I64(false), DummyTypeSystem::String(true)];
+    for (group_by, aggregate) in [
+        ("c", ("a", Aggregate::Sum)),
+        ("a", ("c", Aggregate::Count)),
+        ("a", ("b", Aggregate::Sum)),
+    ] {
+        let mut destination = AggregateDestination::new(&[group_by], &[aggregate]);
+        let result = Dispatcher::<_, _, DummyAggregateTransport>::new(
+            DummySource::new(&["a", "b"], &schema),
+            &mut destination,
+            &["2,2"],
+        )
+        .run();
+        assert!(result.is_err());
+    }
+}
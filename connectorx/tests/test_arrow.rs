@@ -1,8 +1,12 @@
-use arrow::array::{BooleanArray, Float64Array, Int64Array, LargeStringArray};
+use arrow::array::{Array, BooleanArray, Float64Array, Int64Array, LargeStringArray, StructArray};
+use arrow::datatypes::{DataType, Field};
 use arrow::record_batch::RecordBatch;
 use connectorx::{
-    destinations::arrow::ArrowDestination, sources::dummy::DummySource,
-    transports::DummyArrowTransport, Dispatcher, DummyTypeSystem,
+    destinations::arrow::ArrowDestination,
+    dummy_typesystem::{Record, RecordType, RecordValue},
+    sources::dummy::DummySource,
+    transports::DummyArrowTransport,
+    DataOrder, Destination, DestinationPartition, Dispatcher, DummyTypeSystem,
 };
 
 #[test]
@@ -112,3 +116,226 @@ fn test_arrow() {
         }
     }
 }
+
+#[test]
+fn test_arrow_batches() {
+    let schema = [DummyTypeSystem::I64(false), DummyTypeSystem::String(true)];
+    let nrows = vec![4, 7];
+    let ncols = schema.len();
+    let headers = vec!["a".to_string(), "b".to_string()];
+    let queries: Vec<String> = nrows.iter().map(|v| format!("{},{}", v, ncols)).collect();
+    let mut destination = ArrowDestination::new();
+    assert!(destination.batch_size(0).is_err());
+    destination.batch_size(3).unwrap();
+    let dispatcher = Dispatcher::<_, _, DummyArrowTransport>::new(
+        DummySource::new(&["a", "b"], &schema),
+        &mut destination,
+        &queries,
+    );
+    dispatcher.run().expect("run dispatcher");
+
+    let records: Vec<RecordBatch> = destination
+        .batches(headers)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(
+        vec![3, 1, 3, 3, 1],
+        records.iter().map(|r| r.num_rows()).collect::<Vec<_>>()
+    );
+    assert_eq!(11, records.iter().map(|r| r.num_rows()).sum::<usize>());
+
+    let expected: Vec<Vec<i64>> = vec![
+        vec![0, 1, 2],
+        vec![3],
+        vec![0, 1, 2],
+        vec![3, 4, 5],
+        vec![6],
+    ];
+    for (record, values) in records.iter().zip(expected) {
+        assert!(record
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .eq(&Int64Array::from(values)));
+    }
+}
+
+#[test]
+fn test_run_to_arrow() {
+    let schema = [DummyTypeSystem::I64(false), DummyTypeSystem::String(true)];
+    let nrows = vec![4, 7];
+    let ncols = schema.len();
+    let queries: Vec<String> = nrows.iter().map(|v| format!("{},{}", v, ncols)).collect();
+
+    let mut destination = ArrowDestination::new();
+    destination.batch_size(3).unwrap();
+    let dispatcher = Dispatcher::<_, _, DummyArrowTransport>::new(
+        DummySource::new(&["a", "b"], &schema),
+        &mut destination,
+        &queries,
+    );
+    dispatcher.run().expect("run dispatcher");
+    let expected = destination
+        .finish(vec!["a".to_string(), "b".to_string()])
+        .unwrap();
+
+    let records = Dispatcher::<_, _, DummyArrowTransport>::to_arrow(
+        DummySource::new(&["a", "b"], &schema),
+        &queries,
+    )
+    .batch_size(3)
+    .run()
+    .expect("run to arrow");
+
+    assert_eq!(expected.len(), records.len());
+    for (e, r) in expected.iter().zip(&records) {
+        assert_eq!(e.schema(), r.schema());
+        assert_eq!(e.columns(), r.columns());
+    }
+
+    let records = Dispatcher::<_, _, DummyArrowTransport>::run_to_arrow(
+        DummySource::new(&["a", "b"], &schema),
+        &queries,
+    )
+    .expect("run to arrow");
+    assert_eq!(2, records.len());
+    assert_eq!(
+        vec![4, 7],
+        records.iter().map(|r| r.num_rows()).collect::<Vec<_>>()
+    );
+
+    let result = Dispatcher::<_, _, DummyArrowTransport>::to_arrow(
+        DummySource::new(&["a", "b"], &schema),
+        &queries,
+    )
+    .batch_size(0)
+    .run();
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_arrow_validity() {
+    let schema = [DummyTypeSystem::I64(false), DummyTypeSystem::Bool(true)];
+    let queries = ["5,2"];
+    let mut destination = ArrowDestination::new();
+    let dispatcher = Dispatcher::<_, _, DummyArrowTransport>::new(
+        DummySource::new(&["a", "b"], &schema),
+        &mut destination,
+        &queries,
+    );
+    dispatcher.run().expect("run dispatcher");
+
+    let records = destination
+        .finish(vec!["a".to_string(), "b".to_string()])
+        .unwrap();
+    assert_eq!(1, records.len());
+
+    let not_null = records[0].column(0);
+    assert_eq!(0, not_null.null_count());
+    assert!(not_null.data().null_buffer().is_none());
+    assert!(!records[0].schema().field(0).is_nullable());
+
+    let nullable = records[0].column(1);
+    assert!(nullable.null_count() > 0);
+    assert!(nullable.data().null_buffer().is_some());
+    assert!(records[0].schema().field(1).is_nullable());
+}
+
+fn person(name: &str, home: Option<(&str, i64)>) -> Record {
+    Record {
+        fields: vec![
+            ("name".to_string(), RecordType::String),
+            (
+                "home".to_string(),
+                RecordType::Record(vec![
+                    ("street".to_string(), RecordType::String),
+                    ("zip".to_string(), RecordType::I64),
+                ]),
+            ),
+        ]
+        .into(),
+        values: vec![
+            RecordValue::String(name.to_string()),
+            home.map_or(RecordValue::Null, |(street, zip)| {
+                RecordValue::Record(vec![
+                    RecordValue::String(street.to_string()),
+                    RecordValue::I64(zip),
+                ])
+            }),
+        ],
+    }
+}
+
+#[test]
+fn test_arrow_records() {
+    let mut destination = ArrowDestination::new();
+    destination
+        .allocate(
+            5,
+            &["p"],
+            &[DummyTypeSystem::Record(true)],
+            DataOrder::RowMajor,
+        )
+        .unwrap();
+    let mut partitions = destination.partition(&[3, 2]).unwrap();
+    partitions[0]
+        .write(Some(person("ann", Some(("main st", 12345)))))
+        .unwrap();
+    partitions[0].write(None::<Record>).unwrap();
+    partitions[0].write(Some(person("bob", None))).unwrap();
+    // the second partition never sees the fields
+    partitions[1].write(None::<Record>).unwrap();
+    partitions[1].write(None::<Record>).unwrap();
+    drop(partitions);
+
+    let records = destination.finish(vec!["p".to_string()]).unwrap();
+    assert_eq!(2, records.len());
+    let home = DataType::Struct(vec![
+        Field::new("street", DataType::LargeUtf8, true),
+        Field::new("zip", DataType::Int64, true),
+    ]);
+    assert_eq!(
+        &DataType::Struct(vec![
+            Field::new("name", DataType::LargeUtf8, true),
+            Field::new("home", home, true),
+        ]),
+        records[0].schema().field(0).data_type()
+    );
+
+    let people = records[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StructArray>()
+        .unwrap();
+    assert!(people.is_valid(0) && people.is_null(1) && people.is_valid(2));
+    let names = people
+        .column_by_name("name")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<LargeStringArray>()
+        .unwrap();
+    assert_eq!(("ann", "bob"), (names.value(0), names.value(2)));
+
+    // a null home of a person is a null nested struct, not a struct of null fields
+    let homes = people
+        .column_by_name("home")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<StructArray>()
+        .unwrap();
+    assert!(homes.is_valid(0) && homes.is_null(2));
+    let zips = homes
+        .column_by_name("zip")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(12345, zips.value(0));
+
+    let nobody = records[1].column(0);
+    assert_eq!(people.data_type(), nobody.data_type());
+    assert_eq!(2, nobody.null_count());
+}
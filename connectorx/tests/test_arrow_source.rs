@@ -0,0 +1,229 @@
+use arrow::array::{Array, ArrayRef, Float64Array, Int64Array, LargeStringArray, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use connectorx::{
+    destinations::arrow::ArrowDestination,
+    sources::arrow::{ArrowSource, ArrowSourcePartition},
+    transports::ArrowArrowTransport,
+    ConnectorAgentError, Dispatcher, SourcePartition,
+};
+use std::env;
+use std::fs::File;
+use std::sync::Arc;
+
+type Row = (i64, Option<f64>, Option<String>);
+
+fn schema(string_type: DataType) -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("x", DataType::Float64, true),
+        Field::new("s", string_type, true),
+    ]))
+}
+
+fn batch(schema: &Arc<Schema>, rows: &[Row]) -> RecordBatch {
+    let ids: ArrayRef = Arc::new(Int64Array::from(
+        rows.iter().map(|r| r.0).collect::<Vec<_>>(),
+    ));
+    let xs: ArrayRef = Arc::new(Float64Array::from(
+        rows.iter().map(|r| r.1).collect::<Vec<_>>(),
+    ));
+    let strings: Vec<Option<&str>> = rows.iter().map(|r| r.2.as_deref()).collect();
+    let ss: ArrayRef = match schema.field(2).data_type() {
+        DataType::LargeUtf8 => Arc::new(LargeStringArray::from(strings)),
+        _ => Arc::new(StringArray::from(strings)),
+    };
+    RecordBatch::try_new(schema.clone(), vec![ids, xs, ss]).unwrap()
+}
+
+fn row(id: i64, x: Option<f64>, s: Option<&str>) -> Row {
+    (id, x, s.map(|s| s.to_string()))
+}
+
+// Two files, the first with batches of 3 and 2 rows, the second with a single batch of 4.
+fn write_files(name: &str, string_type: DataType) -> (Vec<String>, Vec<Row>) {
+    let schema = schema(string_type);
+    let rows = vec![
+        row(0, Some(0.5), Some("a")),
+        row(1, None, Some("b")),
+        row(2, Some(2.5), None),
+        row(3, Some(3.5), Some("c")),
+        row(4, Some(4.5), Some("d")),
+        row(5, Some(5.5), Some("e")),
+        row(6, None, None),
+        row(7, Some(7.5), Some("f")),
+        row(8, Some(8.5), Some("g")),
+    ];
+
+    let mut files = vec![];
+    for (i, batches) in [vec![&rows[0..3], &rows[3..5]], vec![&rows[5..9]]]
+        .iter()
+        .enumerate()
+    {
+        let path = env::temp_dir().join(format!("{}_{}_{}.arrow", name, i, std::process::id()));
+        let mut writer = FileWriter::try_new(File::create(&path).unwrap(), &schema).unwrap();
+        for rows in batches {
+            writer.write(&batch(&schema, rows)).unwrap();
+        }
+        writer.finish().unwrap();
+        files.push(path.to_str().unwrap().to_string());
+    }
+    (files, rows)
+}
+
+fn rows(batches: &[RecordBatch]) -> Vec<Row> {
+    let mut rows = vec![];
+    for batch in batches {
+        let ids = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        let xs = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        let ss = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<LargeStringArray>()
+            .unwrap();
+        for r in 0..batch.num_rows() {
+            rows.push((
+                ids.value(r),
+                if xs.is_null(r) {
+                    None
+                } else {
+                    Some(xs.value(r))
+                },
+                if ss.is_null(r) {
+                    None
+                } else {
+                    Some(ss.value(r).to_string())
+                },
+            ));
+        }
+    }
+    rows
+}
+
+fn run(files: &[String], cell_by_cell: bool) -> Vec<RecordBatch> {
+    let mut destination = ArrowDestination::new();
+    let mut dispatcher =
+        Dispatcher::<_, _, ArrowArrowTransport>::new(ArrowSource::new(), &mut destination, files);
+    if cell_by_cell {
+        dispatcher = dispatcher.cell_by_cell();
+    }
+    dispatcher.run().expect("run dispatcher");
+    destination
+        .finish(vec!["id".into(), "x".into(), "s".into()])
+        .unwrap()
+}
+
+#[test]
+fn test_arrow_source_moves_batches() {
+    let (files, expected) = write_files("moves_batches", DataType::LargeUtf8);
+
+    // the batches of the files are kept as they are
+    let batches = run(&files, false);
+    assert_eq!(
+        vec![3, 2, 4],
+        batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>()
+    );
+    assert_eq!(expected, rows(&batches));
+
+    // while going cell by cell makes one batch per partition
+    let batches = run(&files, true);
+    assert_eq!(
+        vec![5, 4],
+        batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>()
+    );
+    assert_eq!(expected, rows(&batches));
+}
+
+#[test]
+fn test_arrow_source_batch_size() {
+    let (files, expected) = write_files("batch_size", DataType::LargeUtf8);
+
+    let batches = Dispatcher::<_, _, ArrowArrowTransport>::to_arrow(ArrowSource::new(), &files)
+        .batch_size(2)
+        .run()
+        .expect("run dispatcher");
+    assert_eq!(
+        vec![2, 1, 2, 2, 2],
+        batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>()
+    );
+    assert_eq!(expected, rows(&batches));
+}
+
+#[test]
+fn test_arrow_source_keeps_source_batches() {
+    let (files, expected) = write_files("keep_batches", DataType::LargeUtf8);
+
+    let mut source_batches = vec![];
+    for file in &files {
+        let mut partition = ArrowSourcePartition::new(file, 3);
+        partition.prepare().unwrap();
+        source_batches.extend(partition.batch_rows());
+    }
+    assert_eq!(vec![3, 2, 4], source_batches);
+
+    let batches = Dispatcher::<_, _, ArrowArrowTransport>::to_arrow(ArrowSource::new(), &files)
+        .batch_size(2)
+        .keep_source_batches()
+        .run()
+        .expect("run dispatcher");
+    assert_eq!(
+        source_batches,
+        batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>()
+    );
+    assert_eq!(expected, rows(&batches));
+
+    // the batch size still applies to partitions read cell by cell
+    let mut destination = ArrowDestination::new();
+    destination.batch_size(2).unwrap();
+    destination.keep_source_batches();
+    Dispatcher::<_, _, ArrowArrowTransport>::new(ArrowSource::new(), &mut destination, &files)
+        .cell_by_cell()
+        .run()
+        .expect("run dispatcher");
+    let batches = destination
+        .finish(vec!["id".into(), "x".into(), "s".into()])
+        .unwrap();
+    assert_eq!(
+        vec![2, 2, 1, 2, 2],
+        batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_arrow_source_falls_back_to_cells() {
+    // the destination stores strings as LargeUtf8, so Utf8 batches cannot be taken as they are
+    let (files, expected) = write_files("falls_back", DataType::Utf8);
+
+    let batches = run(&files, false);
+    assert_eq!(
+        vec![5, 4],
+        batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>()
+    );
+    assert_eq!(expected, rows(&batches));
+}
+
+#[test]
+fn test_arrow_source_required_column() {
+    let (files, _) = write_files("required", DataType::LargeUtf8);
+
+    let mut destination = ArrowDestination::new();
+    let dispatcher =
+        Dispatcher::<_, _, ArrowArrowTransport>::new(ArrowSource::new(), &mut destination, &files)
+            .with_required_columns(&["x"]);
+    match dispatcher.run() {
+        Err(ConnectorAgentError::UnexpectedNull { col, row }) => {
+            assert_eq!("x", col);
+            assert!(row == 1 || row == 6, "row {}", row);
+        }
+        r => panic!("expected an unexpected null, got {:?}", r),
+    }
+}
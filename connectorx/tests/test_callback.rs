@@ -0,0 +1,96 @@
+use connectorx::{
+    destinations::callback::CallbackDestination, sources::dummy::DummySource,
+    transports::DummyCallbackTransport, ConnectorAgentError, Dispatcher, DummyTypeSystem,
+};
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn test_callback_sum() {
+    let schema = [DummyTypeSystem::I64(false), DummyTypeSystem::String(true)];
+    let nrows = [10, 5, 7];
+    let queries: Vec<String> = nrows.iter().map(|n| format!("{},2", n)).collect();
+
+    let sum = Arc::new(AtomicI64::new(0));
+    let seen = Arc::new(AtomicUsize::new(0));
+    let (s, n) = (Arc::clone(&sum), Arc::clone(&seen));
+    let mut destination = CallbackDestination::new(move |row| {
+        let col = row.index("a").unwrap();
+        s.fetch_add(row.i64(col)?.unwrap(), Ordering::Relaxed);
+        assert_eq!(
+            Some(row.i64(col)?.unwrap().to_string().as_str()),
+            row.str(1)?
+        );
+        n.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    });
+
+    Dispatcher::<_, _, DummyCallbackTransport>::new(
+        DummySource::new(&["a", "b"], &schema),
+        &mut destination,
+        &queries,
+    )
+    .run()
+    .expect("run dispatcher");
+
+    // every partition counts 0..n in its first column
+    assert_eq!(45 + 10 + 21, sum.load(Ordering::Relaxed));
+    assert_eq!(22, seen.load(Ordering::Relaxed));
+}
+
+#[test]
+fn test_callback_serialized() {
+    let schema = [DummyTypeSystem::I64(false), DummyTypeSystem::F64(false)];
+    let queries = ["3,2", "2,2"];
+
+    let rows = Arc::new(Mutex::new(vec![]));
+    let out = Arc::clone(&rows);
+    let mut total = 0;
+    let mut destination = CallbackDestination::serialized(move |row| {
+        total += row.i64(0)?.unwrap();
+        out.lock().unwrap().push((row.f64(1)?.unwrap(), total));
+        Ok(())
+    });
+
+    Dispatcher::<_, _, DummyCallbackTransport>::new(
+        DummySource::new(&["a", "b"], &schema),
+        &mut destination,
+        &queries,
+    )
+    .sequential()
+    .run()
+    .expect("run dispatcher");
+
+    assert_eq!(
+        vec![(0.0, 0), (1.0, 1), (2.0, 3), (0.0, 3), (1.0, 4)],
+        *rows.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_callback_errors() {
+    let schema = [DummyTypeSystem::I64(false)];
+
+    // a wrongly typed accessor fails the run
+    let mut destination = CallbackDestination::new(|row| {
+        row.bool(0)?;
+        Ok(())
+    });
+    let res = Dispatcher::<_, _, DummyCallbackTransport>::new(
+        DummySource::new(&["a"], &schema),
+        &mut destination,
+        &["3,1"],
+    )
+    .run();
+    assert!(matches!(res, Err(ConnectorAgentError::TypeCheckFailed(..))));
+
+    // and so does an error from the closure itself
+    let mut destination = CallbackDestination::new(|_| Err(anyhow::anyhow!("enough").into()));
+    let res = Dispatcher::<_, _, DummyCallbackTransport>::new(
+        DummySource::new(&["a"], &schema),
+        &mut destination,
+        &["3,1"],
+    )
+    .run();
+    assert_eq!("enough", res.unwrap_err().to_string());
+}
@@ -1,6 +1,8 @@
 use connectorx::sources::{csv::CSVSource, Produce, Source, SourcePartition};
 use connectorx::{destinations::memory::MemoryDestination, Destination};
-use connectorx::{transports::CSVMemoryTransport, Dispatcher, DummyTypeSystem};
+use connectorx::{
+    transports::CSVMemoryTransport, ConnectorAgentError, Dispatcher, DummyTypeSystem,
+};
 use ndarray::array;
 
 #[test]
@@ -144,3 +146,26 @@ fn test_csv_infer_schema() {
 
     assert_eq!(expected_schema, writer.schema());
 }
+
+#[test]
+fn test_csv_required_columns() {
+    let files = ["./tests/data/infer_0.csv"];
+
+    let mut writer = MemoryDestination::new();
+    let dispatcher =
+        Dispatcher::<_, _, CSVMemoryTransport>::new(CSVSource::new(&[]), &mut writer, &files)
+            .with_required_columns(&["c0".to_string(), "c6".to_string()]);
+    dispatcher.run().expect("run dispatcher");
+
+    let mut writer = MemoryDestination::new();
+    let dispatcher =
+        Dispatcher::<_, _, CSVMemoryTransport>::new(CSVSource::new(&[]), &mut writer, &files)
+            .with_required_columns(&["c0".to_string(), "c2".to_string()]);
+    match dispatcher.run() {
+        Err(ConnectorAgentError::UnexpectedNull { col, row }) => {
+            assert_eq!("c2", col);
+            assert_eq!(1, row);
+        }
+        r => panic!("expected an unexpected null, got {:?}", r),
+    }
+}
@@ -0,0 +1,65 @@
+use connectorx::{DecimalFormat, DecimalRounding};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+#[test]
+fn test_decimal_rounding() {
+    let values: Vec<Decimal> = ["1.2345", "1.235", "-1.235", "1.225", "1.2"]
+        .iter()
+        .map(|v| Decimal::from_str(v).unwrap())
+        .collect();
+
+    let cases = [
+        (
+            DecimalRounding::HalfUp,
+            ["1.23", "1.24", "-1.24", "1.23", "1.2"],
+        ),
+        (
+            DecimalRounding::HalfEven,
+            ["1.23", "1.24", "-1.24", "1.22", "1.2"],
+        ),
+        (
+            DecimalRounding::Down,
+            ["1.23", "1.23", "-1.23", "1.22", "1.2"],
+        ),
+        (
+            DecimalRounding::Up,
+            ["1.24", "1.24", "-1.24", "1.23", "1.2"],
+        ),
+    ];
+
+    for (rounding, expected) in &cases {
+        let rounded: Vec<String> = values
+            .iter()
+            .map(|&v| rounding.rescale(v, 2).to_string())
+            .collect();
+        assert_eq!(expected.to_vec(), rounded, "{:?}", rounding);
+    }
+
+    assert_eq!(DecimalRounding::HalfEven, DecimalRounding::default());
+}
+
+#[test]
+fn test_decimal_format() {
+    let d = |v: &str| Some(Decimal::from_str(v).unwrap());
+
+    let german = DecimalFormat::new(',', Some('.')).unwrap();
+    assert_eq!(d("1234567.89"), german.parse("1.234.567,89"));
+    assert_eq!(d("-0.5"), german.parse("-0,5"));
+    assert_eq!(d("12"), german.parse(" 12 "));
+    assert_eq!(None, german.parse("1,2,3"));
+
+    // without grouping a `.` is not taken for anything
+    let comma = DecimalFormat::new(',', None).unwrap();
+    assert_eq!(d("1.5"), comma.parse("1,5"));
+    assert_eq!(None, comma.parse("1.5"));
+
+    let swiss = DecimalFormat::new('.', Some('\'')).unwrap();
+    assert_eq!(d("1234567.89"), swiss.parse("1'234'567.89"));
+
+    let plain = DecimalFormat::default();
+    assert_eq!(d("1234567.89"), plain.parse("1234567.89"));
+    assert_eq!(None, plain.parse("1,234,567.89"));
+
+    assert!(DecimalFormat::new(',', Some(',')).is_err());
+}
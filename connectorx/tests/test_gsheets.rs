@@ -0,0 +1,292 @@
+use arrow::array::TimestampMillisecondArray;
+use chrono::{TimeZone, Utc};
+use connectorx::{
+    destinations::{
+        arrow::ArrowDestination,
+        memory::{MemoryDestination, Value},
+    },
+    sources::{
+        gsheets::{parse_datetime, serial_to_datetime, DateTimeRender, GSheetsSource},
+        Source,
+    },
+    transports::{GSheetsArrowTransport, GSheetsMemoryTransport},
+    Destination, Dispatcher, DummyTypeSystem,
+};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+mod common;
+use common::serve;
+
+// Serve `respond(path_and_query, headers)` as the `spreadsheets` collection of the Sheets
+// API, counting the requests into `requests`.
+fn serve_api<F>(requests: Arc<AtomicUsize>, respond: F) -> String
+where
+    F: Fn(&str, &[String]) -> (u16, String) + Send + 'static,
+{
+    let base = serve(move |_, _, path, headers, _| {
+        requests.fetch_add(1, Ordering::SeqCst);
+        respond(path, headers)
+    });
+    format!("{}/v4/spreadsheets", base)
+}
+
+// A sheet whose ranges A1:E3 and A4:E5 share the header in the first row of the sheet.
+fn serve_sheet(requests: Arc<AtomicUsize>) -> String {
+    serve_api(requests, |path, headers| {
+        if !headers.iter().any(|h| h == "authorization: bearer token") {
+            return (401, r#"{"error": {"code": 401}}"#.to_string());
+        }
+        if !path.starts_with("/v4/spreadsheets/sheet-id/values/") {
+            return (404, "{}".to_string());
+        }
+        let values = if path.contains("A1:E3") {
+            json!([
+                ["id", "score", "joined", "ok", "note"],
+                [1, 1.5, "2021-03-04", true, "x"],
+                // the API leaves out the empty cells at the end of a row
+                ["", 2, "3/5/2021 10:20:30", false],
+            ])
+        } else if path.contains("A4:E5") {
+            json!([
+                [3, 3.25, "2021-04-01", true, "y"],
+                [4, 4, "2021-04-02", false, "z"],
+            ])
+        } else {
+            return (400, r#"{"error": {"code": 400}}"#.to_string());
+        };
+        (200, json!({ "values": values }).to_string())
+    })
+}
+
+#[test]
+fn test_gsheets() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let mut source = GSheetsSource::new("sheet-id", "token", &[]);
+    source.api_url(&serve_sheet(requests.clone()));
+
+    let mut destination = MemoryDestination::new();
+    Dispatcher::<_, _, GSheetsMemoryTransport>::new(
+        source,
+        &mut destination,
+        &["Sheet1!A1:E3", "Sheet1!A4:E5"],
+    )
+    .run()
+    .expect("run dispatcher");
+
+    // one request for each range, the first range is not fetched again after the metadata
+    assert_eq!(2, requests.load(Ordering::SeqCst));
+    assert_eq!(
+        &[
+            DummyTypeSystem::I64(true),
+            DummyTypeSystem::F64(false),
+            DummyTypeSystem::DateTime(false),
+            DummyTypeSystem::Bool(false),
+            DummyTypeSystem::String(true),
+        ],
+        destination.schema()
+    );
+    assert_eq!(
+        vec![
+            Value::Null,
+            Value::F64(2.0),
+            Value::DateTime(Utc.ymd(2021, 3, 5).and_hms(10, 20, 30)),
+            Value::Bool(false),
+            Value::Null,
+        ],
+        destination.row(1).unwrap()
+    );
+    assert_eq!(
+        vec![
+            Value::I64(3),
+            Value::F64(3.25),
+            Value::DateTime(Utc.ymd(2021, 4, 1).and_hms(0, 0, 0)),
+            Value::Bool(true),
+            Value::String("y".into()),
+        ],
+        destination.row(2).unwrap()
+    );
+    // the first row of a range after the first is data, not a header
+    assert_eq!(
+        vec![
+            Value::I64(4),
+            Value::F64(4.0),
+            Value::DateTime(Utc.ymd(2021, 4, 2).and_hms(0, 0, 0)),
+            Value::Bool(false),
+            Value::String("z".into()),
+        ],
+        destination.row(3).unwrap()
+    );
+    assert!(destination.row(4).is_err());
+}
+
+#[test]
+fn test_gsheets_arrow() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let mut source = GSheetsSource::new("sheet-id", "token", &[]);
+    source.api_url(&serve_sheet(requests));
+
+    let mut destination = ArrowDestination::new();
+    Dispatcher::<_, _, GSheetsArrowTransport>::new(
+        source,
+        &mut destination,
+        &["Sheet1!A1:E3", "Sheet1!A4:E5"],
+    )
+    .run()
+    .expect("run dispatcher");
+    let names = ["id", "score", "joined", "ok", "note"];
+    let records = destination
+        .finish(names.iter().map(|n| n.to_string()).collect())
+        .unwrap();
+
+    let joined: Vec<_> = records
+        .iter()
+        .flat_map(|batch| {
+            let column = batch
+                .column(2)
+                .as_any()
+                .downcast_ref::<TimestampMillisecondArray>()
+                .unwrap();
+            (0..column.len())
+                .map(|i| column.value(i))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            Utc.ymd(2021, 3, 4).and_hms(0, 0, 0).timestamp_millis(),
+            Utc.ymd(2021, 3, 5).and_hms(10, 20, 30).timestamp_millis(),
+            Utc.ymd(2021, 4, 1).and_hms(0, 0, 0).timestamp_millis(),
+            Utc.ymd(2021, 4, 2).and_hms(0, 0, 0).timestamp_millis(),
+        ],
+        joined
+    );
+}
+
+#[test]
+fn test_gsheets_serial_dates() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let base = serve_api(requests, |path, _| {
+        assert!(path.contains("dateTimeRenderOption=SERIAL_NUMBER"));
+        let values = json!([["id", "at"], [1, 44259.5], [2, 1]]);
+        (200, json!({ "values": values }).to_string())
+    });
+
+    let mut source = GSheetsSource::new(
+        "sheet-id",
+        "token",
+        &[
+            DummyTypeSystem::I64(false),
+            DummyTypeSystem::DateTime(false),
+        ],
+    );
+    source.api_url(&base);
+    source.date_render(DateTimeRender::SerialNumber);
+    let mut destination = MemoryDestination::new();
+    Dispatcher::<_, _, GSheetsMemoryTransport>::new(source, &mut destination, &["A1:B3"])
+        .run()
+        .expect("run dispatcher");
+
+    assert_eq!(
+        vec![
+            Value::I64(1),
+            Value::DateTime(Utc.ymd(2021, 3, 4).and_hms(12, 0, 0))
+        ],
+        destination.row(0).unwrap()
+    );
+}
+
+#[test]
+fn test_gsheets_errors() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let base = serve_sheet(requests);
+
+    // the schema does not match the columns
+    let mut source = GSheetsSource::new("sheet-id", "token", &[DummyTypeSystem::I64(false)]);
+    source.api_url(&base);
+    source.set_queries(&["Sheet1!A1:E3"]);
+    assert!(source.fetch_metadata().is_err());
+
+    let mut source = GSheetsSource::new("sheet-id", "bad token", &[]);
+    source.api_url(&base);
+    source.set_queries(&["Sheet1!A1:E3"]);
+    assert!(source.fetch_metadata().is_err());
+}
+
+#[test]
+fn test_gsheets_infer_schema() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let mut source = GSheetsSource::new("sheet-id", "token", &[]);
+    source.api_url(&serve_sheet(requests));
+    source.set_queries(&["Sheet1!A1:E3"]);
+    source.fetch_metadata().unwrap();
+
+    let rows = vec![
+        vec![
+            json!(1),
+            json!("a"),
+            json!(true),
+            json!("2021-01-01"),
+            json!(1),
+        ],
+        vec![json!(2.5), json!(3), json!(null), json!("x"), json!(2)],
+    ];
+    assert_eq!(
+        vec![
+            // integers and floats
+            DummyTypeSystem::F64(false),
+            // strings and integers
+            DummyTypeSystem::String(false),
+            DummyTypeSystem::Bool(true),
+            // dates and strings
+            DummyTypeSystem::String(false),
+            DummyTypeSystem::I64(false),
+        ],
+        source.infer_schema(&rows)
+    );
+    // columns without any value are strings, and rows may be short
+    assert_eq!(
+        vec![DummyTypeSystem::String(true); 5],
+        source.infer_schema(&[vec![json!("")]])
+    );
+}
+
+#[test]
+fn test_gsheets_parse_datetime() {
+    let at = |y, m, d, h, mi, s| Some(Utc.ymd(y, m, d).and_hms(h, mi, s));
+    assert_eq!(
+        at(2021, 3, 4, 10, 20, 30),
+        parse_datetime("2021-03-04 10:20:30")
+    );
+    assert_eq!(
+        at(2021, 3, 4, 10, 20, 30),
+        parse_datetime("2021-03-04T10:20:30")
+    );
+    assert_eq!(at(2021, 3, 4, 9, 5, 0), parse_datetime("3/4/2021 9:05:00"));
+    assert_eq!(at(2021, 3, 4, 0, 0, 0), parse_datetime("2021-03-04"));
+    assert_eq!(at(2021, 3, 4, 0, 0, 0), parse_datetime("03/04/2021"));
+    assert_eq!(None, parse_datetime("2021-13-01"));
+    assert_eq!(None, parse_datetime("yesterday"));
+}
+
+#[test]
+fn test_gsheets_serial_to_datetime() {
+    assert_eq!(
+        Utc.ymd(1899, 12, 30).and_hms(0, 0, 0),
+        serial_to_datetime(0.0)
+    );
+    assert_eq!(
+        Utc.ymd(2021, 3, 4).and_hms(18, 0, 0),
+        serial_to_datetime(44259.75)
+    );
+    // before the epoch, and a second, which is not exact as a fraction of a day
+    assert_eq!(
+        Utc.ymd(1899, 12, 29).and_hms(12, 0, 0),
+        serial_to_datetime(-0.5)
+    );
+    assert_eq!(
+        Utc.ymd(1899, 12, 30).and_hms_milli(0, 0, 1, 0),
+        serial_to_datetime(1.0 / 86_400.0)
+    );
+}
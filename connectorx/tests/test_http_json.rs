@@ -0,0 +1,197 @@
+use arrow::array::{Array, TimestampMillisecondArray};
+use chrono::{TimeZone, Utc};
+use connectorx::{
+    destinations::{
+        arrow::ArrowDestination,
+        memory::{MemoryDestination, Value},
+    },
+    sources::http_json::{HttpJsonSource, Pagination},
+    transports::{HttpJsonArrowTransport, HttpJsonMemoryTransport},
+    Destination, Dispatcher, DummyTypeSystem,
+};
+
+mod common;
+use common::serve;
+
+fn read(source: HttpJsonSource, url: &str, nrows: usize) -> (MemoryDestination, Vec<Vec<Value>>) {
+    let mut destination = MemoryDestination::new();
+    let dispatcher =
+        Dispatcher::<_, _, HttpJsonMemoryTransport>::new(source, &mut destination, &[url]);
+    dispatcher.run().expect("run dispatcher");
+    let rows = (0..nrows).map(|r| destination.row(r).unwrap()).collect();
+    (destination, rows)
+}
+
+#[test]
+fn test_http_json_offset_until_empty() {
+    let base = serve(|_, _, path, _, _| {
+        let body = match path {
+            "/items?offset=0&limit=2" => r#"[{"id": 1, "name": "a"}, {"id": 2, "name": "b"}]"#,
+            "/items?offset=2&limit=2" => r#"[{"id": 3, "name": null}]"#,
+            _ => "[]",
+        };
+        (200, body.to_string())
+    });
+
+    let mut source = HttpJsonSource::new(&[]);
+    source
+        .pagination(Pagination::Offset {
+            offset_param: "offset".into(),
+            limit_param: "limit".into(),
+            page_size: 2,
+            total_path: None,
+        })
+        .unwrap();
+    let (destination, rows) = read(source, &format!("{}/items", base), 3);
+
+    assert_eq!(
+        &[DummyTypeSystem::I64(false), DummyTypeSystem::String(true)],
+        destination.schema()
+    );
+    assert_eq!(
+        vec![
+            vec![Value::I64(1), Value::String("a".into())],
+            vec![Value::I64(2), Value::String("b".into())],
+            vec![Value::I64(3), Value::Null],
+        ],
+        rows
+    );
+}
+
+#[test]
+fn test_http_json_offset_total() {
+    let base = serve(|_, _, path, _, _| {
+        let body = match path {
+            "/items?offset=0&limit=2" => {
+                r#"{"total": 3, "data": {"items": [{"x": 1.5}, {"x": 2}]}}"#
+            }
+            "/items?offset=2&limit=2" => r#"{"total": 3, "data": {"items": [{"x": 3}]}}"#,
+            _ => return (404, "{}".to_string()),
+        };
+        (200, body.to_string())
+    });
+
+    let mut source = HttpJsonSource::new(&[]);
+    assert!(source
+        .pagination(Pagination::Offset {
+            offset_param: "offset".into(),
+            limit_param: "limit".into(),
+            page_size: 0,
+            total_path: None,
+        })
+        .is_err());
+    source.records_path("data.items");
+    source
+        .pagination(Pagination::Offset {
+            offset_param: "offset".into(),
+            limit_param: "limit".into(),
+            page_size: 2,
+            total_path: Some("total".into()),
+        })
+        .unwrap();
+    let (destination, rows) = read(source, &format!("{}/items", base), 3);
+
+    assert_eq!(&[DummyTypeSystem::F64(false)], destination.schema());
+    assert_eq!(
+        vec![
+            vec![Value::F64(1.5)],
+            vec![Value::F64(2.0)],
+            vec![Value::F64(3.0)]
+        ],
+        rows
+    );
+}
+
+#[test]
+fn test_http_json_next_token() {
+    let base = serve(|_, _, path, headers, _| {
+        if !headers.iter().any(|h| h == "authorization: bearer secret") {
+            return (401, r#"{"error": "unauthorized"}"#.to_string());
+        }
+        let body = match path {
+            "/events" => r#"{"next": "p2", "records": [{"ok": true, "tags": ["x", "y"]}]}"#,
+            "/events?page=p2" => {
+                r#"{"next": null, "records": [{"ok": false, "tags": [], "meta": {"k": 1}}]}"#
+            }
+            _ => return (404, "{}".to_string()),
+        };
+        (200, body.to_string())
+    });
+
+    let mut source = HttpJsonSource::new(&[
+        DummyTypeSystem::Bool(false),
+        DummyTypeSystem::String(false),
+        DummyTypeSystem::String(true),
+    ]);
+    source.header("Authorization", "Bearer secret");
+    source.records_path("records");
+    source
+        .pagination(Pagination::NextToken {
+            token_param: "page".into(),
+            token_path: "next".into(),
+        })
+        .unwrap();
+    let (_, rows) = read(source, &format!("{}/events", base), 2);
+
+    assert_eq!(
+        vec![
+            vec![
+                Value::Bool(true),
+                Value::String(r#"["x","y"]"#.into()),
+                Value::Null
+            ],
+            vec![
+                Value::Bool(false),
+                Value::String("[]".into()),
+                Value::String(r#"{"k":1}"#.into())
+            ],
+        ],
+        rows
+    );
+}
+
+#[test]
+fn test_http_json_error_status() {
+    let base = serve(|_, _, _, _, _| (401, r#"{"error": "unauthorized"}"#.to_string()));
+
+    let mut destination = MemoryDestination::new();
+    let url = format!("{}/events", base);
+    let dispatcher = Dispatcher::<_, _, HttpJsonMemoryTransport>::new(
+        HttpJsonSource::new(&[]),
+        &mut destination,
+        &[url],
+    );
+    assert!(dispatcher.run().is_err());
+}
+
+#[test]
+fn test_http_json_arrow() {
+    let base = serve(|_, _, _, _, _| {
+        let body = r#"[{"id": 1, "at": "2021-03-04T05:06:07+01:00"}, {"id": 2, "at": null}]"#;
+        (200, body.to_string())
+    });
+
+    let mut destination = ArrowDestination::new();
+    let url = format!("{}/events", base);
+    Dispatcher::<_, _, HttpJsonArrowTransport>::new(
+        HttpJsonSource::new(&[]),
+        &mut destination,
+        &[url],
+    )
+    .run()
+    .expect("run dispatcher");
+    let records = destination
+        .finish(vec!["id".to_string(), "at".to_string()])
+        .unwrap();
+
+    let at = records[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<TimestampMillisecondArray>()
+        .unwrap();
+    assert_eq!(
+        Utc.ymd(2021, 3, 4).and_hms(4, 6, 7).timestamp_millis(),
+        at.value(0)
+    );
+    assert!(at.is_null(1));
+}
@@ -0,0 +1,158 @@
+use arrow::array::{Array, DictionaryArray, Float64Array, Int64Array, StringArray};
+use arrow::datatypes::{DataType, Int32Type};
+use connectorx::{
+    destinations::long_format::LongFormatDestination,
+    impl_transport,
+    sources::sqlite::{SqliteSource, SqliteTypeSystem},
+    Dispatcher, DummyTypeSystem, TypeConversion,
+};
+use rusqlite::Connection;
+use std::env;
+use std::fs;
+
+struct SqliteLongFormatTransport;
+
+impl_transport!(
+    name = SqliteLongFormatTransport,
+    systems = SqliteTypeSystem => DummyTypeSystem,
+    route = SqliteSource => LongFormatDestination,
+    mappings = {
+        { Int8[i64]      => I64[i64]       | conversion all }
+        { Real[f64]      => F64[f64]       | conversion all }
+        { Text[Box<str>] => String[String] | conversion half }
+    }
+);
+
+impl TypeConversion<Box<str>, String> for SqliteLongFormatTransport {
+    fn convert(val: Box<str>) -> String {
+        val.to_string()
+    }
+}
+
+fn metrics_db(name: &str) -> String {
+    let path = env::temp_dir().join(format!("long_format_{}_{}.db", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE metrics(ts INTEGER NOT NULL, host TEXT, cpu REAL, mem REAL, disk INTEGER);
+         INSERT INTO metrics VALUES
+            (1000, 'a', 0.5, 10.0, 7), (2000, 'a', 0.25, NULL, 8), (3000, 'b', 0.75, 12.0, 9);",
+    )
+    .unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+fn series_labels(column: &dyn Array) -> Vec<String> {
+    let dict = column
+        .as_any()
+        .downcast_ref::<DictionaryArray<Int32Type>>()
+        .unwrap();
+    let values = dict.values();
+    let names = values.as_any().downcast_ref::<StringArray>().unwrap();
+    dict.keys()
+        .iter()
+        .map(|k| names.value(k.unwrap() as usize).to_string())
+        .collect()
+}
+
+#[test]
+fn test_long_format() {
+    let db = metrics_db("wide");
+    let queries = [
+        "SELECT ts, cpu, mem, disk FROM metrics WHERE ts < 2500",
+        "SELECT ts, cpu, mem, disk FROM metrics WHERE ts >= 2500",
+    ];
+    let mut destination = LongFormatDestination::new("ts");
+    Dispatcher::<_, _, SqliteLongFormatTransport>::new(
+        SqliteSource::new(&db, 2).unwrap(),
+        &mut destination,
+        &queries,
+    )
+    .run()
+    .expect("run dispatcher");
+
+    let batches = destination.finish(&["ts", "cpu", "mem", "disk"]).unwrap();
+    assert_eq!(2, batches.len());
+    let schema = batches[0].schema();
+    let names: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(vec!["ts", "series_id", "value"], names);
+    assert_eq!(
+        &DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+        schema.field(1).data_type()
+    );
+
+    // three series for each of the three wide rows
+    assert_eq!(9, batches.iter().map(|b| b.num_rows()).sum::<usize>());
+    let (mut ts, mut labels, mut values) = (vec![], vec![], vec![]);
+    for batch in &batches {
+        let col = batch.column(0);
+        let col = col.as_any().downcast_ref::<Int64Array>().unwrap();
+        ts.extend(col.iter().map(Option::unwrap));
+        labels.extend(series_labels(batch.column(1).as_ref()));
+        let col = batch.column(2);
+        let col = col.as_any().downcast_ref::<Float64Array>().unwrap();
+        values.extend(col.iter());
+    }
+    assert_eq!(
+        vec![1000, 1000, 1000, 2000, 2000, 2000, 3000, 3000, 3000],
+        ts
+    );
+    assert_eq!(
+        ["cpu", "mem", "disk", "cpu", "mem", "disk", "cpu", "mem", "disk"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>(),
+        labels
+    );
+    assert_eq!(
+        vec![
+            Some(0.5),
+            Some(10.0),
+            Some(7.0),
+            Some(0.25),
+            None,
+            Some(8.0),
+            Some(0.75),
+            Some(12.0),
+            Some(9.0)
+        ],
+        values
+    );
+}
+
+#[test]
+fn test_long_format_series() {
+    let db = metrics_db("series");
+    let mut destination = LongFormatDestination::new("ts").with_series(&["disk"]);
+    Dispatcher::<_, _, SqliteLongFormatTransport>::new(
+        SqliteSource::new(&db, 1).unwrap(),
+        &mut destination,
+        &["SELECT ts, host, disk FROM metrics"],
+    )
+    .run()
+    .expect("run dispatcher");
+
+    let batches = destination.finish(&["ts", "host", "disk"]).unwrap();
+    assert_eq!(3, batches[0].num_rows());
+    assert_eq!(
+        vec!["disk"; 3],
+        series_labels(batches[0].column(1).as_ref())
+    );
+    // only integer series keep integer values
+    let values = batches[0].column(2);
+    let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(
+        vec![Some(7), Some(8), Some(9)],
+        values.iter().collect::<Vec<_>>()
+    );
+
+    // a text column cannot be a series
+    let mut destination = LongFormatDestination::new("ts");
+    let result = Dispatcher::<_, _, SqliteLongFormatTransport>::new(
+        SqliteSource::new(&db, 1).unwrap(),
+        &mut destination,
+        &["SELECT ts, host, disk FROM metrics"],
+    )
+    .run();
+    assert!(result.is_err());
+}
@@ -0,0 +1,89 @@
+use connectorx::{
+    destinations::memory::MemoryDestination, sources::dummy::DummySource,
+    transports::DummyMemoryTransport, Dispatcher, DummyTypeSystem, MemoryEstimate, RunMetrics,
+};
+use std::mem::size_of;
+
+fn run(nrows: &[usize], threshold: f64) -> RunMetrics {
+    let schema = [DummyTypeSystem::I64(false), DummyTypeSystem::F64(true)];
+    let queries: Vec<String> = nrows.iter().map(|n| format!("{},2", n)).collect();
+    let mut destination = MemoryDestination::new();
+    Dispatcher::<_, _, DummyMemoryTransport>::new(
+        DummySource::new(&["a", "b"], &schema),
+        &mut destination,
+        &queries,
+    )
+    .skew_warning(threshold)
+    .run_with_metrics()
+    .expect("run dispatcher")
+}
+
+#[test]
+fn test_partition_skew() {
+    let metrics = run(&[90, 5, 5], 2.0);
+    assert_eq!(
+        vec![90, 5, 5],
+        metrics
+            .partitions
+            .iter()
+            .map(|p| p.rows)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(100, metrics.total_rows());
+    assert!((metrics.skew_ratio() - 2.7).abs() < 1e-9);
+    let warning = metrics.skew_warning.expect("skew warning");
+    assert!(
+        warning.contains("partition 0 has 90 of 100 rows"),
+        "{}",
+        warning
+    );
+
+    // the same skew under a looser threshold passes silently
+    let metrics = run(&[90, 5, 5], 3.0);
+    assert!((metrics.skew_ratio() - 2.7).abs() < 1e-9);
+    assert_eq!(None, metrics.skew_warning);
+}
+
+#[test]
+fn test_partition_no_skew() {
+    let metrics = run(&[10, 10, 10, 10], 1.5);
+    assert_eq!(4, metrics.partitions.len());
+    assert!((metrics.skew_ratio() - 1.0).abs() < 1e-9);
+    assert_eq!(None, metrics.skew_warning);
+
+    let metrics = run(&[0, 0], 1.5);
+    assert!((metrics.skew_ratio() - 1.0).abs() < 1e-9);
+    assert_eq!(None, metrics.skew_warning);
+}
+
+#[test]
+fn test_memory_accounting() {
+    let schema = [
+        DummyTypeSystem::I64(false),
+        DummyTypeSystem::F64(true),
+        DummyTypeSystem::I64(false),
+        DummyTypeSystem::String(true),
+    ];
+    let mut destination = MemoryDestination::new();
+    let metrics = Dispatcher::<_, _, DummyMemoryTransport>::new(
+        DummySource::new(&["a", "b", "c", "d"], &schema),
+        &mut destination,
+        &["7,4", "3,4"],
+    )
+    .with_memory_accounting()
+    .run_with_metrics()
+    .expect("run dispatcher");
+
+    // the dummy source does not buffer, so writing needs no more than the destination
+    let row = 2 * size_of::<i64>() + size_of::<Option<f64>>() + size_of::<Option<String>>();
+    assert_eq!(
+        Some(MemoryEstimate {
+            allocate: 10 * row,
+            write: 10 * row,
+        }),
+        metrics.memory
+    );
+
+    // only reported when asked for
+    assert_eq!(None, run(&[10], 2.0).memory);
+}
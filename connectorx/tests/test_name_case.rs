@@ -0,0 +1,58 @@
+use connectorx::{
+    destinations::memory::MemoryDestination, name_case::normalize_names,
+    sources::dummy::DummySource, transports::DummyMemoryTransport, ConnectorAgentError, Dispatcher,
+    DummyTypeSystem, NameCase,
+};
+
+#[test]
+fn camel_to_snake() {
+    let names: Vec<String> = [
+        "userId",
+        "HTTPServer",
+        "OrderDate",
+        "total amount",
+        "col2Value",
+    ]
+    .iter()
+    .map(ToString::to_string)
+    .collect();
+    assert_eq!(
+        vec![
+            "user_id",
+            "http_server",
+            "order_date",
+            "total_amount",
+            "col2_value"
+        ],
+        normalize_names(&names, NameCase::Snake).unwrap()
+    );
+    assert_eq!(
+        vec![
+            "USERID",
+            "HTTPSERVER",
+            "ORDERDATE",
+            "TOTAL AMOUNT",
+            "COL2VALUE"
+        ],
+        normalize_names(&names, NameCase::Upper).unwrap()
+    );
+}
+
+#[test]
+fn name_collision() {
+    let schema = [DummyTypeSystem::I64(false), DummyTypeSystem::I64(false)];
+    let mut destination = MemoryDestination::new();
+    let dispatcher = Dispatcher::<_, _, DummyMemoryTransport>::new(
+        DummySource::new(&["userId", "user_id"], &schema),
+        &mut destination,
+        &["2,2"],
+    )
+    .with_name_case(NameCase::Snake);
+
+    match dispatcher.run() {
+        Err(ConnectorAgentError::ColumnNameCollision(a, b, name)) => {
+            assert_eq!(("userId", "user_id", "user_id"), (&*a, &*b, &*name))
+        }
+        r => panic!("expected a name collision, got {:?}", r),
+    }
+}
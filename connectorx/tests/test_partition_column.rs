@@ -0,0 +1,80 @@
+use arrow::array::Int64Array;
+use connectorx::{
+    destinations::{arrow::ArrowDestination, memory::MemoryDestination},
+    sources::dummy::DummySource,
+    transports::{DummyArrowTransport, DummyMemoryTransport},
+    Destination, Dispatcher, DummyTypeSystem,
+};
+use ndarray::array;
+
+const SCHEMA: [DummyTypeSystem; 2] = [DummyTypeSystem::I64(false), DummyTypeSystem::F64(true)];
+const QUERIES: [&str; 3] = ["3,2", "1,2", "2,2"];
+
+#[test]
+fn test_partition_column() {
+    let mut destination = MemoryDestination::new();
+    Dispatcher::<_, _, DummyMemoryTransport>::new(
+        DummySource::new(&["a", "b"], &SCHEMA),
+        &mut destination,
+        &QUERIES,
+    )
+    .with_partition_column("__partition__")
+    .run()
+    .expect("run dispatcher");
+
+    assert_eq!(
+        &[
+            DummyTypeSystem::I64(false),
+            DummyTypeSystem::F64(true),
+            DummyTypeSystem::I64(false)
+        ],
+        destination.schema()
+    );
+    assert_eq!(
+        array![0, 0, 0, 1, 2, 2],
+        destination.column_view::<i64>(2).unwrap()
+    );
+}
+
+#[test]
+fn test_partition_column_arrow() {
+    let mut destination = ArrowDestination::new();
+    Dispatcher::<_, _, DummyArrowTransport>::new(
+        DummySource::new(&["a", "b"], &SCHEMA),
+        &mut destination,
+        &QUERIES,
+    )
+    .with_partition_column("__partition__")
+    .run()
+    .expect("run dispatcher");
+
+    let names = vec![
+        "a".to_string(),
+        "b".to_string(),
+        "__partition__".to_string(),
+    ];
+    let batches = destination.finish(names).unwrap();
+    assert_eq!(3, batches.len());
+    for (i, batch) in batches.iter().enumerate() {
+        assert_eq!("__partition__", batch.schema().field(2).name());
+        let partitions = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert!(partitions.values().iter().all(|&p| p == i as i64));
+    }
+}
+
+#[test]
+fn test_partition_column_name_taken() {
+    let mut destination = MemoryDestination::new();
+    let result = Dispatcher::<_, _, DummyMemoryTransport>::new(
+        DummySource::new(&["a", "b"], &SCHEMA),
+        &mut destination,
+        &QUERIES,
+    )
+    .with_partition_column("b")
+    .run();
+    assert!(result.is_err());
+}
@@ -0,0 +1,115 @@
+#![feature(generic_associated_types)]
+#![allow(incomplete_features)]
+
+use chrono::{DateTime, Utc};
+use connectorx::{
+    destinations::memory::MemoryDestination,
+    impl_transport,
+    sources::dummy::{DummySource, DummySourcePartition},
+    DataOrder, Dispatcher, DummyTypeSystem, Result, Source, TypeConversion,
+};
+use ndarray::array;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// number of partitions opened, a database source opens a connection for each
+static OPENED: AtomicUsize = AtomicUsize::new(0);
+
+struct ConnectingSource {
+    inner: DummySource,
+    nqueries: usize,
+}
+
+impl ConnectingSource {
+    fn new() -> Self {
+        ConnectingSource {
+            inner: DummySource::new(&["a", "b"], &SCHEMA),
+            nqueries: 0,
+        }
+    }
+}
+
+impl Source for ConnectingSource {
+    const DATA_ORDERS: &'static [DataOrder] = DummySource::DATA_ORDERS;
+    type TypeSystem = DummyTypeSystem;
+    type Partition = DummySourcePartition;
+
+    fn set_data_order(&mut self, data_order: DataOrder) -> Result<()> {
+        self.inner.set_data_order(data_order)
+    }
+
+    fn set_queries<Q: AsRef<str>>(&mut self, queries: &[Q]) {
+        self.nqueries = queries.len();
+        self.inner.set_queries(queries);
+    }
+
+    fn fetch_metadata(&mut self) -> Result<()> {
+        self.inner.fetch_metadata()
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.inner.names()
+    }
+
+    fn schema(&self) -> Vec<DummyTypeSystem> {
+        self.inner.schema()
+    }
+
+    fn partition(self) -> Result<Vec<DummySourcePartition>> {
+        OPENED.fetch_add(self.nqueries, Ordering::SeqCst);
+        self.inner.partition()
+    }
+}
+
+struct ConnectingMemoryTransport;
+
+impl_transport!(
+    name = ConnectingMemoryTransport,
+    systems = DummyTypeSystem => DummyTypeSystem,
+    route = ConnectingSource => MemoryDestination,
+    mappings = {
+        { F64[f64]                => F64[f64]                | conversion all}
+        { I64[i64]                => I64[i64]                | conversion all}
+        { Bool[bool]              => Bool[bool]              | conversion all}
+        { String[String]          => String[String]          | conversion all}
+        { DateTime[DateTime<Utc>] => DateTime[DateTime<Utc>] | conversion all}
+    }
+);
+
+const SCHEMA: [DummyTypeSystem; 2] = [DummyTypeSystem::I64(false), DummyTypeSystem::F64(true)];
+const QUERIES: [&str; 5] = ["1,2", "2,2", "3,2", "4,2", "5,2"];
+
+#[test]
+fn test_partition_subset() {
+    let mut destination = MemoryDestination::new();
+    Dispatcher::<_, _, ConnectingMemoryTransport>::new(
+        ConnectingSource::new(),
+        &mut destination,
+        &QUERIES,
+    )
+    .with_partition_subset(2..4)
+    .with_partition_column("__partition__")
+    .run()
+    .expect("run dispatcher");
+
+    assert_eq!(2, OPENED.load(Ordering::SeqCst));
+    assert_eq!(7, destination.column_view::<i64>(0).unwrap().len());
+    assert_eq!(
+        array![2, 2, 2, 3, 3, 3, 3],
+        destination.column_view::<i64>(2).unwrap()
+    );
+}
+
+#[test]
+fn test_partition_subset_out_of_range() {
+    for subset in [3..6, 2..2] {
+        let mut destination = MemoryDestination::new();
+        let result = Dispatcher::<_, _, ConnectingMemoryTransport>::new(
+            ConnectingSource::new(),
+            &mut destination,
+            &QUERIES,
+        )
+        .with_partition_subset(subset)
+        .run();
+        assert!(result.is_err());
+    }
+}
@@ -1,14 +1,33 @@
+use arrow::array::{
+    Array, FixedSizeListArray, Float64Array, Int64Array, LargeStringArray, ListArray, StructArray,
+    TimestampMillisecondArray, UInt64Array,
+};
+use arrow::datatypes::{DataType, TimeUnit};
+use chrono::{DateTime, TimeZone, Utc};
 use connectorx::{
-    destinations::memory::MemoryDestination,
+    destinations::{
+        arrow::{ArrowDestination, CIRCLE_EXTENSION_NAME, POINT_EXTENSION_NAME},
+        memory::{MemoryDestination, Value},
+    },
+    dummy_typesystem::{Record, RecordType, RecordValue, Snapshot},
+    source_router::SourceType,
     sources::{
-        postgres::{Binary, PostgresSource, CSV},
-        Produce, Source, SourcePartition,
+        postgres::{
+            Binary, Multirange, PostgresSource, PseudoTypePolicy, Range, RangeBound, RegTypePolicy,
+            StaleMatviewPolicy, CSV,
+        },
+        Notice, NoticePolicy, Produce, Source, SourcePartition,
     },
-    transports::PostgresMemoryTransport,
-    Dispatcher,
+    transports::{PostgresArrowTransport, PostgresMemoryTransport},
+    ConnectorAgentError, DecimalRounding, Dispatcher,
 };
 use ndarray::array;
+use postgres::types::{Field, FromSql, Kind, Type};
+use postgres::{Client, NoTls};
+use rust_decimal::Decimal;
 use std::env;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[test]
 fn load_and_parse() {
@@ -58,6 +77,91 @@ fn load_and_parse() {
     );
 }
 
+#[test]
+fn test_postgres_flow_control() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let window = 4;
+    let mut source = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+    assert!(source.flow_control(0).is_err());
+    source.flow_control(window).unwrap();
+    source.set_queries(&["select test_int from test_table"]);
+    source.fetch_metadata().unwrap();
+
+    let mut partition = source.partition().unwrap().remove(0);
+    partition.prepare().expect("run query");
+    let mut parser = partition.parser().unwrap();
+
+    let mut ints = vec![];
+    for _ in 0..6 {
+        ints.push(Produce::<i32>::produce(&mut parser).unwrap());
+        assert!(parser.peak_buffered_rows() <= window);
+    }
+    assert_eq!(vec![1, 2, 0, 3, 4, 1314], ints);
+    assert_eq!(window, parser.peak_buffered_rows());
+    assert!(Produce::<i32>::produce(&mut parser).is_err());
+}
+
+fn read_text_arrays(source: PostgresSource<Binary>) -> Vec<Option<Vec<Option<String>>>> {
+    let queries = [
+        // sqlparser cannot parse array literals or `::text[]`, so split strings into arrays
+        "select string_to_array(s, '|', '*') as arr from (values (1, 'a|*|c'), (2, ''), \
+                    (3, null), (4, '{x,y}|')) t(i, s) order by i",
+    ];
+    let mut destination = ArrowDestination::new();
+    Dispatcher::<_, _, PostgresArrowTransport>::new(source, &mut destination, &queries)
+        .run()
+        .expect("run dispatcher");
+
+    let batches = destination.finish(vec!["arr".to_string()]).unwrap();
+    string_lists(batches[0].column(0).as_ref())
+}
+
+// The items of each list in a column of string lists.
+fn string_lists(column: &dyn Array) -> Vec<Option<Vec<Option<String>>>> {
+    let lists = column.as_any().downcast_ref::<ListArray>().unwrap();
+    (0..lists.len())
+        .map(|i| {
+            if lists.is_null(i) {
+                return None;
+            }
+            let items = lists.value(i);
+            let items = items.as_any().downcast_ref::<LargeStringArray>().unwrap();
+            Some(
+                (0..items.len())
+                    .map(|j| (!items.is_null(j)).then(|| items.value(j).to_string()))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_postgres_text_array() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let copy = read_text_arrays(PostgresSource::new(&dburl, 1).unwrap());
+    let mut cursor = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+    cursor.flow_control(2).unwrap();
+    let cursor = read_text_arrays(cursor);
+
+    let s = |v: &str| Some(v.to_string());
+    assert_eq!(
+        vec![
+            Some(vec![s("a"), None, s("c")]),
+            Some(vec![]),
+            None,
+            Some(vec![s("{x,y}"), s("")]),
+        ],
+        copy
+    );
+    assert_eq!(copy, cursor);
+}
+
 #[test]
 fn test_postgres() {
     let _ = env_logger::builder().is_test(true).try_init();
@@ -115,6 +219,611 @@ fn test_postgres() {
     );
 }
 
+#[test]
+fn test_postgres_buf_size() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let queries = [
+        "select * from test_table where test_int < 2",
+        "select * from test_table where test_int >= 2",
+    ];
+
+    for &buf_size in &[1, 1024] {
+        let mut builder = PostgresSource::new(&dburl, 2).unwrap();
+        builder.set_read_buffer(buf_size).unwrap();
+        let mut destination = MemoryDestination::new();
+        let dispatcher = Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(
+            builder,
+            &mut destination,
+            &queries,
+        );
+
+        dispatcher.run().expect("run dispatcher");
+        assert_eq!(
+            array![Some(1), Some(0), Some(2), Some(3), Some(4), Some(1314)],
+            destination.column_view::<Option<i64>>(0).unwrap()
+        );
+        assert_eq!(
+            array![
+                Some("str1".to_string()),
+                Some("a".to_string()),
+                Some("str2".to_string()),
+                Some("b".to_string()),
+                Some("c".to_string()),
+                None
+            ],
+            destination.column_view::<Option<String>>(2).unwrap()
+        );
+    }
+
+    let mut builder = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+    assert!(builder.buf_size(0).is_err());
+    assert!(builder.set_read_buffer(0).is_err());
+}
+
+#[test]
+fn test_postgres_keepalive() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let queries = [
+        "select * from test_table where test_int < 2",
+        "select * from test_table where test_int >= 2",
+    ];
+    let mut builder = PostgresSource::new(&dburl, 2).unwrap();
+    builder.keepalive(Duration::from_secs(1));
+    let mut destination = MemoryDestination::new();
+    let dispatcher = Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(
+        builder,
+        &mut destination,
+        &queries,
+    );
+
+    dispatcher.run().expect("run dispatcher");
+    assert_eq!(
+        array![Some(1), Some(0), Some(2), Some(3), Some(4), Some(1314)],
+        destination.column_view::<Option<i64>>(0).unwrap()
+    );
+}
+
+#[test]
+fn test_postgres_computed_column() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let queries = ["select * from test_table"];
+    let mut builder = PostgresSource::new(&dburl, 1).unwrap();
+    builder.add_computed_column("total", "test_int + test_nullint");
+    let mut destination = MemoryDestination::new();
+    let dispatcher = Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(
+        builder,
+        &mut destination,
+        &queries,
+    );
+
+    dispatcher.run().expect("run dispatcher");
+    assert_eq!(
+        array![Some(4), None, Some(5), Some(10), Some(13), Some(1316)],
+        destination.column_view::<Option<i64>>(5).unwrap()
+    );
+
+    let mut builder = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+    builder.add_computed_column("total", "test_int + no_such_column");
+    builder.set_queries(&queries);
+    assert!(builder.fetch_metadata().is_err());
+}
+
+#[test]
+fn test_postgres_point() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let queries = ["select p from (select point(1.5, 2) as p union all select null::point) t"];
+    let builder = PostgresSource::new(&dburl, 1).unwrap();
+    let mut destination = ArrowDestination::new();
+    let dispatcher =
+        Dispatcher::<_, _, PostgresArrowTransport>::new(builder, &mut destination, &queries);
+
+    dispatcher.run().expect("run dispatcher");
+    let records = destination.finish(vec!["p".to_string()]).unwrap();
+    assert_eq!(1, records.len());
+
+    let field = records[0].schema().field(0).clone();
+    assert_eq!(
+        Some(&POINT_EXTENSION_NAME.to_string()),
+        field
+            .metadata()
+            .as_ref()
+            .and_then(|m| m.get("ARROW:extension:name"))
+    );
+
+    let points = records[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<FixedSizeListArray>()
+        .unwrap();
+    assert_eq!(2, points.len());
+    assert!(points.is_valid(0));
+    assert!(points.is_null(1));
+    let first = points.value(0);
+    let first = first.as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(&[1.5, 2.0], first.values());
+}
+
+#[test]
+fn test_postgres_circle() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let queries =
+        ["select c from (select circle(point(1.5, 2), 3) as c union all select null::circle) t"];
+    let builder = PostgresSource::new(&dburl, 1).unwrap();
+    let mut destination = ArrowDestination::new();
+    let dispatcher =
+        Dispatcher::<_, _, PostgresArrowTransport>::new(builder, &mut destination, &queries);
+
+    dispatcher.run().expect("run dispatcher");
+    let records = destination.finish(vec!["c".to_string()]).unwrap();
+    assert_eq!(1, records.len());
+
+    let field = records[0].schema().field(0).clone();
+    assert_eq!(
+        Some(&CIRCLE_EXTENSION_NAME.to_string()),
+        field
+            .metadata()
+            .as_ref()
+            .and_then(|m| m.get("ARROW:extension:name"))
+    );
+
+    let circles = records[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<FixedSizeListArray>()
+        .unwrap();
+    assert_eq!(3, circles.value_length());
+    assert!(circles.is_valid(0));
+    assert!(circles.is_null(1));
+    let first = circles.value(0);
+    let first = first.as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(&[1.5, 2.0, 3.0], first.values());
+}
+
+#[test]
+fn test_postgres_csv_diagnostics() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    // the text of an infinite date does not parse as one
+    let queries = [
+        "select '2021-01-01'::date as d",
+        "select d from (values ('2021-01-02'::date), ('infinity'::date)) t(d)",
+    ];
+    let mut source = PostgresSource::<CSV>::new(&dburl, 2).unwrap();
+    source.diagnostics(true);
+    let mut destination = MemoryDestination::new();
+    let err =
+        Dispatcher::<_, _, PostgresMemoryTransport<CSV>>::new(source, &mut destination, &queries)
+            .run()
+            .unwrap_err();
+    match &err {
+        ConnectorAgentError::CannotDecode {
+            col,
+            row,
+            raw_bytes,
+            ..
+        } => {
+            // counted from the start of the result, across the partitions
+            assert_eq!(("d", 2), (col.as_str(), *row));
+            assert_eq!(&Some(b"infinity".to_vec()), raw_bytes);
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[test]
+fn test_postgres_tid() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let queries = [
+        "select t from (select '(4294967295,7)'::tid as t union all select null::tid) t",
+        "select ctid as t from test_table where test_int = 1",
+    ];
+    let builder = PostgresSource::new(&dburl, 2).unwrap();
+    let mut destination = ArrowDestination::new();
+    Dispatcher::<_, _, PostgresArrowTransport>::new(builder, &mut destination, &queries)
+        .run()
+        .expect("run dispatcher");
+    let records = destination.finish(vec!["t".to_string()]).unwrap();
+
+    let tids: Vec<Option<String>> = records
+        .iter()
+        .flat_map(|batch| {
+            let col = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<LargeStringArray>()
+                .unwrap();
+            (0..col.len())
+                .map(|i| {
+                    if col.is_valid(i) {
+                        Some(col.value(i).to_string())
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    assert_eq!(3, tids.len());
+    assert!(tids.contains(&Some("(4294967295,7)".to_string())));
+    assert!(tids.contains(&None));
+    // where the row lies depends on the table's history, but it is a block and an index
+    let ctid = tids
+        .iter()
+        .flatten()
+        .find(|t| t.as_str() != "(4294967295,7)")
+        .unwrap();
+    assert!(ctid.starts_with('(') && ctid.ends_with(')') && ctid.contains(','));
+}
+
+#[test]
+fn test_postgres_arrow_datetime() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let queries = ["select ts, d from (select '2021-03-04 05:06:07.891+02'::timestamptz as ts, '2021-03-04'::date as d union all select null, null) t"];
+    let builder = PostgresSource::new(&dburl, 1).unwrap();
+    let mut destination = ArrowDestination::new();
+    Dispatcher::<_, _, PostgresArrowTransport>::new(builder, &mut destination, &queries)
+        .run()
+        .expect("run dispatcher");
+    let records = destination
+        .finish(vec!["ts".to_string(), "d".to_string()])
+        .unwrap();
+    assert_eq!(1, records.len());
+
+    let utc = DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".to_string()));
+    for col in 0..2 {
+        assert_eq!(&utc, records[0].schema().field(col).data_type());
+        assert_eq!(&utc, records[0].column(col).data_type());
+    }
+    let ts = records[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<TimestampMillisecondArray>()
+        .unwrap();
+    assert_eq!(
+        Utc.ymd(2021, 3, 4)
+            .and_hms_milli(3, 6, 7, 891)
+            .timestamp_millis(),
+        ts.value(0)
+    );
+    assert!(ts.is_null(1));
+    let d = records[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<TimestampMillisecondArray>()
+        .unwrap();
+    assert_eq!(
+        Utc.ymd(2021, 3, 4).and_hms(0, 0, 0).timestamp_millis(),
+        d.value(0)
+    );
+    assert!(d.is_null(1));
+}
+
+#[test]
+fn test_postgres_snapshot_wire_format() {
+    // nxip, xmin, xmax, then the in-progress ids
+    let mut raw = vec![];
+    raw.extend_from_slice(&2i32.to_be_bytes());
+    for xid in &[10u64, 20, 10, 14] {
+        raw.extend_from_slice(&xid.to_be_bytes());
+    }
+    assert_eq!(
+        Snapshot {
+            xmin: 10,
+            xmax: 20,
+            xip: vec![10, 14]
+        },
+        Snapshot::from_sql(&Type::PG_SNAPSHOT, &raw).unwrap()
+    );
+
+    // one id short
+    assert!(Snapshot::from_sql(&Type::PG_SNAPSHOT, &raw[..raw.len() - 8]).is_err());
+}
+
+#[test]
+fn test_postgres_xid8_snapshot() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let queries = ["select xid, snap from (\
+         select pg_current_xact_id() as xid, pg_current_snapshot() as snap \
+         union all select '42'::xid8, '10:20:10,14,15'::pg_snapshot \
+         union all select '18446744073709551615'::xid8, '10:10:'::pg_snapshot \
+         union all select null, null) t"];
+    let builder = PostgresSource::new(&dburl, 1).unwrap();
+    let mut destination = ArrowDestination::new();
+    let dispatcher =
+        Dispatcher::<_, _, PostgresArrowTransport>::new(builder, &mut destination, &queries);
+
+    dispatcher.run().expect("run dispatcher");
+    let records = destination
+        .finish(vec!["xid".to_string(), "snap".to_string()])
+        .unwrap();
+    assert_eq!(1, records.len());
+
+    let xids = records[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<UInt64Array>()
+        .unwrap();
+    assert!(xids.value(0) > 0);
+    assert_eq!(42, xids.value(1));
+    // above i64::MAX
+    assert_eq!(u64::MAX, xids.value(2));
+    assert!(xids.is_null(3));
+
+    let snapshots = records[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<StructArray>()
+        .unwrap();
+    let field = |name| {
+        snapshots
+            .column_by_name(name)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap()
+    };
+    let (xmin, xmax) = (field("xmin"), field("xmax"));
+    let xip = snapshots
+        .column_by_name("xip")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<ListArray>()
+        .unwrap();
+    let xip = |row| {
+        let ids = xip.value(row);
+        ids.as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap()
+            .values()
+            .to_vec()
+    };
+
+    assert!(snapshots.is_valid(0));
+    assert!(xmin.value(0) <= xmax.value(0));
+    assert_eq!(
+        (10, 20, vec![10, 14, 15]),
+        (xmin.value(1), xmax.value(1), xip(1))
+    );
+    assert_eq!((10, 10, vec![]), (xmin.value(2), xmax.value(2), xip(2)));
+    assert!(snapshots.is_null(3));
+}
+
+#[test]
+fn test_postgres_jsonpath() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let queries = [
+        "select p, p::text as t from (values (1, '$.a.b[*]'::jsonpath), (2, null)) v(i, p) \
+         order by i",
+    ];
+    let builder = PostgresSource::new(&dburl, 1).unwrap();
+    let mut destination = MemoryDestination::new();
+    let dispatcher = Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(
+        builder,
+        &mut destination,
+        &queries,
+    );
+
+    dispatcher.run().expect("run dispatcher");
+    // the canonical form quotes the keys
+    let path = Value::String(r#"$."a"."b"[*]"#.to_string());
+    assert_eq!(vec![path.clone(), path], destination.row(0).unwrap());
+    assert_eq!(vec![Value::Null, Value::Null], destination.row(1).unwrap());
+}
+
+#[test]
+fn test_postgres_reg_types() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let queries = [
+        "select c, p from (values (1, 'pg_class'::regclass, 'now'::regproc), (2, null, null)) \
+         v(i, c, p) order by i",
+    ];
+    let read = |policy| {
+        let mut source = PostgresSource::new(&dburl, 1).unwrap();
+        source.reg_types(policy);
+        let mut destination = MemoryDestination::new();
+        Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(
+            source,
+            &mut destination,
+            &queries,
+        )
+        .run()
+        .expect("run dispatcher");
+        (destination.row(0).unwrap(), destination.row(1).unwrap())
+    };
+
+    // pg_class and now() have fixed OIDs
+    let (row, nulls) = read(RegTypePolicy::Oid);
+    assert_eq!(vec![Value::I64(1259), Value::I64(1299)], row);
+    assert_eq!(vec![Value::Null, Value::Null], nulls);
+
+    let (row, nulls) = read(RegTypePolicy::Name);
+    assert_eq!(
+        vec![
+            Value::String("pg_class".to_string()),
+            Value::String("now".to_string())
+        ],
+        row
+    );
+    assert_eq!(vec![Value::Null, Value::Null], nulls);
+}
+
+#[test]
+fn test_postgres_refcursor() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let queries = ["select test_table_cursor(2)"];
+    let mut builder = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+    builder.follow_refcursor();
+    let mut destination = MemoryDestination::new();
+    let dispatcher = Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(
+        builder,
+        &mut destination,
+        &queries,
+    );
+
+    dispatcher.run().expect("run dispatcher");
+    let rows: Vec<_> = (0..4).map(|i| destination.row(i).unwrap()).collect();
+    let expected = [
+        (2, Some("str2")),
+        (3, Some("b")),
+        (4, Some("c")),
+        (1314, None),
+    ];
+    for (row, (test_int, test_str)) in rows.into_iter().zip(&expected) {
+        let test_str = test_str.map_or(Value::Null, |s| Value::String(s.to_string()));
+        assert_eq!(vec![Value::I64(*test_int), test_str], row);
+    }
+}
+
+#[test]
+fn test_postgres_refcursor_ambiguous() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let queries = ["select test_table_cursors()"];
+    let mut builder = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+    builder.follow_refcursor();
+    let mut destination = MemoryDestination::new();
+    let dispatcher = Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(
+        builder,
+        &mut destination,
+        &queries,
+    );
+
+    assert!(dispatcher.run().is_err());
+}
+
+#[test]
+fn test_postgres_refcursor_columns() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+    let mut client = Client::connect(&dburl, NoTls).unwrap();
+    // the count of the calls to test_query_cursor, from 0 on a new sequence
+    let calls = |client: &mut Client| -> i64 {
+        let query = "select case when is_called then last_value else 0 end from test_cursor_calls";
+        client.query_one(query, &[]).unwrap().get(0)
+    };
+
+    // ROW(...) is a column of the pseudo-type `record`
+    let queries = [
+        "select test_query_cursor('select test_int, row(test_int, test_str) as pair from test_table where test_int >= 2 order by test_int')",
+        "select test_query_cursor('select 5 as test_int, row(1, 2) as pair')",
+    ];
+    let before = calls(&mut client);
+    let mut source = PostgresSource::<Binary>::new(&dburl, 2).unwrap();
+    source.follow_refcursor();
+    source.pseudo_types(PseudoTypePolicy::Skip);
+    let mut destination = MemoryDestination::new();
+    Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(source, &mut destination, &queries)
+        .run()
+        .expect("run dispatcher");
+
+    // the first call also told the columns, but it ran only once
+    assert_eq!(2, calls(&mut client) - before);
+    assert_eq!(1, destination.row(0).unwrap().len());
+    assert_eq!(
+        array![Some(2), Some(3), Some(4), Some(1314), Some(5)],
+        destination.column_view::<Option<i64>>(0).unwrap()
+    );
+
+    let mut source = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+    source.follow_refcursor();
+    source.set_queries(&queries[1..]);
+    match source.fetch_metadata() {
+        Err(ConnectorAgentError::UnmappablePseudoType(col, ty)) => {
+            assert_eq!(("pair", "record"), (col.as_str(), ty.as_str()));
+        }
+        other => panic!(
+            "expecting an unmappable pseudo-type error, got {:?}",
+            other.err()
+        ),
+    }
+
+    // the rows of a cursor cannot be rewritten to cast or compute columns
+    let mut source = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+    source.follow_refcursor();
+    source.reg_types(RegTypePolicy::Name);
+    source.set_queries(&["select test_query_cursor('select ''pg_class''::regclass as rel')"]);
+    assert!(source.fetch_metadata().is_err());
+
+    let mut source = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+    source.follow_refcursor();
+    source.add_computed_column("twice", "test_int * 2");
+    source.set_queries(&queries[..1]);
+    assert!(source.fetch_metadata().is_err());
+}
+
+#[test]
+fn test_postgres_numeric_scale() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let cases = [
+        (DecimalRounding::HalfUp, ["1.23", "1.23", "-1.24"]),
+        (DecimalRounding::HalfEven, ["1.23", "1.22", "-1.24"]),
+        (DecimalRounding::Down, ["1.23", "1.22", "-1.23"]),
+        (DecimalRounding::Up, ["1.24", "1.23", "-1.24"]),
+    ];
+
+    for (rounding, expected) in &cases {
+        let mut source = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+        source.numeric_scale(2, *rounding);
+        source.set_queries(&[
+            "select n::numeric from (values (1, 1.2345), (2, 1.225), (3, -1.235)) t(i, n) order by i",
+        ]);
+        source.fetch_metadata().unwrap();
+
+        let mut partitions = source.partition().unwrap();
+        let mut partition = partitions.remove(0);
+        partition.prepare().expect("run query");
+        let mut parser = partition.parser().unwrap();
+
+        let mut values = vec![];
+        for _ in 0..3 {
+            let v: Option<Decimal> = parser.produce().unwrap();
+            values.push(v.unwrap().to_string());
+        }
+        assert_eq!(expected.to_vec(), values, "{:?}", rounding);
+    }
+}
+
 #[test]
 fn test_postgres_agg() {
     let _ = env_logger::builder().is_test(true).try_init();
@@ -242,3 +951,541 @@ fn test_postgres_csv() {
         dst.column_view::<Option<bool>>(4).unwrap()
     );
 }
+
+#[test]
+fn test_postgres_pseudo_type() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    // ROW(...) without a composite type behind it is an anonymous `record`
+    let queries = ["select test_int, row(test_int, test_str) as pair, test_float from test_table"];
+
+    let mut source = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+    source.set_queries(&queries);
+    match source.fetch_metadata() {
+        Err(ConnectorAgentError::UnmappablePseudoType(col, ty)) => {
+            assert_eq!("pair", col);
+            assert_eq!("record", ty);
+        }
+        other => panic!(
+            "expecting an unmappable pseudo-type error, got {:?}",
+            other.err()
+        ),
+    }
+
+    let mut source = PostgresSource::new(&dburl, 1).unwrap();
+    source.pseudo_types(PseudoTypePolicy::Skip);
+    let mut destination = MemoryDestination::new();
+    let dispatcher = Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(
+        source,
+        &mut destination,
+        &queries,
+    );
+    dispatcher.run().expect("run dispatcher");
+    assert_eq!(2, destination.row(0).unwrap().len());
+    assert_eq!(
+        array![Some(1), Some(2), Some(0), Some(3), Some(4), Some(1314)],
+        destination.column_view::<Option<i64>>(0).unwrap()
+    );
+}
+
+#[test]
+fn test_postgres_rate_limit() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    // 6 rows of 10 bytes each in binary COPY: a tuple header, a length prefix and an int4
+    let queries = [
+        "select test_int from test_table where test_int < 2",
+        "select test_int from test_table where test_int >= 2",
+    ];
+    let mut builder = PostgresSource::new(&dburl, 2).unwrap();
+    assert!(builder.rate_limit(0).is_err());
+    builder.rate_limit(20).unwrap();
+    builder.buf_size(1).unwrap();
+    let mut destination = MemoryDestination::new();
+    let dispatcher = Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(
+        builder,
+        &mut destination,
+        &queries,
+    );
+
+    let start = Instant::now();
+    dispatcher.run().expect("run dispatcher");
+    // 20 bytes go through as the initial burst, the remaining 40 take 2 seconds
+    assert!(start.elapsed() >= Duration::from_millis(1900));
+    assert_eq!(
+        array![Some(1), Some(0), Some(2), Some(3), Some(4), Some(1314)],
+        destination.column_view::<Option<i64>>(0).unwrap()
+    );
+}
+
+// A composite field: the oid of its type, its length or -1 for a null, then its value.
+fn composite_field(raw: &mut Vec<u8>, oid: u32, value: Option<&[u8]>) {
+    raw.extend_from_slice(&oid.to_be_bytes());
+    match value {
+        Some(value) => {
+            raw.extend_from_slice(&(value.len() as i32).to_be_bytes());
+            raw.extend_from_slice(value);
+        }
+        None => raw.extend_from_slice(&(-1i32).to_be_bytes()),
+    }
+}
+
+#[test]
+fn test_postgres_composite_wire_format() {
+    let address = Type::new(
+        "test_address".to_string(),
+        100_001,
+        Kind::Composite(vec![
+            Field::new("street".to_string(), Type::TEXT),
+            Field::new("zip".to_string(), Type::INT4),
+        ]),
+        "public".to_string(),
+    );
+    let person = Type::new(
+        "test_person".to_string(),
+        100_002,
+        Kind::Composite(vec![
+            Field::new("name".to_string(), Type::TEXT),
+            Field::new("home".to_string(), address),
+        ]),
+        "public".to_string(),
+    );
+
+    let mut home = 2i32.to_be_bytes().to_vec();
+    composite_field(&mut home, 25, Some(b"main st"));
+    composite_field(&mut home, 23, None);
+    let mut raw = 2i32.to_be_bytes().to_vec();
+    composite_field(&mut raw, 25, Some(b"ann"));
+    composite_field(&mut raw, 100_001, Some(&home));
+    assert_eq!(
+        Record {
+            fields: vec![
+                ("name".to_string(), RecordType::String),
+                (
+                    "home".to_string(),
+                    RecordType::Record(vec![
+                        ("street".to_string(), RecordType::String),
+                        ("zip".to_string(), RecordType::I64),
+                    ])
+                ),
+            ]
+            .into(),
+            values: vec![
+                RecordValue::String("ann".to_string()),
+                RecordValue::Record(vec![
+                    RecordValue::String("main st".to_string()),
+                    RecordValue::Null
+                ]),
+            ],
+        },
+        Record::from_sql(&person, &raw).unwrap()
+    );
+
+    // the nested record itself is null
+    let mut raw = 2i32.to_be_bytes().to_vec();
+    composite_field(&mut raw, 25, Some(b"bob"));
+    composite_field(&mut raw, 100_001, None);
+    assert_eq!(
+        vec![RecordValue::String("bob".to_string()), RecordValue::Null],
+        Record::from_sql(&person, &raw).unwrap().values
+    );
+
+    // cut in the middle of a field
+    assert!(Record::from_sql(&person, &raw[..raw.len() - 2]).is_err());
+}
+
+#[test]
+fn test_postgres_composite() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let queries = ["select p from (\
+         select row('ann', 1.5, 'happy', row('main st', 12345))::test_person as p \
+         union all select row('bob', null, null, null)::test_person \
+         union all select null::test_person) t"];
+    let builder = PostgresSource::new(&dburl, 1).unwrap();
+    let mut destination = ArrowDestination::new();
+    let dispatcher =
+        Dispatcher::<_, _, PostgresArrowTransport>::new(builder, &mut destination, &queries);
+
+    dispatcher.run().expect("run dispatcher");
+    let records = destination.finish(vec!["p".to_string()]).unwrap();
+    assert_eq!(1, records.len());
+
+    let people = records[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StructArray>()
+        .unwrap();
+    // the schema keeps the names of the fields
+    match records[0].schema().field(0).data_type() {
+        DataType::Struct(fields) => assert_eq!(
+            vec!["name", "score", "mood", "home"],
+            fields.iter().map(|f| f.name().as_str()).collect::<Vec<_>>()
+        ),
+        dt => panic!("unexpected type {:?}", dt),
+    }
+    assert!(people.is_valid(0) && people.is_valid(1) && people.is_null(2));
+
+    let strings = |name| {
+        people
+            .column_by_name(name)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<LargeStringArray>()
+            .unwrap()
+    };
+    assert_eq!("ann", strings("name").value(0));
+    assert_eq!("bob", strings("name").value(1));
+    assert_eq!("happy", strings("mood").value(0));
+    assert!(strings("mood").is_null(1));
+
+    let scores = people
+        .column_by_name("score")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap();
+    assert_eq!(1.5, scores.value(0));
+    assert!(scores.is_null(1));
+
+    let homes = people
+        .column_by_name("home")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<StructArray>()
+        .unwrap();
+    assert!(homes.is_valid(0) && homes.is_null(1));
+    let streets = homes
+        .column_by_name("street")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<LargeStringArray>()
+        .unwrap();
+    assert_eq!("main st", streets.value(0));
+    let zips = homes
+        .column_by_name("zip")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(12345, zips.value(0));
+}
+
+#[test]
+fn test_postgres_composite_shared_fields() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let mut source = PostgresSource::<Binary>::new(&dburl, 1).unwrap();
+    source.set_queries(&["select p from (\
+         select row('ann', 1.5, 'happy', null)::test_person as p \
+         union all select row('bob', null, null, null)::test_person) t"]);
+    source.fetch_metadata().unwrap();
+
+    let mut partition = source.partition().unwrap().remove(0);
+    partition.prepare().expect("run query");
+    let mut parser = partition.parser().unwrap();
+
+    let ann = Produce::<Option<Record>>::produce(&mut parser)
+        .unwrap()
+        .unwrap();
+    let bob = Produce::<Option<Record>>::produce(&mut parser)
+        .unwrap()
+        .unwrap();
+    // the records of a column share its fields
+    assert!(Arc::ptr_eq(&ann.fields, &bob.fields));
+    assert_eq!(4, ann.fields.len());
+    assert_eq!(RecordValue::String("bob".to_string()), bob.values[0]);
+}
+
+// A range of timestamptzs: its flags, then each finite bound as its length and value.
+fn tstz_range(flags: u8, bounds: &[DateTime<Utc>]) -> Vec<u8> {
+    let epoch = Utc.ymd(2000, 1, 1).and_hms(0, 0, 0);
+    let mut raw = vec![flags];
+    for bound in bounds {
+        let micros = (*bound - epoch).num_microseconds().unwrap();
+        raw.extend_from_slice(&8i32.to_be_bytes());
+        raw.extend_from_slice(&micros.to_be_bytes());
+    }
+    raw
+}
+
+#[test]
+fn test_postgres_multirange_wire_format() {
+    let ty = Type::new(
+        "tstzmultirange".to_string(),
+        4534,
+        Kind::Simple,
+        "pg_catalog".to_string(),
+    );
+    let ranges = [
+        // [lower, upper)
+        tstz_range(
+            0x02,
+            &[
+                Utc.ymd(2021, 1, 1).and_hms(0, 0, 0),
+                Utc.ymd(2021, 1, 2).and_hms(0, 0, 0),
+            ],
+        ),
+        // (lower, infinity)
+        tstz_range(0x10, &[Utc.ymd(2021, 3, 1).and_hms_milli(12, 30, 0, 500)]),
+    ];
+    let mut raw = (ranges.len() as i32).to_be_bytes().to_vec();
+    for range in &ranges {
+        raw.extend_from_slice(&(range.len() as i32).to_be_bytes());
+        raw.extend_from_slice(range);
+    }
+    let multirange = Multirange::from_sql(&ty, &raw).unwrap();
+    assert_eq!(
+        Multirange(vec![
+            Range {
+                lower: Some(RangeBound::TimestampTz(
+                    Utc.ymd(2021, 1, 1).and_hms(0, 0, 0)
+                )),
+                upper: Some(RangeBound::TimestampTz(
+                    Utc.ymd(2021, 1, 2).and_hms(0, 0, 0)
+                )),
+                lower_inc: true,
+                upper_inc: false,
+            },
+            Range {
+                lower: Some(RangeBound::TimestampTz(
+                    Utc.ymd(2021, 3, 1).and_hms_milli(12, 30, 0, 500)
+                )),
+                upper: None,
+                lower_inc: false,
+                upper_inc: false,
+            },
+        ]),
+        multirange
+    );
+    assert_eq!(
+        vec![
+            r#"["2021-01-01 00:00:00+00","2021-01-02 00:00:00+00")"#,
+            r#"("2021-03-01 12:30:00.500+00",)"#,
+        ],
+        multirange
+            .0
+            .iter()
+            .map(|range| range.to_string())
+            .collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        Multirange(vec![]),
+        Multirange::from_sql(&ty, &0i32.to_be_bytes()).unwrap()
+    );
+    assert!(Multirange::from_sql(&ty, &1i32.to_be_bytes()).is_err());
+    assert!(!<Multirange as FromSql>::accepts(&Type::TSTZ_RANGE));
+}
+
+#[test]
+fn test_postgres_multirange() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+    let queries = [
+        "select r from (values \
+            (1, '{[2021-01-01 00:00+00, 2021-01-02 00:00+00), [2021-03-01 00:00+00, 2021-03-05 00:00+00]}'::tstzmultirange), \
+            (2, '{}'::tstzmultirange), (3, null::tstzmultirange)) t(i, r) order by i",
+    ];
+    let mut destination = ArrowDestination::new();
+    Dispatcher::<_, _, PostgresArrowTransport>::new(
+        PostgresSource::new(&dburl, 1).unwrap(),
+        &mut destination,
+        &queries,
+    )
+    .run()
+    .expect("run dispatcher");
+
+    let batches = destination.finish(vec!["r".to_string()]).unwrap();
+    let s = |v: &str| Some(v.to_string());
+    assert_eq!(
+        vec![
+            Some(vec![
+                s(r#"["2021-01-01 00:00:00+00","2021-01-02 00:00:00+00")"#),
+                s(r#"["2021-03-01 00:00:00+00","2021-03-05 00:00:00+00"]"#),
+            ]),
+            Some(vec![]),
+            None,
+        ],
+        string_lists(batches[0].column(0).as_ref())
+    );
+}
+
+#[test]
+fn test_postgres_ctid_range() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+    let mut client = Client::connect(&dburl, NoTls).unwrap();
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS test_ctid, test_ctid_parted, test_ctid_child, test_ctid_parent;
+             CREATE TABLE test_ctid(id INTEGER NOT NULL, s TEXT);
+             INSERT INTO test_ctid VALUES (1, 'a');
+             CREATE TABLE test_ctid_parted(id INTEGER NOT NULL) PARTITION BY RANGE (id);
+             CREATE TABLE test_ctid_parent(id INTEGER NOT NULL);
+             CREATE TABLE test_ctid_child() INHERITS (test_ctid_parent);",
+        )
+        .unwrap();
+
+    let ty = SourceType::Postgres;
+    let (min, max) = ty
+        .get_col_range(&dburl, "SELECT * FROM test_ctid", "ctid")
+        .unwrap();
+    assert_eq!((0, 0), (min, max));
+
+    // rows appended after the range was taken land past it
+    client
+        .batch_execute(
+            "INSERT INTO test_ctid SELECT i, repeat('x', 500) FROM generate_series(2, 100) i",
+        )
+        .unwrap();
+    let mut count = |query: &str| -> i64 {
+        let sql = format!("SELECT count(*) FROM ({}) t", query);
+        client.query_one(&*sql, &[]).unwrap().get(0)
+    };
+    let closed = ty
+        .get_part_query("SELECT * FROM test_ctid", "ctid", min, max + 1, false)
+        .unwrap();
+    let last = ty
+        .get_part_query("SELECT * FROM test_ctid", "ctid", min, max + 1, true)
+        .unwrap();
+    assert!(count(&closed) < 100);
+    assert_eq!(100, count(&last));
+
+    for table in &["test_ctid_parted", "test_ctid_parent"] {
+        let query = format!("SELECT * FROM {}", table);
+        assert!(ty.get_col_range(&dburl, &query, "ctid").is_err());
+    }
+    assert!(ty
+        .get_col_range(&dburl, "SELECT * FROM test_ctid_child", "ctid")
+        .is_ok());
+}
+
+#[test]
+fn test_postgres_matview_freshness() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+    let mut client = Client::connect(&dburl, NoTls).unwrap();
+    client
+        .batch_execute(
+            "DROP MATERIALIZED VIEW IF EXISTS test_matview;
+             DROP TABLE IF EXISTS test_matview_base, test_matview_refresh;
+             CREATE TABLE test_matview_base(id INTEGER NOT NULL);
+             INSERT INTO test_matview_base VALUES (1), (2);
+             CREATE MATERIALIZED VIEW test_matview AS SELECT count(*) AS n FROM test_matview_base;
+             CREATE TABLE test_matview_refresh(refreshed_at TIMESTAMPTZ NOT NULL);
+             INSERT INTO test_matview_refresh VALUES (now());
+             INSERT INTO test_matview_base VALUES (3);",
+        )
+        .unwrap();
+
+    let read_count = |view: &str| {
+        let mut source = PostgresSource::new(&dburl, 1).unwrap();
+        source.matview_freshness(
+            view,
+            "SELECT max(refreshed_at) FROM test_matview_refresh",
+            Duration::from_secs(3600),
+            StaleMatviewPolicy::Refresh,
+        );
+        let mut destination = MemoryDestination::new();
+        Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(
+            source,
+            &mut destination,
+            &["SELECT n FROM test_matview"],
+        )
+        .run()
+        .map(|_| destination.row(0).unwrap())
+    };
+
+    // fresh enough, so the row inserted after the view was created is not in it yet
+    assert_eq!(vec![Value::I64(2)], read_count("test_matview").unwrap());
+
+    client
+        .batch_execute("UPDATE test_matview_refresh SET refreshed_at = now() - interval '2 hours'")
+        .unwrap();
+    assert_eq!(
+        vec![Value::I64(3)],
+        read_count("public.test_matview").unwrap()
+    );
+
+    // the view is never pasted into the refresh as it is
+    assert!(read_count("test_matview; DROP TABLE test_matview_base").is_err());
+    assert!(read_count("test_matview_base").is_err());
+    client
+        .query_one("SELECT count(*) FROM test_matview_base", &[])
+        .unwrap();
+}
+
+#[test]
+fn test_postgres_notice_policy() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_URL").unwrap();
+
+    let queries = ["select truncate_warn(test_str, 2) as s from test_table where test_int = 2"];
+    let mut source = PostgresSource::new(&dburl, 1).unwrap();
+    source.notice_policy(NoticePolicy::Collect);
+    let mut destination = MemoryDestination::new();
+    let metrics = Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(
+        source,
+        &mut destination,
+        &queries,
+    )
+    .run_with_metrics()
+    .expect("run dispatcher");
+
+    assert_eq!(
+        vec![Value::String("st".to_string())],
+        destination.row(0).unwrap()
+    );
+    let truncated = Notice {
+        severity: "WARNING".to_string(),
+        code: "01000".to_string(),
+        message: "value \"str2\" truncated to 2 characters".to_string(),
+    };
+    assert!(!metrics.notices.is_empty());
+    assert!(metrics.notices.iter().all(|n| *n == truncated));
+
+    // nothing is collected without the policy
+    let source = PostgresSource::new(&dburl, 1).unwrap();
+    let metrics = Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(
+        source,
+        &mut destination,
+        &queries,
+    )
+    .run_with_metrics()
+    .expect("run dispatcher");
+    assert!(metrics.notices.is_empty());
+}
+
+// Needs a server listening on a Unix socket, e.g. `host=/var/run/postgresql dbname=postgres`.
+#[test]
+#[ignore]
+fn test_postgres_unix_socket() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let dburl = env::var("POSTGRES_SOCKET_URL").unwrap();
+
+    // the server has no address of its own for a connection over a Unix socket
+    let queries = ["select inet_server_addr() is null as local"];
+    let mut destination = MemoryDestination::new();
+    Dispatcher::<_, _, PostgresMemoryTransport<Binary>>::new(
+        PostgresSource::new(&dburl, 1).unwrap(),
+        &mut destination,
+        &queries,
+    )
+    .run()
+    .expect("run dispatcher");
+    assert_eq!(vec![Value::Bool(true)], destination.row(0).unwrap());
+}
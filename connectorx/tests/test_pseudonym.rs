@@ -0,0 +1,117 @@
+use arrow::array::{Float64Array, LargeStringArray};
+use arrow::record_batch::RecordBatch;
+use connectorx::{
+    destinations::arrow::ArrowDestination,
+    impl_transport,
+    sources::sqlite::{SqliteSource, SqliteTypeSystem},
+    Dispatcher, DummyTypeSystem, Pseudonymizer, TypeConversion,
+};
+use rusqlite::Connection;
+use std::env;
+use std::fs;
+
+struct SqliteArrowTransport;
+
+impl_transport!(
+    name = SqliteArrowTransport,
+    systems = SqliteTypeSystem => DummyTypeSystem,
+    route = SqliteSource => ArrowDestination,
+    mappings = {
+        { Int8[i64]      => I64[i64]       | conversion all }
+        { Real[f64]      => F64[f64]       | conversion all }
+        { Text[Box<str>] => String[String] | conversion half }
+    }
+);
+
+impl TypeConversion<Box<str>, String> for SqliteArrowTransport {
+    fn convert(val: Box<str>) -> String {
+        val.to_string()
+    }
+}
+
+fn shop_db(name: &str) -> String {
+    let path = env::temp_dir().join(format!("pseudonym_{}_{}.db", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE users(id INTEGER, name TEXT);
+         INSERT INTO users VALUES (1, 'ann'), (2, 'bob'), (NULL, 'eve');
+         CREATE TABLE orders(user_id TEXT, amount REAL);
+         INSERT INTO orders VALUES ('2', 3.5), ('1', 1.5), ('2', 2.0);",
+    )
+    .unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+fn read(db: &str, query: &str, col: &str, key: &[u8]) -> Vec<RecordBatch> {
+    Dispatcher::<_, _, SqliteArrowTransport>::to_arrow(SqliteSource::new(db, 1).unwrap(), &[query])
+        .with_pseudonymize(col, key)
+        .run()
+        .expect("run dispatcher")
+}
+
+fn strings(batch: &RecordBatch, col: usize) -> Vec<Option<String>> {
+    let col = batch.column(col);
+    let col = col.as_any().downcast_ref::<LargeStringArray>().unwrap();
+    col.iter().map(|v| v.map(str::to_string)).collect()
+}
+
+#[test]
+fn test_pseudonymizer() {
+    // RFC 4231, test case 2
+    assert_eq!(
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+        Pseudonymizer::new(b"Jefe").pseudonym("what do ya want for nothing?")
+    );
+}
+
+#[test]
+fn test_pseudonymize() {
+    let db = shop_db("join");
+    let key = b"secret";
+    let users = read(&db, "SELECT id, name FROM users", "id", key);
+    let ids = strings(&users[0], 0);
+    assert_eq!(3, ids.len());
+    assert_eq!(None, ids[2]);
+    assert_ne!(ids[0], ids[1]);
+    assert_eq!(64, ids[0].as_ref().unwrap().len());
+    // the other columns stay as they are
+    assert_eq!(
+        vec![
+            Some("ann".to_string()),
+            Some("bob".to_string()),
+            Some("eve".to_string())
+        ],
+        strings(&users[0], 1)
+    );
+
+    // a separate run of the same query gives the same pseudonyms
+    let again = read(&db, "SELECT id, name FROM users", "id", key);
+    assert_eq!(ids, strings(&again[0], 0));
+
+    // and so does another table holding the key, even as text
+    let orders = read(&db, "SELECT user_id, amount FROM orders", "user_id", key);
+    assert_eq!(
+        vec![ids[1].clone(), ids[0].clone(), ids[1].clone()],
+        strings(&orders[0], 0)
+    );
+    let amounts = orders[0].column(1);
+    let amounts = amounts.as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(3.5, amounts.value(0));
+
+    // another key gives other pseudonyms
+    let other = read(&db, "SELECT id, name FROM users", "id", b"other");
+    assert_ne!(ids[0], strings(&other[0], 0)[0]);
+}
+
+#[test]
+fn test_pseudonymize_missing_column() {
+    let db = shop_db("missing");
+    let result = Dispatcher::<_, _, SqliteArrowTransport>::to_arrow(
+        SqliteSource::new(&db, 1).unwrap(),
+        &["SELECT id, name FROM users"],
+    )
+    .with_pseudonymize("email", b"secret")
+    .run();
+    assert!(result.is_err());
+}
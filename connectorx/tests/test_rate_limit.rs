@@ -0,0 +1,48 @@
+use connectorx::rate_limit::RateLimiter;
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+#[test]
+fn test_rate_limit_shared() {
+    let per_sec = 20_000;
+    let limiter = Arc::new(RateLimiter::new(per_sec).unwrap());
+
+    // 4 readers taking 1000 bytes at a time, 40_000 bytes in total
+    let start = Instant::now();
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let limiter = Arc::clone(&limiter);
+            thread::spawn(move || {
+                for _ in 0..10 {
+                    limiter.acquire(1000);
+                }
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    // the first second worth of bytes goes through as a burst, the rest at the cap
+    let throughput = (40_000 - per_sec) as f64 / elapsed;
+    assert!(
+        throughput <= per_sec as f64 * 1.01,
+        "{} bytes/s over the cap",
+        throughput
+    );
+    assert!(elapsed < 3., "took {}s", elapsed);
+}
+
+#[test]
+fn test_rate_limit_large_take() {
+    assert!(RateLimiter::new(0).is_err());
+    let limiter = RateLimiter::new(1000).unwrap();
+
+    // a take larger than the bucket is let through, and the following ones pay for it
+    let start = Instant::now();
+    limiter.acquire(1500);
+    limiter.acquire(1);
+    assert!(start.elapsed().as_secs_f64() >= 0.5);
+}
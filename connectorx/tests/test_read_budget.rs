@@ -0,0 +1,110 @@
+use connectorx::{
+    destinations::memory::MemoryDestination, read_budget::ReadBudget, sources::dummy::DummySource,
+    transports::DummyMemoryTransport, Dispatcher, DummyTypeSystem,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_read_budget_bound() {
+    let budget = Arc::new(ReadBudget::new(2, 1).unwrap());
+    let live = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let handles: Vec<_> = (0..6)
+        .map(|_| {
+            let (budget, live, peak) = (budget.clone(), live.clone(), peak.clone());
+            thread::spawn(move || {
+                for _ in 0..4 {
+                    let _slot = budget.acquire();
+                    let now = live.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(2));
+                    live.fetch_sub(1, Ordering::SeqCst);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(2, peak.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_read_budget_round_robin() {
+    let budget = Arc::new(ReadBudget::new(1, 1).unwrap());
+    let order = Arc::new(Mutex::new(vec![]));
+    // the large reader holds the only slot until the small one queued up behind it
+    let slot = budget.acquire();
+    let small = {
+        let (budget, order) = (budget.clone(), order.clone());
+        thread::spawn(move || {
+            let _slot = budget.acquire();
+            order.lock().unwrap().push("small");
+        })
+    };
+    thread::sleep(Duration::from_millis(50));
+    drop(slot);
+    // the next batch of the large reader waits for the small one
+    let _slot = budget.acquire();
+    order.lock().unwrap().push("large");
+    small.join().unwrap();
+    assert_eq!(vec!["small", "large"], *order.lock().unwrap());
+}
+
+#[test]
+fn test_read_budget_dispatch() {
+    let schema = [DummyTypeSystem::I64(false), DummyTypeSystem::String(true)];
+    // one large partition and a few small ones
+    let queries = ["500,2", "3,2", "0,2", "7,2", "1,2"];
+
+    let mut expected = MemoryDestination::new();
+    Dispatcher::<_, _, DummyMemoryTransport>::new(
+        DummySource::new(&["a", "b"], &schema),
+        &mut expected,
+        &queries,
+    )
+    .run()
+    .expect("run dispatcher");
+
+    let mut destination = MemoryDestination::new();
+    let metrics = Dispatcher::<_, _, DummyMemoryTransport>::new(
+        DummySource::new(&["a", "b"], &schema),
+        &mut destination,
+        &queries,
+    )
+    .with_read_budget(2, 16)
+    .run_with_metrics()
+    .expect("run dispatcher");
+
+    assert_eq!(
+        vec![500, 3, 0, 7, 1],
+        metrics
+            .partitions
+            .iter()
+            .map(|p| p.rows)
+            .collect::<Vec<_>>()
+    );
+    for row in 0..511 {
+        assert_eq!(expected.row(row).unwrap(), destination.row(row).unwrap());
+    }
+}
+
+#[test]
+fn test_read_budget_zero() {
+    assert!(ReadBudget::new(0, 1).is_err());
+    assert!(ReadBudget::new(1, 0).is_err());
+
+    let schema = [DummyTypeSystem::I64(false)];
+    let mut destination = MemoryDestination::new();
+    let result = Dispatcher::<_, _, DummyMemoryTransport>::new(
+        DummySource::new(&["a"], &schema),
+        &mut destination,
+        &["3,1"],
+    )
+    .with_read_budget(0, 16)
+    .run();
+    assert!(result.is_err());
+}
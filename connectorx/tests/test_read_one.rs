@@ -0,0 +1,100 @@
+use connectorx::{
+    destinations::memory::Value, sources::dummy::DummySource, transports::DummyMemoryTransport,
+    ConnectorAgentError, Dispatcher, DummyTypeSystem, ExtraResults,
+};
+
+#[test]
+fn test_read_one_row() {
+    let schema = [
+        DummyTypeSystem::I64(false),
+        DummyTypeSystem::String(true),
+        DummyTypeSystem::Bool(true),
+    ];
+    let row = Dispatcher::<_, _, DummyMemoryTransport>::read_one_row(
+        DummySource::new(&["a", "b", "c"], &schema),
+        "1,3",
+        ExtraResults::Error,
+    )
+    .unwrap();
+    assert_eq!(
+        Some(vec![
+            Value::I64(0),
+            Value::String("0".to_string()),
+            Value::Bool(true)
+        ]),
+        row
+    );
+
+    let row = Dispatcher::<_, _, DummyMemoryTransport>::read_one_row(
+        DummySource::new(&["a", "b", "c"], &schema),
+        "0,3",
+        ExtraResults::Error,
+    )
+    .unwrap();
+    assert_eq!(None, row);
+
+    let res = Dispatcher::<_, _, DummyMemoryTransport>::read_one_row(
+        DummySource::new(&["a", "b", "c"], &schema),
+        "5,3",
+        ExtraResults::Error,
+    );
+    assert!(matches!(res, Err(ConnectorAgentError::TooManyRows(5))));
+
+    let row = Dispatcher::<_, _, DummyMemoryTransport>::read_one_row(
+        DummySource::new(&["a", "b", "c"], &schema),
+        "5,3",
+        ExtraResults::TakeFirst,
+    )
+    .unwrap();
+    assert_eq!(Some(Value::I64(0)), row.map(|r| r[0].clone()));
+}
+
+#[test]
+fn test_read_scalar() {
+    let scalar = Dispatcher::<_, _, DummyMemoryTransport>::read_scalar::<i64>(
+        DummySource::new(&["a"], &[DummyTypeSystem::I64(false)]),
+        "1,1",
+        ExtraResults::Error,
+    )
+    .unwrap();
+    assert_eq!(Some(0), scalar);
+
+    let scalar = Dispatcher::<_, _, DummyMemoryTransport>::read_scalar::<String>(
+        DummySource::new(&["a"], &[DummyTypeSystem::String(true)]),
+        "1,1",
+        ExtraResults::Error,
+    )
+    .unwrap();
+    assert_eq!(Some("0".to_string()), scalar);
+
+    let schema = [DummyTypeSystem::F64(true), DummyTypeSystem::I64(false)];
+    let res = Dispatcher::<_, _, DummyMemoryTransport>::read_scalar::<f64>(
+        DummySource::new(&["a", "b"], &schema),
+        "1,2",
+        ExtraResults::Error,
+    );
+    assert!(matches!(res, Err(ConnectorAgentError::TooManyColumns(2))));
+
+    let res = Dispatcher::<_, _, DummyMemoryTransport>::read_scalar::<f64>(
+        DummySource::new(&[] as &[&str], &[]),
+        "1,0",
+        ExtraResults::TakeFirst,
+    );
+    assert!(matches!(res, Err(ConnectorAgentError::NoColumns)));
+
+    let scalar = Dispatcher::<_, _, DummyMemoryTransport>::read_scalar::<f64>(
+        DummySource::new(&["a", "b"], &schema),
+        "3,2",
+        ExtraResults::TakeFirst,
+    )
+    .unwrap();
+    assert_eq!(Some(0.), scalar);
+
+    // asking for the wrong type is a type error, not a silent None
+    let res = Dispatcher::<_, _, DummyMemoryTransport>::read_scalar::<String>(
+        DummySource::new(&["a"], &[DummyTypeSystem::I64(false)]),
+        "1,1",
+        ExtraResults::Error,
+    );
+    assert!(matches!(res, Err(ConnectorAgentError::TypeCheckFailed(..))));
+}
@@ -0,0 +1,228 @@
+#![feature(generic_associated_types)]
+#![allow(incomplete_features)]
+
+use chrono::{DateTime, Utc};
+use connectorx::{
+    impl_transport,
+    sources::{csv::CSVSource, dummy::DummySource},
+    Consume, DataOrder, Destination, DestinationPartition, Dispatcher, DummyTypeSystem, Result,
+    TypeConversion,
+};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::env;
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::Duration;
+
+// bytes the current thread has allocated and not freed yet, and the most there were at once
+thread_local! {
+    static HELD: Cell<usize> = const { Cell::new(0) };
+    static HELD_PEAK: Cell<usize> = const { Cell::new(0) };
+}
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = HELD.try_with(|held| {
+            held.set(held.get() + layout.size());
+            let _ = HELD_PEAK.try_with(|peak| peak.set(peak.get().max(held.get())));
+        });
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // memory freed by another thread than the one that allocated it is not counted there
+        let _ = HELD.try_with(|held| held.set(held.get().saturating_sub(layout.size())));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[derive(Default)]
+struct Counters {
+    // number of partitions that have started writing but not finalized yet
+    live: AtomicUsize,
+    peak: AtomicUsize,
+    started: AtomicUsize,
+}
+
+#[derive(Default)]
+struct TrackingDestination {
+    schema: Vec<DummyTypeSystem>,
+    counters: Arc<Counters>,
+}
+
+impl Destination for TrackingDestination {
+    const DATA_ORDERS: &'static [DataOrder] = &[DataOrder::RowMajor];
+    type TypeSystem = DummyTypeSystem;
+    type Partition<'a> = TrackingPartition;
+
+    fn allocate<S: AsRef<str>>(
+        &mut self,
+        _nrows: usize,
+        _names: &[S],
+        schema: &[DummyTypeSystem],
+        _data_order: DataOrder,
+    ) -> Result<()> {
+        self.schema = schema.to_vec();
+        Ok(())
+    }
+
+    fn partition(&mut self, counts: &[usize]) -> Result<Vec<TrackingPartition>> {
+        Ok(counts
+            .iter()
+            .map(|&nrows| TrackingPartition {
+                nrows,
+                ncols: self.schema.len(),
+                started: false,
+                counters: self.counters.clone(),
+            })
+            .collect())
+    }
+
+    fn schema(&self) -> &[DummyTypeSystem] {
+        &self.schema
+    }
+}
+
+struct TrackingPartition {
+    nrows: usize,
+    ncols: usize,
+    started: bool,
+    counters: Arc<Counters>,
+}
+
+impl<'a> DestinationPartition<'a> for TrackingPartition {
+    type TypeSystem = DummyTypeSystem;
+
+    fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    fn finalize(&mut self) -> Result<()> {
+        if self.started {
+            self.counters.live.fetch_sub(1, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+}
+
+impl<T> Consume<T> for TrackingPartition {
+    fn consume(&mut self, _value: T) -> Result<()> {
+        if !self.started {
+            self.started = true;
+            self.counters.started.fetch_add(1, Ordering::SeqCst);
+            let live = self.counters.live.fetch_add(1, Ordering::SeqCst) + 1;
+            self.counters.peak.fetch_max(live, Ordering::SeqCst);
+            // give concurrently running partitions the chance to overlap
+            sleep(Duration::from_millis(10));
+        }
+        Ok(())
+    }
+}
+
+struct DummyTrackingTransport;
+
+impl_transport!(
+    name = DummyTrackingTransport,
+    systems = DummyTypeSystem => DummyTypeSystem,
+    route = DummySource => TrackingDestination,
+    mappings = {
+        { F64[f64]                => F64[f64]                | conversion all}
+        { I64[i64]                => I64[i64]                | conversion all}
+        { Bool[bool]              => Bool[bool]              | conversion all}
+        { String[String]          => String[String]          | conversion all}
+        { DateTime[DateTime<Utc>] => DateTime[DateTime<Utc>] | conversion all}
+    }
+);
+
+#[test]
+fn test_sequential() {
+    let schema = [DummyTypeSystem::I64(false), DummyTypeSystem::String(true)];
+    let queries: Vec<String> = (0..8).map(|_| "5,2".to_string()).collect();
+    let mut destination = TrackingDestination::default();
+    let dispatcher = Dispatcher::<_, _, DummyTrackingTransport>::new(
+        DummySource::new(&["a", "b"], &schema),
+        &mut destination,
+        &queries,
+    )
+    .sequential();
+    dispatcher.run().expect("run dispatcher");
+
+    let counters = &destination.counters;
+    assert_eq!(8, counters.started.load(Ordering::SeqCst));
+    assert_eq!(1, counters.peak.load(Ordering::SeqCst));
+    assert_eq!(0, counters.live.load(Ordering::SeqCst));
+}
+
+struct CSVTrackingTransport;
+
+impl_transport!(
+    name = CSVTrackingTransport,
+    systems = DummyTypeSystem => DummyTypeSystem,
+    route = CSVSource => TrackingDestination,
+    mappings = {
+        { F64[f64]                => F64[f64]                | conversion all}
+        { I64[i64]                => I64[i64]                | conversion all}
+        { Bool[bool]              => Bool[bool]              | conversion all}
+        { String[String]          => String[String]          | conversion all}
+        { DateTime[DateTime<Utc>] => DateTime[DateTime<Utc>] | conversion all}
+    }
+);
+
+// the most bytes held at once on this thread while reading `files` one after another
+fn sequential_peak(files: &[String]) -> usize {
+    let schema = [DummyTypeSystem::I64(false), DummyTypeSystem::String(false)];
+    let mut destination = TrackingDestination::default();
+    let dispatcher = Dispatcher::<_, _, CSVTrackingTransport>::new(
+        CSVSource::new(&schema),
+        &mut destination,
+        files,
+    )
+    .sequential();
+
+    let base = HELD.with(Cell::get);
+    HELD_PEAK.with(|peak| peak.set(base));
+    dispatcher.run().expect("run dispatcher");
+    HELD_PEAK.with(Cell::get) - base
+}
+
+#[test]
+fn test_sequential_csv_memory() {
+    let dir = env::temp_dir().join(format!("connectorx-sequential-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("create dir");
+    let text = "x".repeat(100);
+    let files: Vec<String> = (0..4)
+        .map(|i| {
+            let path = dir.join(format!("part{}.csv", i));
+            let mut content = "id,text\n".to_string();
+            for row in 0..10000 {
+                content.push_str(&format!("{},{}\n", row, text));
+            }
+            fs::write(&path, content).expect("write csv");
+            path.to_str().unwrap().to_string()
+        })
+        .collect();
+
+    let one = sequential_peak(&files[..1]);
+    let all = sequential_peak(&files);
+    fs::remove_dir_all(&dir).expect("remove dir");
+
+    // only the partition being written holds its records, not the four of them
+    assert!(
+        all < one * 2,
+        "{} bytes held for four files, {} for one",
+        all,
+        one
+    );
+}
@@ -0,0 +1,148 @@
+use connectorx::{
+    destinations::memory::{MemoryDestination, Value},
+    impl_transport,
+    sources::{
+        sharded::ShardedSource,
+        sqlite::{SqliteSource, SqliteTypeSystem},
+    },
+    Dispatcher, DummyTypeSystem, TypeConversion,
+};
+use rusqlite::Connection;
+use std::env;
+use std::fs;
+
+struct ShardedSqliteTransport;
+
+impl_transport!(
+    name = ShardedSqliteTransport,
+    systems = SqliteTypeSystem => DummyTypeSystem,
+    route = ShardedSource<SqliteSource> => MemoryDestination,
+    mappings = {
+        { Int8[i64]      => I64[i64]       | conversion all }
+        { Text[Box<str>] => String[String] | conversion half }
+    }
+);
+
+impl TypeConversion<Box<str>, String> for ShardedSqliteTransport {
+    fn convert(val: Box<str>) -> String {
+        val.to_string()
+    }
+}
+
+// A shard of the users table, each user named after the shard it lives on.
+fn shard(name: &str, create: &str, ids: &[i64]) -> SqliteSource {
+    let path = env::temp_dir().join(format!("shard_{}_{}.db", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(create).unwrap();
+    for id in ids {
+        conn.execute(
+            "INSERT INTO users VALUES (?1, ?2)",
+            rusqlite::params![id, format!("{}{}", name, id)],
+        )
+        .unwrap();
+    }
+    SqliteSource::new(path.to_str().unwrap(), 2).unwrap()
+}
+
+const USERS: &str = "CREATE TABLE users(id INTEGER NOT NULL, name TEXT)";
+
+#[test]
+fn test_sharded_partitions() {
+    let source = ShardedSource::new(vec![
+        shard("a", USERS, &[1, 2, 3]),
+        shard("b", USERS, &[4, 5]),
+    ])
+    .with_routes(&[0, 1, 0]);
+    let queries = [
+        "SELECT id, name FROM users WHERE id % 2 = 1",
+        "SELECT id, name FROM users",
+        "SELECT id, name FROM users WHERE id % 2 = 0",
+    ];
+
+    let mut destination = MemoryDestination::new();
+    Dispatcher::<_, _, ShardedSqliteTransport>::new(source, &mut destination, &queries)
+        .with_partition_column("partition")
+        .run()
+        .expect("run dispatcher");
+
+    let mut rows: Vec<_> = (0..5).map(|r| destination.row(r).unwrap()).collect();
+    // every partition read the shard it was routed to
+    for row in &rows {
+        match (&row[1], &row[2]) {
+            (Value::String(name), Value::I64(0)) | (Value::String(name), Value::I64(2)) => {
+                assert!(name.starts_with('a'), "{:?}", row)
+            }
+            (Value::String(name), Value::I64(1)) => assert!(name.starts_with('b'), "{:?}", row),
+            _ => panic!("unexpected row {:?}", row),
+        }
+    }
+
+    rows.sort_by_key(|row| format!("{:?}", row[0]));
+    let ids: Vec<_> = rows.iter().map(|row| row[0].clone()).collect();
+    assert_eq!((1..=5).map(Value::I64).collect::<Vec<_>>(), ids);
+}
+
+#[test]
+fn test_sharded_partition_subset() {
+    let source = ShardedSource::new(vec![
+        shard("f", USERS, &[1, 2, 3]),
+        shard("g", USERS, &[4, 5]),
+    ])
+    .with_routes(&[0, 1, 0]);
+    let queries = [
+        "SELECT id, name FROM users WHERE id % 2 = 1",
+        "SELECT id, name FROM users",
+        "SELECT id, name FROM users WHERE id % 2 = 0",
+    ];
+
+    // the routes still go by the index among all the queries
+    let mut destination = MemoryDestination::new();
+    Dispatcher::<_, _, ShardedSqliteTransport>::new(source, &mut destination, &queries)
+        .with_partition_column("partition")
+        .with_partition_subset(1..3)
+        .run()
+        .expect("run dispatcher");
+
+    let mut rows: Vec<_> = (0..3).map(|r| destination.row(r).unwrap()).collect();
+    rows.sort_by_key(|row| format!("{:?}", row[0]));
+    assert_eq!(
+        vec![
+            vec![Value::I64(2), Value::String("f2".into()), Value::I64(2)],
+            vec![Value::I64(4), Value::String("g4".into()), Value::I64(1)],
+            vec![Value::I64(5), Value::String("g5".into()), Value::I64(1)],
+        ],
+        rows
+    );
+    assert!(destination.row(3).is_err());
+}
+
+#[test]
+fn test_sharded_schema_mismatch() {
+    let source = ShardedSource::new(vec![
+        shard("c", USERS, &[1]),
+        shard(
+            "d",
+            "CREATE TABLE users(id INTEGER NOT NULL, name INTEGER)",
+            &[2],
+        ),
+    ]);
+    let mut destination = MemoryDestination::new();
+    let result = Dispatcher::<_, _, ShardedSqliteTransport>::new(
+        source,
+        &mut destination,
+        &["SELECT id, name FROM users", "SELECT id, name FROM users"],
+    )
+    .run();
+    assert!(result.is_err());
+
+    // more queries than shards without routes
+    let source = ShardedSource::new(vec![shard("e", USERS, &[1])]);
+    let result = Dispatcher::<_, _, ShardedSqliteTransport>::new(
+        source,
+        &mut destination,
+        &["SELECT id, name FROM users", "SELECT id, name FROM users"],
+    )
+    .run();
+    assert!(result.is_err());
+}
@@ -0,0 +1,36 @@
+use connectorx::source_router::{SourceConn, SourceType};
+use std::convert::TryFrom;
+
+#[test]
+fn test_source_conn_unix_socket() {
+    let conn = SourceConn::try_from("host=/var/run/postgresql user=postgres dbname=tpch").unwrap();
+    assert!(matches!(conn.ty, SourceType::Postgres));
+    assert_eq!(
+        "host=/var/run/postgresql user=postgres dbname=tpch",
+        conn.conn
+    );
+
+    let conn = SourceConn::try_from("postgresql://%2Fvar%2Frun%2Fpostgresql/tpch").unwrap();
+    assert!(matches!(conn.ty, SourceType::Postgres));
+}
+
+#[test]
+fn test_ctid_part_query() {
+    let query = "SELECT * FROM test_table WHERE test_int < 1000";
+    assert_eq!(
+        "SELECT * FROM test_table WHERE (test_int < 1000) AND ctid >= CAST('(0,0)' AS tid) AND ctid < CAST('(4,0)' AS tid)",
+        SourceType::Postgres
+            .get_part_query(query, "ctid", 0, 4, false)
+            .unwrap()
+    );
+    // the last partition takes the blocks appended during the read as well
+    assert_eq!(
+        "SELECT * FROM test_table WHERE (test_int < 1000) AND ctid >= CAST('(4,0)' AS tid)",
+        SourceType::Postgres
+            .get_part_query(query, "ctid", 4, 8, true)
+            .unwrap()
+    );
+    assert!(SourceType::Postgres
+        .get_part_query("SELECT * FROM a JOIN b ON a.id = b.id", "ctid", 0, 4, false)
+        .is_err());
+}
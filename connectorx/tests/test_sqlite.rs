@@ -0,0 +1,207 @@
+use connectorx::{
+    destinations::memory::{MemoryDestination, Value},
+    impl_transport,
+    sources::sqlite::{SqliteSource, SqliteTypeSystem},
+    sql::limit_query,
+    ConnectorAgentError, Destination, Dispatcher, DummyTypeSystem, ExtraResults, TypeConversion,
+};
+use rusqlite::Connection;
+use sqlparser::dialect::SQLiteDialect;
+use std::env;
+use std::fs;
+
+struct SqliteMemoryTransport;
+
+impl_transport!(
+    name = SqliteMemoryTransport,
+    systems = SqliteTypeSystem => DummyTypeSystem,
+    route = SqliteSource => MemoryDestination,
+    mappings = {
+        { Int8[i64] => I64[i64] | conversion all }
+        { Real[f64] => F64[f64] | conversion all }
+        { Text[Box<str>] => String[String] | conversion half }
+    }
+);
+
+impl TypeConversion<Box<str>, String> for SqliteMemoryTransport {
+    fn convert(val: Box<str>) -> String {
+        val.to_string()
+    }
+}
+
+// The values of `v` have no declared type, so each query takes the type of its first value.
+fn mixed_numeric_db(name: &str) -> String {
+    let path = env::temp_dir().join(format!("{}_{}.db", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE t(id INTEGER NOT NULL, v);
+         INSERT INTO t VALUES (0, 1), (1, 2), (2, 2.5), (3, NULL), (4, 4);",
+    )
+    .unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+const QUERIES: [&str; 2] = [
+    "SELECT v FROM t WHERE id < 2",
+    "SELECT v FROM t WHERE id >= 2",
+];
+
+#[test]
+fn test_sqlite_numeric_coercion() {
+    let db = mixed_numeric_db("numeric_coercion");
+
+    let mut destination = MemoryDestination::new();
+    let dispatcher = Dispatcher::<_, _, SqliteMemoryTransport>::new(
+        SqliteSource::new(&db, 2).unwrap(),
+        &mut destination,
+        &QUERIES,
+    )
+    .with_numeric_coercion(true);
+    dispatcher.run().expect("run dispatcher");
+
+    assert_eq!(&[DummyTypeSystem::F64(true)], destination.schema());
+    let values: Vec<_> = (0..5).map(|r| destination.row(r).unwrap()).collect();
+    assert_eq!(
+        vec![
+            vec![Value::F64(1.)],
+            vec![Value::F64(2.)],
+            vec![Value::F64(2.5)],
+            vec![Value::Null],
+            vec![Value::F64(4.)],
+        ],
+        values
+    );
+}
+
+#[test]
+fn test_sqlite_without_numeric_coercion() {
+    let db = mixed_numeric_db("no_numeric_coercion");
+
+    // the column is read as the integer the first query has, which 2.5 is not
+    let mut destination = MemoryDestination::new();
+    let dispatcher = Dispatcher::<_, _, SqliteMemoryTransport>::new(
+        SqliteSource::new(&db, 2).unwrap(),
+        &mut destination,
+        &QUERIES,
+    );
+    assert!(dispatcher.run().is_err());
+}
+
+#[test]
+fn test_sqlite_read_one() {
+    let db = mixed_numeric_db("read_one");
+    let read_one = |query: &str| {
+        Dispatcher::<_, _, SqliteMemoryTransport>::read_one_row(
+            SqliteSource::new(&db, 1).unwrap(),
+            query,
+            ExtraResults::Error,
+        )
+    };
+
+    // only two of the five rows are read to tell there are too many
+    assert!(matches!(
+        read_one("SELECT id FROM t"),
+        Err(ConnectorAgentError::TooManyRows(2))
+    ));
+    assert_eq!(
+        Some(vec![Value::I64(4)]),
+        read_one("SELECT id FROM t ORDER BY id DESC LIMIT 1").unwrap()
+    );
+    assert_eq!(
+        Some(vec![Value::I64(3)]),
+        read_one("SELECT id FROM t ORDER BY id LIMIT 1 OFFSET 3").unwrap()
+    );
+
+    let dialect = SQLiteDialect {};
+    assert_eq!(
+        "SELECT id FROM t LIMIT 2",
+        limit_query("SELECT id FROM t LIMIT 10", 2, &dialect).unwrap()
+    );
+    assert_eq!(
+        "SELECT id FROM t LIMIT 1",
+        limit_query("SELECT id FROM t LIMIT 1", 2, &dialect).unwrap()
+    );
+}
+
+#[test]
+fn test_sqlite_null_probes() {
+    let path = env::temp_dir().join(format!("null_probes_{}.db", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE t(id INTEGER NOT NULL, v);
+         INSERT INTO t VALUES (0, NULL), (1, 1), (2, 2.5), (3, NULL);",
+    )
+    .unwrap();
+    let db = path.to_str().unwrap();
+
+    // the null first value of the first query tells nothing, the second query types `v`
+    for coerce in &[true, false] {
+        let mut destination = MemoryDestination::new();
+        Dispatcher::<_, _, SqliteMemoryTransport>::new(
+            SqliteSource::new(db, 2).unwrap(),
+            &mut destination,
+            &QUERIES,
+        )
+        .with_numeric_coercion(*coerce)
+        .run()
+        .expect("run dispatcher");
+        assert_eq!(&[DummyTypeSystem::F64(true)], destination.schema());
+    }
+
+    let mut destination = MemoryDestination::new();
+    let res = Dispatcher::<_, _, SqliteMemoryTransport>::new(
+        SqliteSource::new(db, 1).unwrap(),
+        &mut destination,
+        &["SELECT v FROM t WHERE id = 0"],
+    )
+    .with_numeric_coercion(true)
+    .run();
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_sqlite_diagnostics() {
+    let path = env::temp_dir().join(format!("diagnostics_{}.db", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let conn = Connection::open(&path).unwrap();
+    // the second name is not valid UTF-8
+    conn.execute_batch(
+        "CREATE TABLE users(id INTEGER NOT NULL, name TEXT);
+         INSERT INTO users VALUES (0, 'ann'), (1, CAST(x'c328' AS TEXT));",
+    )
+    .unwrap();
+    // the bad cell is the first of the second partition, the second of the result
+    let queries = [
+        "SELECT id, name FROM users WHERE id = 0",
+        "SELECT id, name FROM users WHERE id = 1",
+    ];
+
+    let mut source = SqliteSource::new(path.to_str().unwrap(), 2).unwrap();
+    source.diagnostics(true);
+    let mut destination = MemoryDestination::new();
+    let err = Dispatcher::<_, _, SqliteMemoryTransport>::new(source, &mut destination, &queries)
+        .run()
+        .unwrap_err();
+    match &err {
+        ConnectorAgentError::CannotDecode {
+            col,
+            row,
+            raw_bytes,
+            ..
+        } => {
+            assert_eq!(("name", 1), (col.as_str(), *row));
+            assert_eq!(&Some(vec![0xc3, 0x28]), raw_bytes);
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+    assert!(err.to_string().contains("raw bytes: c328"), "{}", err);
+
+    // the bytes stay out of the error by default
+    let source = SqliteSource::new(path.to_str().unwrap(), 2).unwrap();
+    let err = Dispatcher::<_, _, SqliteMemoryTransport>::new(source, &mut destination, &queries)
+        .run()
+        .unwrap_err();
+    assert!(!err.to_string().contains("c328"), "{}", err);
+}
@@ -0,0 +1,111 @@
+use arrow::array::{BooleanArray, Float64Array, Int64Array, LargeStringArray};
+use connectorx::{
+    destinations::{
+        arrow::ArrowDestination,
+        memory::{MemoryDestination, Value},
+        tee::TeeDestination,
+    },
+    impl_transport,
+    sources::dummy::DummySource,
+    DataOrder, Destination, Dispatcher, DummyTypeSystem, TypeConversion,
+};
+
+struct DummyTeeTransport;
+
+impl_transport!(
+    name = DummyTeeTransport,
+    systems = DummyTypeSystem => DummyTypeSystem,
+    route = DummySource => TeeDestination<MemoryDestination, ArrowDestination>,
+    mappings = {
+        { F64[f64]       => F64[f64]       | conversion all}
+        { I64[i64]       => I64[i64]       | conversion all}
+        { Bool[bool]     => Bool[bool]     | conversion all}
+        { String[String] => String[String] | conversion all}
+    }
+);
+
+#[test]
+fn test_tee() {
+    let schema = [
+        DummyTypeSystem::I64(false),
+        DummyTypeSystem::F64(true),
+        DummyTypeSystem::Bool(false),
+        DummyTypeSystem::String(true),
+    ];
+    let queries = ["3,4", "5,4"];
+    let mut destination = TeeDestination::new(MemoryDestination::new(), ArrowDestination::new());
+    Dispatcher::<_, _, DummyTeeTransport>::new(
+        DummySource::new(&["a", "b", "c", "d"], &schema),
+        &mut destination,
+        &queries,
+    )
+    .with_partition_column("partition")
+    .run()
+    .expect("run dispatcher");
+
+    let (memory, arrow) = destination.into_inner();
+    let names = ["a", "b", "c", "d", "partition"];
+    let batches = arrow
+        .finish(names.iter().map(|n| n.to_string()).collect())
+        .unwrap();
+    assert_eq!(2, batches.len());
+
+    let mut row = 0;
+    for batch in &batches {
+        let ids = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        let xs = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        let bs = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .unwrap();
+        let ss = batch
+            .column(3)
+            .as_any()
+            .downcast_ref::<LargeStringArray>()
+            .unwrap();
+        let ps = batch
+            .column(4)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        for r in 0..batch.num_rows() {
+            assert_eq!(
+                vec![
+                    Value::I64(ids.value(r)),
+                    Value::F64(xs.value(r)),
+                    Value::Bool(bs.value(r)),
+                    Value::String(ss.value(r).to_string()),
+                    Value::I64(ps.value(r)),
+                ],
+                memory.row(row).unwrap()
+            );
+            row += 1;
+        }
+    }
+    assert_eq!(8, row);
+}
+
+#[test]
+fn test_tee_data_orders() {
+    assert_eq!(
+        &[DataOrder::ColumnMajor, DataOrder::RowMajor],
+        <TeeDestination<ArrowDestination, ArrowDestination> as Destination>::DATA_ORDERS
+    );
+    assert_eq!(
+        &[DataOrder::RowMajor],
+        <TeeDestination<ArrowDestination, MemoryDestination> as Destination>::DATA_ORDERS
+    );
+    assert_eq!(
+        &[DataOrder::RowMajor],
+        <TeeDestination<MemoryDestination, ArrowDestination> as Destination>::DATA_ORDERS
+    );
+}
@@ -0,0 +1,202 @@
+use arrow::array::{Array, TimestampMillisecondArray};
+use chrono::{TimeZone, Utc};
+use connectorx::{
+    destinations::{
+        arrow::ArrowDestination,
+        memory::{MemoryDestination, Value},
+    },
+    sources::trino::TrinoSource,
+    transports::{TrinoArrowTransport, TrinoMemoryTransport},
+    Destination, Dispatcher, DummyTypeSystem,
+};
+use std::time::Duration;
+
+mod common;
+use common::serve;
+
+const COLUMNS: &str = r#"[
+    {"name": "id", "type": "bigint"},
+    {"name": "name", "type": "varchar(10)"},
+    {"name": "price", "type": "decimal(10,2)"},
+    {"name": "tags", "type": "array(varchar)"},
+    {"name": "at", "type": "timestamp(3) with time zone"}
+]"#;
+
+#[test]
+fn test_trino_pages() {
+    let endpoint = serve(|base, method, path, headers, body| {
+        let resp = match (method, path) {
+            ("POST", "/v1/statement") => {
+                assert!(headers.contains(&"x-trino-user: test".to_string()));
+                assert_eq!("select * from items", body);
+                format!(
+                    r#"{{"id": "q1", "nextUri": "{}/v1/statement/queued/q1/1", "stats": {{"state": "QUEUED"}}}}"#,
+                    base
+                )
+            }
+            ("GET", "/v1/statement/queued/q1/1") => format!(
+                r#"{{"id": "q1", "nextUri": "{}/v1/statement/executing/q1/2", "columns": {},
+                    "data": [[1, "a", "1.50", ["x", "y"], "2021-03-04 05:06:07.890 UTC"],
+                             [2, null, "2.25", [], "2021-03-04 05:06:07.000 +01:00"]],
+                    "stats": {{"state": "RUNNING"}}}}"#,
+                base, COLUMNS
+            ),
+            ("GET", "/v1/statement/executing/q1/2") => format!(
+                r#"{{"id": "q1", "nextUri": "{}/v1/statement/executing/q1/3", "columns": {},
+                    "data": [[3, "c", null, null, null]], "stats": {{"state": "RUNNING"}}}}"#,
+                base, COLUMNS
+            ),
+            ("GET", "/v1/statement/executing/q1/3") => format!(
+                r#"{{"id": "q1", "columns": {}, "stats": {{"state": "FINISHED"}}}}"#,
+                COLUMNS
+            ),
+            _ => return (404, "{}".to_string()),
+        };
+        (200, resp)
+    });
+
+    let mut source = TrinoSource::new(&endpoint, "test");
+    source.poll_interval(Duration::from_millis(1));
+    let mut destination = MemoryDestination::new();
+    let metrics = Dispatcher::<_, _, TrinoMemoryTransport>::new(
+        source,
+        &mut destination,
+        &["select * from items"],
+    )
+    .run_with_metrics()
+    .expect("run dispatcher");
+
+    // one partition per page with data
+    assert_eq!(
+        vec![2, 1],
+        metrics
+            .partitions
+            .iter()
+            .map(|p| p.rows)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        &[
+            DummyTypeSystem::I64(true),
+            DummyTypeSystem::String(true),
+            DummyTypeSystem::F64(true),
+            DummyTypeSystem::String(true),
+            DummyTypeSystem::DateTime(true),
+        ],
+        destination.schema()
+    );
+    assert_eq!(
+        vec![
+            Value::I64(1),
+            Value::String("a".into()),
+            Value::F64(1.5),
+            Value::String(r#"["x","y"]"#.into()),
+            Value::DateTime(Utc.ymd(2021, 3, 4).and_hms_milli(5, 6, 7, 890)),
+        ],
+        destination.row(0).unwrap()
+    );
+    assert_eq!(
+        vec![
+            Value::I64(2),
+            Value::Null,
+            Value::F64(2.25),
+            Value::String("[]".into()),
+            Value::DateTime(Utc.ymd(2021, 3, 4).and_hms(4, 6, 7)),
+        ],
+        destination.row(1).unwrap()
+    );
+    assert_eq!(
+        vec![
+            Value::I64(3),
+            Value::String("c".into()),
+            Value::Null,
+            Value::Null,
+            Value::Null,
+        ],
+        destination.row(2).unwrap()
+    );
+}
+
+#[test]
+fn test_trino_arrow() {
+    let endpoint = serve(|base, method, path, _, _| {
+        let columns = r#"[
+            {"name": "d", "type": "date"},
+            {"name": "ts", "type": "timestamp(3)"},
+            {"name": "tstz", "type": "timestamp(3) with time zone"}
+        ]"#;
+        let resp = match (method, path) {
+            ("POST", "/v1/statement") => format!(
+                r#"{{"id": "q3", "nextUri": "{}/v1/statement/executing/q3/1", "stats": {{"state": "QUEUED"}}}}"#,
+                base
+            ),
+            ("GET", "/v1/statement/executing/q3/1") => format!(
+                r#"{{"id": "q3", "columns": {},
+                    "data": [["2021-03-04", "2021-03-04 05:06:07.890", "2021-03-04 05:06:07.000 +01:00"],
+                             [null, null, null]],
+                    "stats": {{"state": "FINISHED"}}}}"#,
+                columns
+            ),
+            _ => return (404, "{}".to_string()),
+        };
+        (200, resp)
+    });
+
+    let mut source = TrinoSource::new(&endpoint, "test");
+    source.poll_interval(Duration::from_millis(1));
+    let mut destination = ArrowDestination::new();
+    Dispatcher::<_, _, TrinoArrowTransport>::new(source, &mut destination, &["select * from t"])
+        .run()
+        .expect("run dispatcher");
+    let records = destination
+        .finish(vec!["d".to_string(), "ts".to_string(), "tstz".to_string()])
+        .unwrap();
+    assert_eq!(1, records.len());
+
+    let expected = [
+        Utc.ymd(2021, 3, 4).and_hms(0, 0, 0),
+        Utc.ymd(2021, 3, 4).and_hms_milli(5, 6, 7, 890),
+        Utc.ymd(2021, 3, 4).and_hms(4, 6, 7),
+    ];
+    for (col, expected) in expected.iter().enumerate() {
+        let values = records[0]
+            .column(col)
+            .as_any()
+            .downcast_ref::<TimestampMillisecondArray>()
+            .unwrap();
+        assert_eq!(expected.timestamp_millis(), values.value(0));
+        assert!(values.is_null(1));
+    }
+}
+
+#[test]
+fn test_trino_query_error() {
+    let endpoint = serve(|base, method, _, _, _| {
+        let resp = match method {
+            "POST" => format!(
+                r#"{{"id": "q2", "nextUri": "{}/v1/statement/queued/q2/1", "stats": {{"state": "QUEUED"}}}}"#,
+                base
+            ),
+            _ => r#"{"id": "q2", "stats": {"state": "FAILED"},
+                     "error": {"message": "line 1:15: Table 'missing' does not exist"}}"#
+                .to_string(),
+        };
+        (200, resp)
+    });
+
+    let mut source = TrinoSource::new(&endpoint, "test");
+    source.poll_interval(Duration::from_millis(1));
+    let mut destination = MemoryDestination::new();
+    let err = Dispatcher::<_, _, TrinoMemoryTransport>::new(
+        source,
+        &mut destination,
+        &["select * from missing"],
+    )
+    .run()
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("Table 'missing' does not exist"),
+        "{}",
+        err
+    );
+}
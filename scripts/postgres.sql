@@ -1,6 +1,8 @@
 DROP TABLE IF EXISTS test_table;
 DROP TABLE IF EXISTS test_str;
 DROP TABLE IF EXISTS test_types;
+DROP TYPE IF EXISTS test_person;
+DROP TYPE IF EXISTS test_address;
 DROP TYPE IF EXISTS happiness;
 
 CREATE TABLE IF NOT EXISTS test_table(
@@ -34,6 +36,8 @@ INSERT INTO test_str VALUES (6, 'Extra', 'y̆');
 INSERT INTO test_str VALUES (7, 'Mixed', 'Ha好ち😁ðy̆');
 
 CREATE TYPE happiness AS ENUM ('happy', 'very happy', 'ecstatic');
+CREATE TYPE test_address AS (street TEXT, zip INTEGER);
+CREATE TYPE test_person AS (name TEXT, score NUMERIC, mood happiness, home test_address);
 CREATE TABLE IF NOT EXISTS test_types(
     test_int16 SMALLINT,
     test_char CHAR,
@@ -55,4 +59,46 @@ CREATE OR REPLACE FUNCTION increment(i integer) RETURNS integer AS $$
     BEGIN
         RETURN i + 1;
     END;
-$$ LANGUAGE plpgsql;
\ No newline at end of file
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION test_table_cursor(min_int integer) RETURNS refcursor AS $$
+    DECLARE
+        c refcursor;
+    BEGIN
+        OPEN c FOR SELECT test_int, test_str FROM test_table WHERE test_int >= min_int ORDER BY test_int;
+        RETURN c;
+    END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION test_table_cursors() RETURNS SETOF refcursor AS $$
+    DECLARE
+        a refcursor;
+        b refcursor;
+    BEGIN
+        OPEN a FOR SELECT test_int FROM test_table;
+        RETURN NEXT a;
+        OPEN b FOR SELECT test_str FROM test_table;
+        RETURN NEXT b;
+    END;
+$$ LANGUAGE plpgsql;
+
+DROP SEQUENCE IF EXISTS test_cursor_calls;
+CREATE SEQUENCE test_cursor_calls;
+
+CREATE OR REPLACE FUNCTION test_query_cursor(query text) RETURNS refcursor AS $$
+    DECLARE
+        c refcursor;
+    BEGIN
+        PERFORM nextval('test_cursor_calls');
+        OPEN c FOR EXECUTE query;
+        RETURN c;
+    END;
+$$ LANGUAGE plpgsql;
+CREATE OR REPLACE FUNCTION truncate_warn(s text, n integer) RETURNS text AS $$
+    BEGIN
+        IF length(s) > n THEN
+            RAISE WARNING 'value "%" truncated to % characters', s, n;
+        END IF;
+        RETURN left(s, n);
+    END;
+$$ LANGUAGE plpgsql;